@@ -1,9 +1,10 @@
 use std::env;
 use std::sync::Arc;
+use std::time::Instant;
 
 use tokio::sync::RwLock;
 
-use serenity::{http::Http, prelude::TypeMap};
+use serenity::{http::Http, model::id::UserId, prelude::TypeMap};
 use warp::{
     body::BodyDeserializeError,
     http::StatusCode,
@@ -13,8 +14,36 @@ use warp::{
 use dbl::types::Webhook;
 use futures_util::future;
 
-use crate::cache::DBLCache;
-use crate::utls::discordhelpers;
+use crate::cache::{DBLCache, ErrorLogCache, HealthMonitorCache, LogThrottleCache, VoterCache};
+use crate::stats::statsmanager;
+use crate::utls::{discordhelpers, throttle};
+
+/// Posts our server count to top.gg, failures are recorded in the health monitor & error log
+pub async fn post_stats(data: &TypeMap, bot_id: u64, server_count: u64, shard_count: u64, context: &str) {
+    if statsmanager::dry_run() {
+        info!("[dry run] POST top.gg stats for {}: {} servers, {} shards", bot_id, server_count, shard_count);
+        return;
+    }
+
+    let new_stats = dbl::types::ShardStats::Cumulative {
+        server_count,
+        shard_count: Some(shard_count)
+    };
+
+    let dbl = data.get::<DBLCache>().unwrap().read().await;
+    let monitor = data.get::<HealthMonitorCache>().unwrap();
+    match dbl.update_stats(bot_id, new_stats).await {
+        Ok(_) => {
+            monitor.lock().unwrap().success("dbl");
+        }
+        Err(e) => {
+            throttle::warn(data.get::<LogThrottleCache>().unwrap(), "Failed to post stats to dbl", "Failed to post stats to dbl");
+            monitor.lock().unwrap().failure("dbl", &format!("{}", e));
+            let mut errors = data.get::<ErrorLogCache>().unwrap().lock().await;
+            errors.push(&format!("{}: dbl stats", context), &format!("{}", e));
+        }
+    }
+}
 
 pub struct BotsListAPI {
     password: String,
@@ -37,8 +66,9 @@ impl BotsListAPI {
         }
     }
 
+    /// The vote channel is optional, votes still count towards the voter cooldown without one
     pub fn should_spawn(&self) -> bool {
-        self.port != 0 && !self.password.is_empty() && self.vote_channel != 0
+        self.port != 0 && !self.password.is_empty()
     }
 
     pub fn spawn(self, http: Arc<Http>, data: Arc<RwLock<TypeMap>>) {
@@ -92,6 +122,24 @@ impl BotsListAPI {
     fn send_vote(user_id: u64, vote_channel: u64, http: Arc<Http>, data: Arc<RwLock<TypeMap>>) {
         tokio::spawn(async move {
             let read = data.read().await;
+            read.get::<VoterCache>().unwrap().lock().await.insert(user_id, Instant::now());
+
+            if thank_voters() {
+                let emb = discordhelpers::build_vote_thanks_embed();
+                let sent = match UserId(user_id).create_dm_channel(&http).await {
+                    Ok(dm) => discordhelpers::send_embed(dm.id, &http, emb).await.is_ok(),
+                    Err(_) => false,
+                };
+                if !sent {
+                    // closed DMs are common, not worth more than a debug line
+                    debug!("Unable to thank voter {}", user_id);
+                }
+            }
+
+            if vote_channel == 0 {
+                return;
+            }
+
             let client_lock = read.get::<DBLCache>().expect("Unable to find dbl data");
             let awd = client_lock.read().await;
 
@@ -107,6 +155,13 @@ impl BotsListAPI {
     }
 }
 
+/// Whether voters get a thank you in their DMs, off unless VOTE_THANKS is set
+fn thank_voters() -> bool {
+    env::var("VOTE_THANKS")
+        .map(|t| t == "1" || t.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
 async fn custom_error(err: Rejection) -> Result<impl Reply, Rejection> {
     if err.find::<BodyDeserializeError>().is_some() {
         Ok(warp::reply::with_status(
@@ -0,0 +1,49 @@
+use std::env;
+
+use crate::utls::constants::{DEFAULT_GODBOLT_URL, DEFAULT_WANDBOX_URL};
+
+// Operators running their own godbolt or wandbox instance point us at it with
+// GODBOLT_URL & WANDBOX_URL. Every godbolt request is sent by hand, compiler lists
+// included (see execution::load_compilers), so GODBOLT_URL covers all of them.
+// The wandbox crate still fetches wandbox's compiler list from the public instance
+
+/// Base url of the godbolt instance we talk to, without a trailing slash
+pub fn godbolt_url() -> String {
+    base_url("GODBOLT_URL", DEFAULT_GODBOLT_URL)
+}
+
+/// Base url of the wandbox instance we talk to, without a trailing slash
+pub fn wandbox_url() -> String {
+    base_url("WANDBOX_URL", DEFAULT_WANDBOX_URL)
+}
+
+/// Warns about configured urls we're unable to use, those fall back to the public instance
+pub fn validate() {
+    for (key, default) in [("GODBOLT_URL", DEFAULT_GODBOLT_URL), ("WANDBOX_URL", DEFAULT_WANDBOX_URL)].iter() {
+        let configured = env::var(key).unwrap_or_default();
+        if configured.is_empty() {
+            continue;
+        }
+
+        if parse(&configured).is_some() {
+            info!("Using {} for {}", configured, key);
+        } else {
+            warn!("Invalid {} '{}', expected an http(s) url. Falling back to {}", key, configured, default);
+        }
+    }
+}
+
+fn base_url(key: &str, default: &str) -> String {
+    env::var(key)
+        .ok()
+        .and_then(|v| parse(&v))
+        .unwrap_or_else(|| String::from(default))
+}
+
+fn parse(value: &str) -> Option<String> {
+    let url = reqwest::Url::parse(value.trim()).ok()?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return None;
+    }
+    Some(url.as_str().trim_end_matches('/').to_string())
+}
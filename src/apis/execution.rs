@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use serde::*;
+use serde_json::{json, Value};
+
+use godbolt::{Compiler, Godbolt, GodboltCacheEntry, Language};
+
+use crate::apis::endpoints;
+
+// The godbolt crate only understands the assembly half of a compilation
+// response, so execution requests are sent by hand and the execution
+// results are picked out of the response ourselves
+
+#[derive(Deserialize, Default)]
+pub struct OutputLine {
+    #[serde(default)]
+    pub text: String,
+}
+
+/// Output of the build godbolt does before executing, this is where linker errors end up
+#[derive(Deserialize, Default)]
+pub struct BuildResult {
+    #[serde(default)]
+    pub code: i32,
+    #[serde(default)]
+    pub stderr: Vec<OutputLine>,
+    #[serde(default, rename = "execTime")]
+    pub exec_time: Value,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionResult {
+    #[serde(default)]
+    pub code: i32,
+    #[serde(default)]
+    pub did_execute: bool,
+    #[serde(default)]
+    pub stdout: Vec<OutputLine>,
+    #[serde(default)]
+    pub stderr: Vec<OutputLine>,
+    #[serde(default)]
+    pub build_result: Option<BuildResult>,
+    #[serde(default)]
+    pub exec_time: Value,
+}
+
+impl ExecutionResult {
+    pub fn stdout_text(&self) -> String {
+        join_lines(&self.stdout)
+    }
+
+    pub fn stderr_text(&self) -> String {
+        join_lines(&self.stderr)
+    }
+
+    /// How long the program ran for, if godbolt timed it
+    pub fn exec_time_ms(&self) -> Option<u64> {
+        millis(&self.exec_time)
+    }
+}
+
+impl BuildResult {
+    pub fn stderr_text(&self) -> String {
+        join_lines(&self.stderr)
+    }
+
+    /// How long the build took, if godbolt timed it
+    pub fn exec_time_ms(&self) -> Option<u64> {
+        millis(&self.exec_time)
+    }
+}
+
+// godbolt reports times in milliseconds, sometimes as a number & sometimes as a string
+fn millis(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => s.parse::<u64>().ok(),
+        _ => None,
+    }
+}
+
+fn join_lines(lines: &[OutputLine]) -> String {
+    lines
+        .iter()
+        .map(|l| l.text.as_str())
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+/// Compiles and executes the source on godbolt, returning both the assembly & the execution results.
+/// Libraries are given as (id, version). Without `execute` nothing is ran & the execution results are empty.
+/// Responses aren't buffered past `limit` bytes, the bool is set when what was received had to be cut off
+pub async fn compile_and_execute(
+    compiler_id: &str,
+    source: &str,
+    options: &str,
+    libraries: &[(&str, &str)],
+    execute: bool,
+    limit: usize,
+) -> Result<(godbolt::CompilationResult, ExecutionResult, bool), Box<dyn Error + Send + Sync>> {
+    let libraries: Vec<Value> = libraries
+        .iter()
+        .map(|(id, version)| json!({ "id": id, "version": version }))
+        .collect();
+    let body = json!({
+        "source": source,
+        "options": {
+            "userArguments": options,
+            "libraries": libraries,
+            "compilerOptions": {},
+            "filters": {
+                "binary": false,
+                "commentOnly": true,
+                "demangle": true,
+                "directives": true,
+                "execute": execute,
+                "intel": true,
+                "labels": true,
+                "libraryCode": false,
+                "trim": true
+            }
+        }
+    });
+
+    let url = format!("{}/api/compiler/{}/compile", endpoints::godbolt_url(), compiler_id);
+    let mut response = reqwest::Client::new()
+        .post(&url)
+        .json(&body)
+        .header(ACCEPT, "application/json")
+        .header(CONTENT_TYPE, "application/json")
+        .send()
+        .await?
+        .error_for_status()?;
+
+    let mut received: Vec<u8> = Vec::new();
+    let mut cut_off = false;
+    while let Some(chunk) = response.chunk().await? {
+        received.extend_from_slice(&chunk);
+        if received.len() > limit {
+            received.truncate(limit);
+            cut_off = true;
+            break;
+        }
+    }
+    let response: Value = if cut_off {
+        match close_truncated(&received) {
+            Some(response) => response,
+            None => return Err(format!("The response was over {} bytes, does the program print too much?", limit).into()),
+        }
+    } else {
+        serde_json::from_slice(&received)?
+    };
+
+    let mut execution: ExecutionResult = match response.get("execResult") {
+        Some(exec) => serde_json::from_value(exec.clone())?,
+        None => ExecutionResult::default(),
+    };
+    // didExecute comes after the program's output, which is where responses get cut off
+    if cut_off && execute && !execution.stdout.is_empty() {
+        execution.did_execute = true;
+    }
+    let compilation = serde_json::from_value(response)?;
+
+    Ok((compilation, execution, cut_off))
+}
+
+// Closes up a response that was cut off, dropping whatever follows the last complete
+// value in an array or object. A value is complete once the comma after it, or the
+// bracket closing it, has been received. None if nothing was complete yet
+fn close_truncated(json: &[u8]) -> Option<Value> {
+    let mut open: Vec<u8> = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    // where the json can be cut off, with the brackets still open there
+    let mut cut: Option<(usize, Vec<u8>)> = None;
+    for (i, b) in json.iter().enumerate() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if *b == b'\\' {
+                escaped = true;
+            } else if *b == b'"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match b {
+            b'"' => in_string = true,
+            b'{' | b'[' => open.push(*b),
+            b'}' | b']' => {
+                open.pop();
+                if !open.is_empty() {
+                    cut = Some((i + 1, open.clone()));
+                }
+            }
+            b',' => cut = Some((i, open.clone())),
+            _ => (),
+        }
+    }
+
+    let (end, open) = cut?;
+    let mut closed = json[..end].to_vec();
+    closed.extend(open.iter().rev().map(|b| if *b == b'{' { b'}' } else { b']' }));
+    serde_json::from_slice(&closed).ok()
+}
+
+/// Our own Godbolt::new, the crate only ever asks the public instance for its languages
+/// & compilers, which would leave us resolving compilers a custom instance may not have
+pub async fn load_compilers() -> Result<Godbolt, Box<dyn Error>> {
+    let languages: Vec<Language> = get_json("/api/languages").await?;
+    let compilers: Vec<Compiler> = get_json("/api/compilers").await?;
+
+    let mut by_language: HashMap<String, Vec<Compiler>> = HashMap::new();
+    for compiler in compilers {
+        by_language.entry(compiler.lang.clone()).or_default().push(compiler);
+    }
+    let cache = languages
+        .into_iter()
+        .map(|language| {
+            let compilers = by_language.remove(&language.id).unwrap_or_default();
+            GodboltCacheEntry { language, compilers }
+        })
+        .collect();
+    Ok(Godbolt { cache })
+}
+
+async fn get_json<T: de::DeserializeOwned>(path: &str) -> Result<T, Box<dyn Error>> {
+    let url = format!("{}{}", endpoints::godbolt_url(), path);
+    let response = reqwest::Client::new()
+        .get(&url)
+        .header(ACCEPT, "application/json")
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(response.json::<T>().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closes_output_cut_off_mid_line() {
+        let json = br#"{"code":0,"execResult":{"code":0,"stdout":[{"text":"one"},{"text":"two"},{"text":"thr"#;
+        let closed = close_truncated(json).unwrap();
+        let exec: ExecutionResult = serde_json::from_value(closed["execResult"].clone()).unwrap();
+        assert_eq!(exec.stdout_text(), "one\ntwo");
+        assert_eq!(closed["code"], 0);
+    }
+
+    #[test]
+    fn keeps_escaped_quotes_in_strings() {
+        let json = br#"{"stdout":[{"text":"say \"hi\", ok"},{"te"#;
+        let closed = close_truncated(json).unwrap();
+        assert_eq!(closed["stdout"][0]["text"], "say \"hi\", ok");
+        assert_eq!(closed["stdout"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn nothing_complete_to_keep() {
+        assert!(close_truncated(br#"{"stdo"#).is_none());
+    }
+}
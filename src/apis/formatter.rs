@@ -0,0 +1,75 @@
+use std::error::Error;
+
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use serde::*;
+use serde_json::json;
+
+use crate::apis::endpoints;
+
+// language -> (godbolt formatter, style), the style is only read by clang-format
+static FORMATTERS: [(&str, (&str, &str)); 8] = [
+    ("c++", ("clangformat", "Google")),
+    ("cpp", ("clangformat", "Google")),
+    ("c", ("clangformat", "Google")),
+    ("rust", ("rustfmt", "")),
+    ("rs", ("rustfmt", "")),
+    ("go", ("gofmt", "")),
+    ("golang", ("gofmt", "")),
+    ("dart", ("dartformat", "")),
+];
+
+#[derive(Deserialize)]
+struct FormatResponse {
+    #[serde(default)]
+    answer: String,
+    #[serde(default)]
+    exit: i32,
+}
+
+/// The formatter godbolt uses for a language, if it has one
+pub fn formatter_for(language: &str) -> Option<&'static str> {
+    FORMATTERS
+        .iter()
+        .find(|(lang, _)| *lang == language)
+        .map(|(_, (formatter, _))| *formatter)
+}
+
+/// All languages we're able to format
+pub fn languages() -> Vec<&'static str> {
+    FORMATTERS.iter().map(|(lang, _)| *lang).collect()
+}
+
+/// Formats the source through godbolt, the outer error is for failed requests while
+/// the inner one holds the formatter's complaint when it rejects the code
+pub async fn format(
+    language: &str,
+    source: &str,
+) -> Result<Result<String, String>, Box<dyn Error + Send + Sync>> {
+    let (formatter, style) = match FORMATTERS.iter().find(|(lang, _)| *lang == language) {
+        Some((_, f)) => *f,
+        None => return Ok(Err(format!("No formatter available for '{}'", language))),
+    };
+
+    let body = json!({
+        "source": source,
+        "base": style,
+        "useSpaces": true,
+        "tabWidth": 4
+    });
+
+    let url = format!("{}/api/format/{}", endpoints::godbolt_url(), formatter);
+    let response: FormatResponse = reqwest::Client::new()
+        .post(&url)
+        .json(&body)
+        .header(ACCEPT, "application/json")
+        .header(CONTENT_TYPE, "application/json")
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    if response.exit != 0 {
+        return Ok(Err(response.answer));
+    }
+    Ok(Ok(response.answer))
+}
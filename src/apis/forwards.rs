@@ -0,0 +1,48 @@
+use serde::*;
+use serenity::http::{request::RequestBuilder, routing::RouteInfo, Http};
+
+// serenity 0.9 predates message forwarding, forwarded messages reach us with
+// no content at all as it lives in a snapshot serenity throws away. We fetch
+// the message again through serenity's http client (for its token & rate limits)
+// but deserialize it ourselves to get at the snapshot
+
+#[derive(Deserialize)]
+struct RawMessage {
+    #[serde(default)]
+    message_snapshots: Vec<MessageSnapshot>,
+}
+
+#[derive(Deserialize)]
+struct MessageSnapshot {
+    message: SnapshotContent,
+}
+
+#[derive(Deserialize)]
+struct SnapshotContent {
+    #[serde(default)]
+    content: String,
+}
+
+/// The content of the message that was forwarded, if the message is a forward at all.
+/// Forwards of several messages are joined together
+pub async fn forwarded_content(http: &Http, channel_id: u64, message_id: u64) -> Option<String> {
+    let request = RequestBuilder::new(RouteInfo::GetMessage { channel_id, message_id }).build();
+    let message: RawMessage = match http.fire(request).await {
+        Ok(m) => m,
+        Err(e) => {
+            debug!("Unable to fetch message {}: {}", message_id, e);
+            return None;
+        }
+    };
+    if message.message_snapshots.is_empty() {
+        return None;
+    }
+
+    let content = message
+        .message_snapshots
+        .iter()
+        .map(|s| s.message.content.as_str())
+        .collect::<Vec<&str>>()
+        .join("\n");
+    Some(content)
+}
@@ -0,0 +1,345 @@
+use std::env;
+
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+use serde::*;
+use serde_json::{json, Value};
+use serenity::builder::CreateEmbed;
+use serenity::model::id::RoleId;
+use serenity::model::user::User;
+
+// serenity 0.9 has no notion of interactions, they reach us as an unknown
+// gateway event and we reply to them through discord's rest api ourselves
+static API_BASE: &str = "https://discord.com/api/v8";
+
+// interaction types
+static INTERACTION_COMPONENT: u64 = 3;
+
+// interaction callback types
+static CALLBACK_MESSAGE: u64 = 4;
+static CALLBACK_DEFERRED_MESSAGE: u64 = 5;
+static CALLBACK_DEFERRED_UPDATE: u64 = 6;
+
+// message component types & button styles
+static COMPONENT_ACTION_ROW: u64 = 1;
+static COMPONENT_BUTTON: u64 = 2;
+static BUTTON_SECONDARY: u64 = 2;
+static BUTTON_DANGER: u64 = 4;
+
+// message flags
+static FLAG_EPHEMERAL: u64 = 1 << 6;
+
+// application command option types
+static OPTION_STRING: u64 = 3;
+
+#[derive(Deserialize)]
+pub struct Interaction {
+    pub id: String,
+    #[serde(rename = "type", default)]
+    pub kind: u64,
+    pub token: String,
+    pub guild_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub member: Option<InteractionMember>,
+    pub user: Option<User>,
+    pub data: Option<InteractionData>,
+}
+
+#[derive(Deserialize)]
+pub struct InteractionMember {
+    pub user: User,
+    #[serde(default)]
+    pub roles: Vec<RoleId>,
+}
+
+#[derive(Deserialize)]
+pub struct InteractionData {
+    /// Set for slash commands
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub options: Vec<InteractionOption>,
+    /// Set for message components, see button
+    #[serde(default)]
+    pub custom_id: String,
+}
+
+#[derive(Deserialize)]
+pub struct InteractionOption {
+    pub name: String,
+    #[serde(default)]
+    pub value: Value,
+}
+
+impl Interaction {
+    /// Guild interactions carry a member, while DMs carry the user directly
+    pub fn author(&self) -> Option<&User> {
+        match &self.member {
+            Some(member) => Some(&member.user),
+            None => self.user.as_ref(),
+        }
+    }
+
+    pub fn command_name(&self) -> &str {
+        match &self.data {
+            Some(data) => &data.name,
+            None => "",
+        }
+    }
+
+    /// Whether this is a click on one of our buttons rather than a slash command
+    pub fn is_component(&self) -> bool {
+        self.kind == INTERACTION_COMPONENT
+    }
+
+    pub fn custom_id(&self) -> &str {
+        match &self.data {
+            Some(data) => &data.custom_id,
+            None => "",
+        }
+    }
+
+    pub fn option(&self, name: &str) -> Option<String> {
+        let data = self.data.as_ref()?;
+        let option = data.options.iter().find(|o| o.name == name)?;
+        option.value.as_str().map(String::from)
+    }
+
+    pub fn guild_id(&self) -> u64 {
+        self.guild_id
+            .as_ref()
+            .and_then(|id| id.parse::<u64>().ok())
+            .unwrap_or_default()
+    }
+
+    pub fn channel_id(&self) -> u64 {
+        self.channel_id
+            .as_ref()
+            .and_then(|id| id.parse::<u64>().ok())
+            .unwrap_or_default()
+    }
+}
+
+fn string_option(name: &str, description: &str, required: bool) -> Value {
+    json!({
+        "type": OPTION_STRING,
+        "name": name,
+        "description": description,
+        "required": required
+    })
+}
+
+/// A button, clicking it sends us an interaction carrying its custom id
+pub fn button(custom_id: &str, label: &str, danger: bool) -> Value {
+    json!({
+        "type": COMPONENT_BUTTON,
+        "style": if danger { BUTTON_DANGER } else { BUTTON_SECONDARY },
+        "label": label,
+        "custom_id": custom_id
+    })
+}
+
+/// Buttons are only accepted inside of a row
+pub fn action_row(buttons: Vec<Value>) -> Value {
+    json!({
+        "type": COMPONENT_ACTION_ROW,
+        "components": buttons
+    })
+}
+
+fn embed_to_json(embed: CreateEmbed) -> Value {
+    Value::Object(serenity::utils::hashmap_to_json_map(embed.0))
+}
+
+/// Overwrites our global application commands with the ones we support
+pub async fn register_commands(application_id: u64) {
+    let commands = json!([
+        {
+            "name": "run",
+            "description": "Compiles and runs a piece of code",
+            "options": [
+                string_option("language", "Language or compiler to use", true),
+                string_option("code", "Code to compile", true),
+                string_option("stdin", "Standard input for the program", false),
+                string_option("options", "Compiler options", false)
+            ]
+        },
+        {
+            "name": "compile",
+            "description": "Compiles a piece of code without showing what it printed",
+            "options": [
+                string_option("language", "Language or compiler to use", true),
+                string_option("code", "Code to compile", true),
+                string_option("options", "Compiler options", false)
+            ]
+        },
+        {
+            "name": "asm",
+            "description": "Displays the assembly output for a piece of code",
+            "options": [
+                string_option("language", "Language or compiler to use", true),
+                string_option("code", "Code to compile", true),
+                string_option("options", "Compiler options", false)
+            ]
+        },
+        {
+            "name": "ping",
+            "description": "Checks if the bot is alive"
+        },
+        {
+            "name": "botinfo",
+            "description": "Information about the bot & where to find it"
+        }
+    ]);
+
+    let token = env::var("BOT_TOKEN").unwrap_or_default();
+    let url = format!("{}/applications/{}/commands", API_BASE, application_id);
+    let result = reqwest::Client::new()
+        .put(&url)
+        .json(&commands)
+        .header(AUTHORIZATION, format!("Bot {}", token))
+        .header(CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    match result {
+        Ok(r) if r.status().is_success() => info!("Registered slash commands"),
+        Ok(r) => warn!("Unable to register slash commands: {}", r.status()),
+        Err(e) => warn!("Unable to register slash commands: {}", e),
+    }
+}
+
+async fn callback(interaction: &Interaction, body: Value) {
+    let url = format!(
+        "{}/interactions/{}/{}/callback",
+        API_BASE, interaction.id, interaction.token
+    );
+    if let Err(e) = reqwest::Client::new().post(&url).json(&body).send().await {
+        warn!("Interaction callback failed: {}", e);
+    }
+}
+
+/// Acknowledges the interaction, giving us up to 15 minutes to edit in a real response
+pub async fn defer(interaction: &Interaction) {
+    callback(interaction, json!({ "type": CALLBACK_DEFERRED_MESSAGE })).await;
+}
+
+/// Acknowledges a button click, the message the button is on is then edited through edit_response
+pub async fn defer_update(interaction: &Interaction) {
+    callback(interaction, json!({ "type": CALLBACK_DEFERRED_UPDATE })).await;
+}
+
+/// What a response is for. Refusals (blocklist, cooldowns) & failures only concern the user
+/// who caused them, so only they get to see those, results are what a command is ran to share
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Reply {
+    Refusal,
+    Failure,
+    Result,
+}
+
+impl Reply {
+    pub fn ephemeral(self) -> bool {
+        self != Reply::Result
+    }
+}
+
+fn message_body(embed: CreateEmbed, ephemeral: bool) -> Value {
+    let flags = if ephemeral { FLAG_EPHEMERAL } else { 0 };
+    json!({
+        "embeds": [embed_to_json(embed)],
+        "flags": flags
+    })
+}
+
+/// Responds to the interaction immediately, ephemeral responses are only visible to the user
+pub async fn respond(interaction: &Interaction, embed: CreateEmbed, ephemeral: bool) {
+    let body = json!({
+        "type": CALLBACK_MESSAGE,
+        "data": message_body(embed, ephemeral)
+    });
+    callback(interaction, body).await;
+}
+
+/// Fills in the response of a previously deferred interaction, for button clicks
+/// that's the message the button is on. `components` are rows from action_row
+pub async fn edit_response(application_id: u64, interaction: &Interaction, embed: CreateEmbed, components: Vec<Value>) {
+    let url = format!(
+        "{}/webhooks/{}/{}/messages/@original",
+        API_BASE, application_id, interaction.token
+    );
+    let body = json!({ "embeds": [embed_to_json(embed)], "components": components });
+    if let Err(e) = reqwest::Client::new().patch(&url).json(&body).send().await {
+        warn!("Unable to edit interaction response: {}", e);
+    }
+}
+
+/// Removes the response of a previously deferred interaction
+pub async fn delete_response(application_id: u64, interaction: &Interaction) {
+    let url = format!(
+        "{}/webhooks/{}/{}/messages/@original",
+        API_BASE, application_id, interaction.token
+    );
+    if let Err(e) = reqwest::Client::new().delete(&url).send().await {
+        warn!("Unable to delete interaction response: {}", e);
+    }
+}
+
+/// Sends an additional message for an interaction we've already responded to
+pub async fn followup(application_id: u64, interaction: &Interaction, embed: CreateEmbed, ephemeral: bool) {
+    let url = format!(
+        "{}/webhooks/{}/{}",
+        API_BASE, application_id, interaction.token
+    );
+    let body = message_body(embed, ephemeral);
+    if let Err(e) = reqwest::Client::new().post(&url).json(&body).send().await {
+        warn!("Unable to send interaction followup: {}", e);
+    }
+}
+
+/// Delivers the final result of a deferred interaction. Successful results replace
+/// the public 'thinking' message along with their buttons, while errors are swapped
+/// out for an ephemeral followup so only the user who ran the command sees them
+pub async fn complete(application_id: u64, interaction: &Interaction, embed: CreateEmbed, ephemeral: bool, components: Vec<Value>) {
+    if ephemeral {
+        delete_response(application_id, interaction).await;
+        followup(application_id, interaction, embed, true).await;
+    } else {
+        edit_response(application_id, interaction, embed, components).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flags(reply: Reply) -> Value {
+        message_body(CreateEmbed::default(), reply.ephemeral())["flags"].clone()
+    }
+
+    #[test]
+    fn refusals_are_ephemeral() {
+        // e.g. the cooldown notice
+        assert!(Reply::Refusal.ephemeral());
+        assert_eq!(flags(Reply::Refusal), json!(64));
+    }
+
+    #[test]
+    fn failures_are_ephemeral() {
+        assert!(Reply::Failure.ephemeral());
+        assert_eq!(flags(Reply::Failure), json!(64));
+    }
+
+    #[test]
+    fn results_are_public() {
+        assert!(!Reply::Result.ephemeral());
+        assert_eq!(flags(Reply::Result), json!(0));
+    }
+
+    #[test]
+    fn body_carries_the_embed() {
+        let mut emb = CreateEmbed::default();
+        emb.description("Pong!");
+        let body = message_body(emb, false);
+        assert_eq!(body["embeds"][0]["description"], json!("Pong!"));
+    }
+}
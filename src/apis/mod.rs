@@ -1 +1,8 @@
 pub mod dbl;
+pub mod endpoints;
+pub mod execution;
+pub mod formatter;
+pub mod forwards;
+pub mod interactions;
+pub mod stream;
+pub mod threads;
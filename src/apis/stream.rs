@@ -0,0 +1,143 @@
+use std::error::Error;
+
+use reqwest::header::CONTENT_TYPE;
+use serde::*;
+use serde_json::{json, Value};
+use tokio::sync::watch;
+
+use crate::apis::endpoints;
+
+// The wandbox crate only knows compile.json, which answers once the program
+// is done. compile.ndjson sends every piece of output as its own json line
+// the moment it's produced, so we speak to it by hand
+
+#[derive(Deserialize, Serialize, Clone)]
+struct StreamEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    data: String,
+}
+
+/// Everything a streamed compilation produced, put back together
+#[derive(Default)]
+pub struct StreamOutput {
+    pub compiler_output: String,
+    pub stdout: String,
+    pub stderr: String,
+    pub status: String,
+    pub signal: String,
+    /// We stopped reading once the output went past our limit, the program never got to finish
+    pub truncated: bool,
+    // the events as we received them, a permlink is made out of these
+    events: Vec<StreamEvent>,
+}
+
+impl StreamOutput {
+    fn apply(&mut self, event: StreamEvent) {
+        match event.kind.as_str() {
+            "CompilerMessageS" | "CompilerMessageE" => self.compiler_output.push_str(&event.data),
+            "StdOut" => self.stdout.push_str(&event.data),
+            "StdErr" => self.stderr.push_str(&event.data),
+            "ExitCode" => self.status = event.data.clone(),
+            "Signal" => self.signal = event.data.clone(),
+            _ => (),
+        }
+        self.events.push(event);
+    }
+
+    fn len(&self) -> usize {
+        self.compiler_output.len() + self.stdout.len() + self.stderr.len()
+    }
+}
+
+fn request_body(compiler: &str, code: &str, options: &[String], switches: &[String], stdin: &str) -> Value {
+    json!({
+        "compiler": compiler,
+        "code": code,
+        "stdin": stdin,
+        "options": switches.join(","),
+        "compiler-option-raw": options.join("\n"),
+        "save": false
+    })
+}
+
+/// Compiles & runs the code on wandbox, broadcasting the program's output so far
+/// every time more of it arrives. Resolves once the program has finished, or as soon
+/// as there's more than `limit` bytes of output so endless output can't pile up.
+/// `switches` are wandbox's own compiler switches, which is how libraries are selected
+pub async fn compile_ndjson(
+    compiler: &str,
+    code: &str,
+    options: &[String],
+    switches: &[String],
+    stdin: &str,
+    limit: usize,
+    updates: watch::Sender<String>,
+) -> Result<StreamOutput, Box<dyn Error + Send + Sync>> {
+    let body = request_body(compiler, code, options, switches, stdin);
+    let mut response = reqwest::Client::new()
+        .post(&format!("{}/api/compile.ndjson", endpoints::wandbox_url()))
+        .json(&body)
+        .header(CONTENT_TYPE, "application/json")
+        .send()
+        .await?
+        .error_for_status()?;
+
+    let mut output = StreamOutput::default();
+    let mut buffer: Vec<u8> = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        buffer.extend_from_slice(&chunk);
+
+        // chunks don't line up with events, only complete lines get parsed
+        let mut received = false;
+        while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            if let Ok(event) = serde_json::from_slice::<StreamEvent>(&line) {
+                output.apply(event);
+                received = true;
+            }
+        }
+
+        if received && updates.broadcast(output.stdout.clone()).is_err() {
+            // nobody is watching anymore, the final output still matters though
+        }
+
+        // dropping the response hangs up on wandbox, the program's killed along with it
+        if output.len() > limit {
+            output.truncated = true;
+            break;
+        }
+    }
+
+    Ok(output)
+}
+
+/// Saves a finished compilation as a wandbox permlink, returning its url. This is
+/// where the output we had to cut off from our embeds can still be read
+pub async fn permlink(
+    compiler: &str,
+    code: &str,
+    options: &[String],
+    switches: &[String],
+    stdin: &str,
+    output: &StreamOutput,
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let mut body = request_body(compiler, code, options, switches, stdin);
+    body["results"] = serde_json::to_value(&output.events)?;
+
+    let response: Value = reqwest::Client::new()
+        .post(&format!("{}/api/permlink", endpoints::wandbox_url()))
+        .json(&body)
+        .header(CONTENT_TYPE, "application/json")
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    match response.get("url").and_then(|u| u.as_str()) {
+        Some(url) => Ok(String::from(url)),
+        None => Err("wandbox didn't give us a permlink".into()),
+    }
+}
@@ -0,0 +1,55 @@
+use std::env;
+
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+use serde::*;
+
+// serenity 0.9 predates threads, so we talk to discord directly to
+// create them. Once created, a thread is just another channel to us.
+static THREAD_ENDPOINT: &str = "https://discord.com/api/v9/channels";
+
+#[derive(Serialize)]
+struct StartThreadRequest<'a> {
+    name: &'a str,
+    auto_archive_duration: u64,
+}
+
+#[derive(Deserialize)]
+struct ThreadChannel {
+    id: String,
+}
+
+/// Starts a thread off of an existing message, returning the new thread's id.
+/// A `None` result means the guild or channel doesn't support threads (or we
+/// lack the permissions to create one) and callers should post inline instead
+pub async fn start_thread(channel_id: u64, message_id: u64, name: &str) -> Option<u64> {
+    let token = env::var("BOT_TOKEN").ok()?;
+    let url = format!("{}/{}/messages/{}/threads", THREAD_ENDPOINT, channel_id, message_id);
+
+    let request = StartThreadRequest {
+        name,
+        auto_archive_duration: 60,
+    };
+
+    let response = match reqwest::Client::new()
+        .post(&url)
+        .json(&request)
+        .header(AUTHORIZATION, format!("Bot {}", token))
+        .header(CONTENT_TYPE, "application/json")
+        .send()
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            warn!("Thread creation request failed: {}", e);
+            return None;
+        }
+    };
+
+    if !response.status().is_success() {
+        debug!("Unable to start thread in {}: {}", channel_id, response.status());
+        return None;
+    }
+
+    let thread: ThreadChannel = response.json().await.ok()?;
+    thread.id.parse::<u64>().ok()
+}
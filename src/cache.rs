@@ -1,7 +1,8 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::sync::Arc;
 use std::error::Error;
+use std::time::Instant;
 
 use tokio::sync::RwLock;
 
@@ -9,9 +10,26 @@ use serenity::prelude::{TypeMap, TypeMapKey};
 use serenity::futures::lock::Mutex;
 use serenity::model::id::UserId;
 use serenity::client::bridge::gateway::ShardManager;
+use serenity::builder::CreateEmbed;
+use serenity::framework::Framework;
 
+use crate::apis::execution;
+use crate::stats::metrics::Metrics;
 use crate::stats::statsmanager::StatsManager;
 use crate::utls::blocklist::Blocklist;
+use crate::utls::schedule::Schedule;
+use crate::utls::expiry::Expirations;
+use crate::utls::constants::{COMMAND_CONTENT_CACHE_SIZE, EXPAND_CACHE_SIZE, OUTPUT_RECORD_SIZE, RERUN_CACHE_SIZE, RUNTIME_VERSION_CACHE_SIZE, SUPPORTER_LOOKUP_CACHE_SIZE, VOTER_CACHE_SIZE};
+use crate::utls::errorlog::ErrorLog;
+use crate::utls::guildlog::GuildLog;
+use crate::utls::limiter::{CompileLimiter, GuildRateLimiter};
+use crate::utls::monitor::HealthMonitor;
+use crate::utls::profiles::Profiles;
+use crate::utls::reports::OutputRecord;
+use crate::utls::parser::ParserResult;
+use crate::utls::sessions::SessionLog;
+use crate::utls::settings::Settings;
+use crate::utls::throttle::LogThrottle;
 
 use godbolt::Godbolt;
 use wandbox::Wandbox;
@@ -26,6 +44,12 @@ impl TypeMapKey for ConfigCache {
     type Value = Arc<RwLock<HashMap<&'static str, String>>>;
 }
 
+/// The bot's owners, as registered with the framework
+pub struct OwnersCache;
+impl TypeMapKey for OwnersCache {
+    type Value = Arc<HashSet<UserId>>;
+}
+
 /// The cache of all compilers/languages from wandbox - along with our bindings for their api
 pub struct WandboxCache;
 impl TypeMapKey for WandboxCache {
@@ -56,6 +80,147 @@ impl TypeMapKey for BlocklistCache {
     type Value = Arc<RwLock<Blocklist>>;
 }
 
+/// Upcoming & running maintenance windows
+pub struct ScheduleCache;
+impl TypeMapKey for ScheduleCache {
+    type Value = Arc<RwLock<Schedule>>;
+}
+
+/// Why compiling has been switched off, if it has. Set when the primary backend fails
+/// its startup self-test, or by an owner through ;killswitch
+pub struct KillSwitchCache;
+impl TypeMapKey for KillSwitchCache {
+    type Value = Arc<RwLock<Option<String>>>;
+}
+
+/// Outputs waiting to be deleted once their guild's expiry is up
+pub struct ExpiryCache;
+impl TypeMapKey for ExpiryCache {
+    type Value = Arc<tokio::sync::Mutex<Expirations>>;
+}
+
+/// Persistent guild & channel level settings configured by server admins
+pub struct SettingsCache;
+impl TypeMapKey for SettingsCache {
+    type Value = Arc<RwLock<Settings>>;
+}
+
+/// Last time each user ran a slash command, since interactions don't go through the framework's buckets
+pub struct InteractionCooldownCache;
+impl TypeMapKey for InteractionCooldownCache {
+    type Value = Arc<tokio::sync::Mutex<LruCache<u64, Instant>>>;
+}
+
+/// The supporter cooldown worked out for each recent message, nospam_check & before both need
+/// it and a booster check can take a trip to discord, see discordhelpers::supporter_cooldown
+pub struct SupporterLookupCache;
+impl TypeMapKey for SupporterLookupCache {
+    type Value = Arc<tokio::sync::Mutex<LruCache<u64, Option<u8>>>>;
+}
+
+/// When each user last voted for us on top.gg, recent voters get a reduced cooldown
+pub struct VoterCache;
+impl TypeMapKey for VoterCache {
+    type Value = Arc<tokio::sync::Mutex<LruCache<u64, Instant>>>;
+}
+
+/// Last time each supporter ran a compile command, their reduced cooldown is tracked by us instead of the framework
+pub struct SupporterCooldownCache;
+impl TypeMapKey for SupporterCooldownCache {
+    type Value = Arc<tokio::sync::Mutex<LruCache<u64, Instant>>>;
+}
+
+/// User id -> when their last message was compiled in an auto-compile channel. These
+/// skip the framework, so its nospam bucket doesn't cover them
+pub struct AutoCompileCooldownCache;
+impl TypeMapKey for AutoCompileCooldownCache {
+    type Value = Arc<tokio::sync::Mutex<LruCache<u64, Instant>>>;
+}
+
+/// Recent compile requests of each guild, for the guild wide rate limit
+pub struct GuildRateLimitCache;
+impl TypeMapKey for GuildRateLimitCache {
+    type Value = Arc<tokio::sync::Mutex<GuildRateLimiter>>;
+}
+
+/// Rolling log of recent command & event errors, viewable by owners
+pub struct ErrorLogCache;
+impl TypeMapKey for ErrorLogCache {
+    type Value = Arc<tokio::sync::Mutex<ErrorLog>>;
+}
+
+/// Limits the amount of simultaneous requests we send to our compilation backends
+pub struct CompileLimiterCache;
+impl TypeMapKey for CompileLimiterCache {
+    type Value = Arc<CompileLimiter>;
+}
+
+/// Repeated warnings waiting to be summarized, see utls::throttle
+pub struct LogThrottleCache;
+impl TypeMapKey for LogThrottleCache {
+    type Value = Arc<std::sync::Mutex<LogThrottle>>;
+}
+
+/// Consecutive failure tracking for the stats & bot list services we post to
+pub struct HealthMonitorCache;
+impl TypeMapKey for HealthMonitorCache {
+    type Value = Arc<std::sync::Mutex<HealthMonitor>>;
+}
+
+/// Counters served to Prometheus, see stats::metrics
+pub struct MetricsCache;
+impl TypeMapKey for MetricsCache {
+    type Value = Arc<std::sync::Mutex<Metrics>>;
+}
+
+/// Per-language runtime limits for compilations
+pub struct ProfilesCache;
+impl TypeMapKey for ProfilesCache {
+    type Value = Arc<Profiles>;
+}
+
+/// Bot output message id -> the request behind it, used for reporting outputs
+pub struct OutputRecordCache;
+impl TypeMapKey for OutputRecordCache {
+    type Value = Arc<tokio::sync::Mutex<LruCache<u64, OutputRecord>>>;
+}
+
+/// Run output message id -> (author, request), so a run can be repeated with new input
+pub struct RerunCache;
+impl TypeMapKey for RerunCache {
+    type Value = Arc<tokio::sync::Mutex<LruCache<u64, (u64, ParserResult)>>>;
+}
+
+/// Most recent guild joins & leaves for ;recentguilds
+pub struct GuildLogCache;
+impl TypeMapKey for GuildLogCache {
+    type Value = Arc<tokio::sync::Mutex<GuildLog>>;
+}
+
+/// Session ids & reconnects of each shard, viewable by owners
+pub struct SessionCache;
+impl TypeMapKey for SessionCache {
+    type Value = Arc<tokio::sync::Mutex<SessionLog>>;
+}
+
+/// Command message id -> (author, results, record) of minimal mode runs that haven't been expanded yet
+pub struct ExpandCache;
+impl TypeMapKey for ExpandCache {
+    type Value = Arc<tokio::sync::Mutex<LruCache<u64, (u64, CreateEmbed, OutputRecord)>>>;
+}
+
+/// Guild ids we've received a guild_create for, so reconnects aren't mistaken for new joins
+pub struct SeenGuildsCache;
+impl TypeMapKey for SeenGuildsCache {
+    type Value = Arc<tokio::sync::Mutex<HashSet<u64>>>;
+}
+
+/// Language or compiler -> the wandbox compiler it resolves to, for ;version
+pub struct RuntimeVersionCache;
+impl TypeMapKey for RuntimeVersionCache {
+    type Value = Arc<tokio::sync::Mutex<LruCache<String, wandbox::Compiler>>>;
+}
+
 /// Contains the shard manager - used to send global presence updates
 pub struct ShardManagerCache;
 impl TypeMapKey for ShardManagerCache {
@@ -68,11 +233,32 @@ impl TypeMapKey for MessageDeleteCache {
     type Value = Arc<tokio::sync::Mutex<LruCache<u64, Message>>>;
 }
 
+/// What compile commands last ran with, so an edit can tell if anything changed, see events::rerun_edited
+pub struct CommandContentCache;
+impl TypeMapKey for CommandContentCache {
+    type Value = Arc<tokio::sync::Mutex<LruCache<u64, String>>>;
+}
+
+/// The framework our commands are dispatched through, so edited commands can be ran again
+pub struct FrameworkCache;
+impl TypeMapKey for FrameworkCache {
+    type Value = Arc<Box<dyn Framework + Send + Sync>>;
+}
+
+/// Each user's most recent compilation request, kept briefly so it can be re-ran with ;swap
+pub struct LastSourceCache;
+impl TypeMapKey for LastSourceCache {
+    type Value = Arc<tokio::sync::Mutex<LruCache<u64, (Instant, ParserResult)>>>;
+}
+
 pub async fn fill(
     data: Arc<RwLock<TypeMap>>,
     prefix: &str,
     id: &UserId,
-    shard_manager: Arc<tokio::sync::Mutex<ShardManager>>
+    owners: HashSet<UserId>,
+    shard_count: u64,
+    shard_manager: Arc<tokio::sync::Mutex<ShardManager>>,
+    framework: Arc<Box<dyn Framework + Send + Sync>>
 ) -> Result<(), Box<dyn Error>> {
     let mut data = data.write().await;
 
@@ -87,26 +273,54 @@ pub async fn fill(
     map.insert("JOIN_LOG", env::var("JOIN_LOG")?);
     map.insert("BOT_PREFIX", String::from(prefix));
     map.insert("BOT_ID", id.to_string());
+    map.insert("SHARD_COUNT", shard_count.to_string());
     data.insert::<ConfigCache>(Arc::new(RwLock::new(map)));
 
+    // Owners, so hooks can tell who may bypass guild level restrictions
+    data.insert::<OwnersCache>(Arc::new(owners));
+
     // Shard manager for universal presence
     data.insert::<ShardManagerCache>(shard_manager);
+    data.insert::<FrameworkCache>(framework);
 
     // Wandbox
-    let mut broken_compilers = std::collections::HashSet::new();
+    let mut broken_compilers = HashSet::new();
     broken_compilers.insert(String::from("ghc-head"));
     broken_compilers.insert(String::from("go-head"));
-    let mut broken_languages = std::collections::HashSet::new();
+    let mut broken_languages = HashSet::new();
     broken_languages.insert(String::from("cpp"));
     let wbox = wandbox::Wandbox::new(Some(broken_compilers), Some(broken_languages)).await?;
     info!("WandBox cache loaded");
     data.insert::<WandboxCache>(Arc::new(RwLock::new(wbox)));
+    data.insert::<RuntimeVersionCache>(Arc::new(tokio::sync::Mutex::new(LruCache::new(RUNTIME_VERSION_CACHE_SIZE))));
 
     // Message delete cache
     data.insert::<MessageDeleteCache>(Arc::new(tokio::sync::Mutex::new(LruCache::new(10))));
+    data.insert::<CommandContentCache>(Arc::new(tokio::sync::Mutex::new(LruCache::new(COMMAND_CONTENT_CACHE_SIZE))));
+
+    // Output records for moderation reports
+    data.insert::<OutputRecordCache>(Arc::new(tokio::sync::Mutex::new(LruCache::new(OUTPUT_RECORD_SIZE))));
+
+    // Runs which can be repeated with new input
+    data.insert::<RerunCache>(Arc::new(tokio::sync::Mutex::new(LruCache::new(RERUN_CACHE_SIZE))));
+    data.insert::<ExpandCache>(Arc::new(tokio::sync::Mutex::new(LruCache::new(EXPAND_CACHE_SIZE))));
+    data.insert::<SeenGuildsCache>(Arc::new(tokio::sync::Mutex::new(HashSet::new())));
+    data.insert::<GuildLogCache>(Arc::new(tokio::sync::Mutex::new(GuildLog::new())));
+    data.insert::<SessionCache>(Arc::new(tokio::sync::Mutex::new(SessionLog::new())));
+
+    // Last source cache
+    data.insert::<LastSourceCache>(Arc::new(tokio::sync::Mutex::new(LruCache::new(250))));
+
+    // Slash command cooldowns
+    data.insert::<InteractionCooldownCache>(Arc::new(tokio::sync::Mutex::new(LruCache::new(1000))));
+    data.insert::<SupporterCooldownCache>(Arc::new(tokio::sync::Mutex::new(LruCache::new(1000))));
+    data.insert::<SupporterLookupCache>(Arc::new(tokio::sync::Mutex::new(LruCache::new(SUPPORTER_LOOKUP_CACHE_SIZE))));
+    data.insert::<AutoCompileCooldownCache>(Arc::new(tokio::sync::Mutex::new(LruCache::new(1000))));
+    data.insert::<VoterCache>(Arc::new(tokio::sync::Mutex::new(LruCache::new(VOTER_CACHE_SIZE))));
+    data.insert::<GuildRateLimitCache>(Arc::new(tokio::sync::Mutex::new(GuildRateLimiter::new())));
 
     // Godbolt
-    let godbolt = Godbolt::new().await?;
+    let godbolt = execution::load_compilers().await?;
     info!("Godbolt cache loaded");
     data.insert::<GodboltCache>(Arc::new(RwLock::new(godbolt)));
 
@@ -116,15 +330,123 @@ pub async fn fill(
     data.insert::<DBLCache>(Arc::new(RwLock::new(client)));
 
     // Stats tracking
-    let stats = StatsManager::new();
+    let monitor = Arc::new(std::sync::Mutex::new(HealthMonitor::new()));
+    data.insert::<HealthMonitorCache>(monitor.clone());
+    let throttle = Arc::new(std::sync::Mutex::new(LogThrottle::new()));
+    data.insert::<LogThrottleCache>(throttle.clone());
+    let stats = StatsManager::new(monitor, throttle);
     if stats.should_track() {
         info!("Statistics tracking enabled");
     }
     data.insert::<StatsManagerCache>(Arc::new(Mutex::new(stats)));
+    data.insert::<MetricsCache>(Arc::new(std::sync::Mutex::new(Metrics::new())));
 
     // Blocklist
     let blocklist = Blocklist::new();
     data.insert::<BlocklistCache>(Arc::new(RwLock::new(blocklist)));
 
+    // Maintenance schedule
+    data.insert::<ScheduleCache>(Arc::new(RwLock::new(Schedule::new())));
+    data.insert::<KillSwitchCache>(Arc::new(RwLock::new(None)));
+    data.insert::<ExpiryCache>(Arc::new(tokio::sync::Mutex::new(Expirations::new())));
+
+    // Recent errors
+    data.insert::<ErrorLogCache>(Arc::new(tokio::sync::Mutex::new(ErrorLog::new())));
+    data.insert::<CompileLimiterCache>(Arc::new(CompileLimiter::new()));
+    data.insert::<ProfilesCache>(Arc::new(Profiles::new()));
+
+    // Guild & channel settings
+    let settings = Settings::new();
+    data.insert::<SettingsCache>(Arc::new(RwLock::new(settings)));
+
+    // handlers only log a missing cache, so catch a forgotten insert before we go live
+    let missing = missing_caches(&data);
+    if !missing.is_empty() {
+        return Err(format!("Caches missing after initialization: {}", missing.join(", ")).into());
+    }
+
     Ok(())
 }
+
+/// Looks up one of our caches, logging which one it was if it was never inserted.
+/// Handlers bail out on None rather than taking the whole shard down with a panic
+pub fn get_cache<T: TypeMapKey>(data: &TypeMap) -> Option<&T::Value> {
+    let cache = data.get::<T>();
+    if cache.is_none() {
+        error!("Cache {} not initialized", cache_name::<T>());
+    }
+    cache
+}
+
+/// get_cache for event handlers & our background tasks, which have nowhere to report a
+/// missing cache to. get_cache has logged it, so they stop there
+macro_rules! cache_or_return {
+    ($data:expr, $cache:ty) => {
+        match $crate::cache::get_cache::<$cache>($data) {
+            Some(cache) => cache,
+            None => return,
+        }
+    };
+    ($data:expr, $cache:ty, $ret:expr) => {
+        match $crate::cache::get_cache::<$cache>($data) {
+            Some(cache) => cache,
+            None => return $ret,
+        }
+    };
+}
+
+// every cache fill is expected to insert, checked once it's done
+fn missing_caches(data: &TypeMap) -> Vec<&'static str> {
+    let caches = [
+        present::<ConfigCache>(data),
+        present::<OwnersCache>(data),
+        present::<WandboxCache>(data),
+        present::<GodboltCache>(data),
+        present::<DBLCache>(data),
+        present::<StatsManagerCache>(data),
+        present::<BlocklistCache>(data),
+        present::<ScheduleCache>(data),
+        present::<KillSwitchCache>(data),
+        present::<ExpiryCache>(data),
+        present::<SettingsCache>(data),
+        present::<InteractionCooldownCache>(data),
+        present::<VoterCache>(data),
+        present::<SupporterCooldownCache>(data),
+        present::<SupporterLookupCache>(data),
+        present::<AutoCompileCooldownCache>(data),
+        present::<GuildRateLimitCache>(data),
+        present::<ErrorLogCache>(data),
+        present::<CompileLimiterCache>(data),
+        present::<LogThrottleCache>(data),
+        present::<HealthMonitorCache>(data),
+        present::<MetricsCache>(data),
+        present::<ProfilesCache>(data),
+        present::<OutputRecordCache>(data),
+        present::<RerunCache>(data),
+        present::<GuildLogCache>(data),
+        present::<SessionCache>(data),
+        present::<ExpandCache>(data),
+        present::<SeenGuildsCache>(data),
+        present::<RuntimeVersionCache>(data),
+        present::<ShardManagerCache>(data),
+        present::<MessageDeleteCache>(data),
+        present::<CommandContentCache>(data),
+        present::<FrameworkCache>(data),
+        present::<LastSourceCache>(data),
+    ];
+    caches.iter().filter_map(|c| *c).collect()
+}
+
+fn present<T: TypeMapKey>(data: &TypeMap) -> Option<&'static str> {
+    if data.contains_key::<T>() {
+        None
+    } else {
+        Some(cache_name::<T>())
+    }
+}
+
+// crate::cache::SettingsCache -> SettingsCache
+fn cache_name<T>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    name.rsplit("::").next().unwrap_or(name)
+}
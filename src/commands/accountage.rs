@@ -0,0 +1,46 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::SettingsCache;
+use crate::utls::constants::MAX_MIN_ACCOUNT_AGE;
+use crate::utls::discordhelpers;
+
+#[command]
+#[only_in(guilds)]
+pub async fn accountage(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    if !discordhelpers::is_admin(ctx, msg).await {
+        return Err(CommandError::from(
+            "Only server administrators may change the minimum account age",
+        ));
+    }
+
+    let usage = "Usage: `accountage <days | off>`, e.g. `accountage 7`";
+    let days = match args.current().map(|s| s.to_lowercase()).as_deref() {
+        Some("off") => None,
+        Some(days) => match days.parse::<u64>() {
+            Ok(d) if d > 0 && d <= MAX_MIN_ACCOUNT_AGE => Some(d),
+            Ok(_) => {
+                return Err(CommandError::from(format!(
+                    "The minimum account age can be at most {} days",
+                    MAX_MIN_ACCOUNT_AGE
+                )));
+            }
+            Err(_) => return Err(CommandError::from(usage)),
+        },
+        None => return Err(CommandError::from(usage)),
+    };
+
+    let data = ctx.data.read().await;
+    let mut settings = data.get::<SettingsCache>().unwrap().write().await;
+    settings.guild_mut(msg.guild_id.unwrap().0).min_account_age = days;
+    settings.write();
+
+    let reply = match days {
+        Some(d) => format!("Accounts younger than {} day(s) can no longer compile code here, unless they recently voted for us", d),
+        None => String::from("Accounts of any age may compile code again"),
+    };
+    msg.channel_id.say(&ctx.http, reply).await?;
+    debug!("Command executed");
+    Ok(())
+}
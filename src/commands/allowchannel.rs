@@ -0,0 +1,68 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::SettingsCache;
+use crate::utls::discordhelpers;
+
+#[command]
+#[only_in(guilds)]
+pub async fn allowchannel(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let (guild_id, channel) = resolve_args(ctx, msg, &mut args).await?;
+
+    let data = ctx.data.read().await;
+    let mut settings = data.get::<SettingsCache>().unwrap().write().await;
+    settings.guild_mut(guild_id).allowed_channels.insert(channel.0);
+    settings.write();
+
+    msg.channel_id.say(&ctx.http, format!("Compiling is now allowed in <#{}>", channel.0)).await?;
+    debug!("Command executed");
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn denychannel(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let (guild_id, channel) = resolve_args(ctx, msg, &mut args).await?;
+
+    let data = ctx.data.read().await;
+    let mut settings = data.get::<SettingsCache>().unwrap().write().await;
+    let guild = settings.guild_mut(guild_id);
+    guild.allowed_channels.remove(&channel.0);
+    let remaining = guild.allowed_channels.len();
+    settings.write();
+
+    let reply = if remaining == 0 {
+        String::from("No channel restrictions remain, compiling is allowed everywhere")
+    } else {
+        format!("Compiling is no longer allowed in <#{}>", channel.0)
+    };
+    msg.channel_id.say(&ctx.http, reply).await?;
+    debug!("Command executed");
+    Ok(())
+}
+
+// Both commands are admin only & take an optional channel, defaulting to the current one
+async fn resolve_args(ctx: &Context, msg: &Message, args: &mut Args) -> Result<(u64, ChannelId), CommandError> {
+    if !discordhelpers::is_admin(ctx, msg).await {
+        return Err(CommandError::from(
+            "Only server administrators may change channel restrictions",
+        ));
+    }
+
+    let guild_id = match msg.guild_id {
+        Some(id) => id.0,
+        None => return Err(CommandError::from("This command can only be used in a server")),
+    };
+
+    if args.is_empty() {
+        return Ok((guild_id, msg.channel_id));
+    }
+
+    match args.single::<ChannelId>() {
+        Ok(channel) => Ok((guild_id, channel)),
+        Err(_e) => Err(CommandError::from(
+            "Invalid channel, please mention a channel like #bot-spam",
+        )),
+    }
+}
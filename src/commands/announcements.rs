@@ -0,0 +1,42 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::SettingsCache;
+use crate::utls::discordhelpers;
+
+#[command]
+#[only_in(guilds)]
+pub async fn announcements(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    if !discordhelpers::is_admin(ctx, msg).await {
+        return Err(CommandError::from(
+            "Only server administrators may change where announcements are posted",
+        ));
+    }
+
+    let channel = match args.current().map(|s| s.to_lowercase()).as_deref() {
+        Some("off") => None,
+        _ => match args.single::<ChannelId>() {
+            Ok(c) => Some(c.0),
+            Err(_) => return Err(CommandError::from("Usage: `announcements <#channel | off>`")),
+        },
+    };
+    if let Some(c) = channel {
+        if !discordhelpers::is_guild_channel(ctx, msg.guild_id.unwrap(), ChannelId(c)).await {
+            return Err(CommandError::from("Announcements can only be posted in one of this server's channels"));
+        }
+    }
+
+    let data = ctx.data.read().await;
+    let mut settings = data.get::<SettingsCache>().unwrap().write().await;
+    settings.guild_mut(msg.guild_id.unwrap().0).announcement_channel = channel;
+    settings.write();
+
+    let reply = match channel {
+        Some(c) => format!("Announcements like planned maintenance will be posted in <#{}>", c),
+        None => String::from("This server will no longer receive announcements"),
+    };
+    msg.channel_id.say(&ctx.http, reply).await?;
+    debug!("Command executed");
+    Ok(())
+}
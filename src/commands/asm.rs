@@ -1,3 +1,5 @@
+use std::time::Instant;
+
 use serenity::{
     client::Context,
     framework::standard::{macros::command, Args, CommandError, CommandResult},
@@ -5,46 +7,37 @@ use serenity::{
 };
 use serenity_utils::menu::Menu;
 
-use godbolt::*;
-
-use crate::cache::{GodboltCache, ConfigCache, MessageDeleteCache};
+use crate::apis::execution;
+use crate::commands::compile;
+use crate::cache::{CompileLimiterCache, GodboltCache, ConfigCache, MessageDeleteCache, SettingsCache, ProfilesCache};
 use crate::utls::constants::*;
+use crate::utls::compileresult::{self, CompileResult};
 use crate::utls::parser::*;
+use crate::utls::reports::OutputRecord;
 use crate::utls::{discordhelpers, parser};
 
 #[command]
 #[sub_commands(compilers, languages)]
 #[bucket = "nospam"]
 pub async fn asm(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let default_target = {
+        let data_read = ctx.data.read().await;
+        let settings = data_read.get::<SettingsCache>().unwrap().read().await;
+        settings.default_language(msg.guild_id.map(|g| g.0).unwrap_or_default(), msg.channel_id.0)
+    };
+
     // parse user input
-    let result: ParserResult = match parser::get_components(&msg.content, &msg.author).await {
+    let file = compile::attachment_source(msg).await?;
+    let mut result: ParserResult = match parser::get_components_with_file(&msg.content, &msg.author, default_target, file).await {
         Ok(r) => r,
         Err(e) => {
             return Err(CommandError::from(format!("{}", e)));
         }
     };
 
-    // aquire lock to our godbolt cache
-    let data_read = ctx.data.read().await;
-    let godbolt_lock = match data_read.get::<GodboltCache>() {
-        Some(l) => l,
-        None => {
-            return Err(CommandError::from(
-                "Internal request failure\nGodbolt cache is uninitialized, please file a bug.",
-            ));
-        }
-    };
-    let godbolt = godbolt_lock.read().await;
-
-    let c = match godbolt.resolve(&result.target) {
-        Some(c) => c,
-        None => {
-            return Err(CommandError::from(format!(
-                "Unable to find valid compiler or language '{}'\n",
-                &result.target
-            )));
-        }
-    };
+    // --exec is ours, not the compiler's
+    let execute = result.options.iter().any(|o| o == "--exec");
+    result.options.retain(|o| o != "--exec");
 
     // send out loading emote
     let reaction = match msg
@@ -60,32 +53,16 @@ pub async fn asm(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
         }
     };
 
-    let filters = CompilationFilters {
-        binary: None,
-        comment_only: Some(true),
-        demangle: Some(true),
-        directives: Some(true),
-        execute: None,
-        intel: Some(true),
-        labels: Some(true),
-        library_code: None,
-        trim: Some(true),
+    let (response, mut execution) = match dispatch_request(ctx, &result, execute).await {
+        Ok(resp) => resp,
+        Err(e) => {
+            // we failed, lets remove the loading react before leaving so it doesn't seem like we're still processing
+            msg.delete_reaction_emoji(&ctx.http, reaction.emoji.clone())
+                .await?;
+            return Err(e);
+        }
     };
 
-    let response =
-        match Godbolt::send_request(&c, &result.code, &result.options.join(" "), &filters).await {
-            Ok(resp) => resp,
-            Err(e) => {
-                // we failed, lets remove the loading react before leaving so it doesn't seem like we're still processing
-                msg.delete_reaction_emoji(&ctx.http, reaction.emoji.clone())
-                    .await?;
-                return Err(CommandError::from(format!(
-                    "Godbolt request failed!\n\n{}",
-                    e
-                )));
-            }
-        };
-
     // remove our loading emote
     match msg
         .delete_reaction_emoji(&ctx.http, reaction.emoji.clone())
@@ -99,12 +76,59 @@ pub async fn asm(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
         }
     }
 
-    let emb = discordhelpers::build_asm_embed(&msg.author, &response);
-    let mut emb_msg = discordhelpers::embed_message(emb);
-    let asm_embed = msg
-        .channel_id
-        .send_message(&ctx.http, |_| &mut emb_msg)
-        .await?;
+    if let Some(exec) = execution.as_mut() {
+        exec.collapse_output(compileresult::collapse_threshold());
+        exec.spoiler = result.spoiler;
+
+        // --stderr-only hides the program's regular output
+        if result.stderr_only {
+            exec.stdout.clear();
+        }
+    }
+
+    let mut emb = discordhelpers::build_asm_embed(&msg.author, &response);
+    if let Some(exec) = &execution {
+        discordhelpers::add_execution_fields(&mut emb, exec);
+    }
+    if result.ignored_blocks > 0 {
+        discordhelpers::add_ignored_blocks_note(&mut emb, result.ignored_blocks);
+    }
+    if !result.headers.is_empty() {
+        discordhelpers::add_headers_note(&mut emb, &result.headers);
+    }
+    if result.verbose {
+        let compiler = {
+            let data_read = ctx.data.read().await;
+            let godbolt = data_read.get::<GodboltCache>().unwrap().read().await;
+            godbolt.resolve(&result.target).map(|c| format!("{} ({})", c.id, c.name))
+        };
+        let mut raw_status = match response.asm_size {
+            Some(size) => format!("assembled, {} bytes", size),
+            None => String::from("failed to assemble"),
+        };
+        if let Some(exec) = &execution {
+            raw_status.push_str(&format!(", executed: {}, {}", exec.exit_code.is_some(), exec.status()));
+        }
+
+        discordhelpers::add_request_details(&mut emb, &[
+            ("Backend", String::from("godbolt")),
+            ("Target", result.target.clone()),
+            ("Compiler", compiler.unwrap_or_default()),
+            ("Flags", result.options.join(" ")),
+            ("Execute", execute.to_string()),
+            ("Raw status", raw_status),
+        ]);
+    }
+    let theme = discordhelpers::theme_color(ctx, msg.guild_id).await;
+    discordhelpers::apply_theme(&mut emb, theme);
+    // an edited command updates its old results in place
+    let asm_embed = match discordhelpers::replace_output(ctx, msg, &emb).await {
+        Some(m) => m,
+        None => discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?,
+    };
+
+    discordhelpers::record_output(ctx, &asm_embed, OutputRecord::new(msg, &result.target, &result.code)).await;
+    discordhelpers::record_command(ctx, msg, true).await;
 
     let reaction;
     if response.asm_size.is_some() {
@@ -115,6 +139,12 @@ pub async fn asm(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
 
     asm_embed.react(&ctx.http, reaction).await?;
 
+    // once the command message is gone there's nothing left to link our output to
+    if discordhelpers::auto_delete_command(ctx, msg).await {
+        debug!("Command executed");
+        return Ok(());
+    }
+
     let data_read = ctx.data.read().await;
     let mut delete_cache = data_read.get::<MessageDeleteCache>().unwrap().lock().await;
     delete_cache.insert(msg.id.0, asm_embed.clone());
@@ -122,6 +152,77 @@ pub async fn asm(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
     Ok(())
 }
 
+/// Resolves the user's target and sends their code off to godbolt, optionally executing it as well
+pub async fn dispatch_request(
+    ctx: &Context,
+    result: &ParserResult,
+    execute: bool,
+) -> Result<(godbolt::CompilationResult, Option<CompileResult>), CommandError> {
+    // aquire lock to our godbolt cache
+    let data_read = ctx.data.read().await;
+    let godbolt_lock = match data_read.get::<GodboltCache>() {
+        Some(l) => l,
+        None => {
+            return Err(CommandError::from(
+                "Internal request failure\nGodbolt cache is uninitialized, please file a bug.",
+            ));
+        }
+    };
+    let godbolt = godbolt_lock.read().await;
+
+    let c = match godbolt.resolve(&result.target) {
+        Some(c) => c,
+        None => {
+            return Err(CommandError::from(format!(
+                "Unable to find valid compiler or language '{}'\n",
+                &result.target
+            )));
+        }
+    };
+
+    let limiter = data_read.get::<CompileLimiterCache>().unwrap().clone();
+    let _permit = limiter.acquire().await?;
+    let limit = parser::max_output_bytes();
+
+    if execute {
+        let language = godbolt
+            .cache
+            .iter()
+            .find(|entry| entry.compilers.iter().any(|compiler| compiler.id == c.id))
+            .map(|entry| entry.language.id.clone())
+            .unwrap_or_else(|| result.target.clone());
+        if !data_read.get::<ProfilesCache>().unwrap().for_language(&language).execution_allowed {
+            return Err(CommandError::from(format!("Running {} programs is disabled", language)));
+        }
+        compile::refuse_abuse(&language, &result.code)?;
+        let libraries = compile::godbolt_libraries(&language, &result.libraries)?;
+
+        let started = Instant::now();
+        return match execution::compile_and_execute(&c.id, &result.code, &result.options.join(" "), &libraries, true, limit).await {
+            // asm embeds are cut to fit on their own, a cut off response just shows less
+            Ok((resp, exec, _)) => Ok((resp, Some(CompileResult::from_godbolt(exec, &c.id, started.elapsed())))),
+            Err(e) => Err(CommandError::from(format!(
+                "Godbolt request failed!\n\n{}",
+                e
+            ))),
+        };
+    }
+
+    // libraries only change what the program does, the assembly is the same either way
+    if !result.libraries.is_empty() {
+        return Err(CommandError::from("Libraries can only be used together with --exec"));
+    }
+
+    // sent by hand like executions are, the godbolt crate would buffer responses of any size
+    match execution::compile_and_execute(&c.id, &result.code, &result.options.join(" "), &[], false, limit).await {
+        Ok((resp, _, _)) => Ok((resp, None)),
+        Err(e) => Err(CommandError::from(format!(
+            "Godbolt request failed!\n\n{}",
+            e
+        ))),
+    }
+}
+
 #[command]
 async fn compilers(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
     let data_read = ctx.data.read().await;
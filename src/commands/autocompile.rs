@@ -0,0 +1,36 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::SettingsCache;
+use crate::utls::discordhelpers;
+
+#[command]
+#[only_in(guilds)]
+pub async fn autocompile(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    if !discordhelpers::is_admin(ctx, msg).await {
+        return Err(CommandError::from(
+            "Only server administrators may change whether code in this channel is compiled automatically",
+        ));
+    }
+
+    let enabled = match args.current().map(|s| s.to_lowercase()).as_deref() {
+        Some("on") => true,
+        Some("off") => false,
+        _ => return Err(CommandError::from("Usage: `autocompile <on | off>`")),
+    };
+
+    let data = ctx.data.read().await;
+    let mut settings = data.get::<SettingsCache>().unwrap().write().await;
+    settings.channel_mut(msg.channel_id.0).auto_compile = enabled;
+    settings.write();
+
+    let reply = if enabled {
+        "Every message in this channel with a code block will now be ran, using the code block's language or this channel's default"
+    } else {
+        "Code in this channel will only be ran through commands again"
+    };
+    msg.channel_id.say(&ctx.http, reply).await?;
+    debug!("Command executed");
+    Ok(())
+}
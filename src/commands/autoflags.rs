@@ -0,0 +1,36 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::SettingsCache;
+use crate::utls::discordhelpers;
+
+#[command]
+#[only_in(guilds)]
+pub async fn autoflags(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    if !discordhelpers::is_admin(ctx, msg).await {
+        return Err(CommandError::from(
+            "Only server administrators may change whether flags are added automatically",
+        ));
+    }
+
+    let enabled = match args.current().map(|s| s.to_lowercase()).as_deref() {
+        Some("on") => true,
+        Some("off") => false,
+        _ => return Err(CommandError::from("Usage: `autoflags <on | off>`")),
+    };
+
+    let data = ctx.data.read().await;
+    let mut settings = data.get::<SettingsCache>().unwrap().write().await;
+    settings.guild_mut(msg.guild_id.unwrap().0).no_auto_flags = !enabled;
+    settings.write();
+
+    let reply = if enabled {
+        "Flags like `-pthread` will be added when the code includes a header that needs them"
+    } else {
+        "Only the flags given by the user will be used"
+    };
+    msg.channel_id.say(&ctx.http, reply).await?;
+    debug!("Command executed");
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use serenity::framework::standard::{macros::command, Args, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::commands::compile;
+use crate::utls::discordhelpers;
+
+#[command]
+pub async fn backends(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let current = compile::preferred_backend(ctx, msg.guild_id).await;
+
+    let mut emb = discordhelpers::build_backends_embed(&msg.author, &current);
+    let theme = discordhelpers::theme_color(ctx, msg.guild_id).await;
+    discordhelpers::apply_theme(&mut emb, theme);
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+
+    debug!("Command executed");
+    Ok(())
+}
@@ -3,21 +3,36 @@ use serenity::model::prelude::*;
 use serenity::prelude::*;
 
 use crate::cache::BlocklistCache;
+use crate::utls::schedule;
 
+/// ;block <id> [duration] [reason], blocks without a duration are permanent
 #[command]
 #[owners_only]
-pub async fn block(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
-    if args.len() != 1 {
-        return Err(CommandError::from("Supply an id to block"));
-    }
+pub async fn block(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let arg = match args.single::<u64>() {
+        Ok(id) => id,
+        Err(_) => return Err(CommandError::from("Supply an id to block, optionally followed by a duration like 2h and a reason")),
+    };
 
-    let arg = args.parse::<u64>()?;
+    // a duration is only taken if it's the first thing after the id, the rest is the reason
+    let minutes = args.current().and_then(schedule::parse_duration);
+    if minutes.is_some() {
+        args.advance();
+    }
+    let reason = Some(String::from(args.rest().trim())).filter(|r| !r.is_empty());
 
     let data = ctx.data.read().await;
     let mut blocklist = data.get::<BlocklistCache>().unwrap().write().await;
 
-    blocklist.block(arg);
-
-    msg.channel_id.say(&ctx.http, format!("Blocked snowflake `{}`", &arg)).await?;
+    match minutes {
+        Some(minutes) => {
+            blocklist.insert_temp(arg, chrono::Duration::minutes(minutes as i64), reason);
+            msg.channel_id.say(&ctx.http, format!("Blocked snowflake `{}` for {} minutes", &arg, minutes)).await?;
+        }
+        None => {
+            blocklist.insert_permanent(arg, reason);
+            msg.channel_id.say(&ctx.http, format!("Blocked snowflake `{}`", &arg)).await?;
+        }
+    }
     Ok(())
 }
@@ -0,0 +1,39 @@
+use serenity::framework::standard::{macros::command, Args, CommandResult, CommandError};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::BlocklistCache;
+
+#[command]
+#[owners_only]
+pub async fn blockchannel(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let channel = match args.single::<ChannelId>() {
+        Ok(c) => c,
+        Err(_e) => return Err(CommandError::from("Supply a channel or channel id to block")),
+    };
+
+    let data = ctx.data.read().await;
+    let mut blocklist = data.get::<BlocklistCache>().unwrap().write().await;
+
+    blocklist.block_channel(channel.0);
+
+    msg.channel_id.say(&ctx.http, format!("Blocked channel `{}`", channel.0)).await?;
+    Ok(())
+}
+
+#[command]
+#[owners_only]
+pub async fn unblockchannel(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let channel = match args.single::<ChannelId>() {
+        Ok(c) => c,
+        Err(_e) => return Err(CommandError::from("Supply a channel or channel id to unblock")),
+    };
+
+    let data = ctx.data.read().await;
+    let mut blocklist = data.get::<BlocklistCache>().unwrap().write().await;
+
+    blocklist.unblock_channel(channel.0);
+
+    msg.channel_id.say(&ctx.http, format!("Unblocked channel `{}`", channel.0)).await?;
+    Ok(())
+}
@@ -1,15 +1,26 @@
-use serenity::builder::{CreateEmbed, CreateMessage};
+use serenity::builder::CreateEmbed;
 use serenity::framework::standard::{macros::command, Args, CommandResult};
 use serenity::model::prelude::*;
 use serenity::prelude::*;
 
 use std::env;
 
-use crate::cache::ConfigCache;
-use crate::utls::constants::COLOR_OKAY;
+use crate::cache::{CompileLimiterCache, ConfigCache, StatsManagerCache};
+use crate::utls::discordhelpers;
 
 #[command]
 pub async fn botinfo(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let emb = build_botinfo_embed(ctx, msg.guild_id).await;
+    if let Err(why) = discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await {
+        warn!("Error sending embed: {:?}", why);
+    }
+
+    debug!("Command executed");
+    Ok(())
+}
+
+/// Shared with /botinfo, see slashcommands
+pub async fn build_botinfo_embed(ctx: &Context, guild_id: Option<GuildId>) -> CreateEmbed {
     let invite = env::var("INVITE_LINK").expect("Expected invite link envvar");
     let topgg = env::var("DISCORDBOTS_LINK").expect("Expected top.gg link envvar");
     let github = env::var("GITHUB_LINK").expect("Expected github link envvar");
@@ -29,52 +40,56 @@ pub async fn botinfo(ctx: &Context, msg: &Message, _args: Args) -> CommandResult
         botinfo.get("BOT_AVATAR").unwrap().clone()
     };
 
-    let msg = msg
-        .channel_id
-        .send_message(&ctx.http, |m: &mut CreateMessage| {
-            m.embed(|e: &mut CreateEmbed| {
-                e.title("Compiler Bot");
+    let compiles = {
+        let data_read = ctx.data.read().await;
+        let limiter = data_read.get::<CompileLimiterCache>().unwrap();
+        format!("{}/{}", limiter.in_flight(), limiter.max())
+    };
 
-                let fmt = format!(
-                    "{}\n
-                {}
-                [Invitation link]({})
-                [Vote for us!]({})
-                [GitHub Repository]({})
-                [Statistics Tracker]({})
-                {}",
-                    env!("CARGO_PKG_DESCRIPTION"),
-                    "==========================",
-                    invite,
-                    topgg,
-                    github,
-                    stats,
-                    "=========================="
-                );
+    let largest_shard = {
+        let data_read = ctx.data.read().await;
+        let stats = data_read.get::<StatsManagerCache>().unwrap().lock().await;
+        match stats.largest_shard() {
+            Some((id, guilds)) => format!("Shard {} ({} guilds)", id, guilds),
+            None => String::from("Shards are still starting"),
+        }
+    };
 
-                e.description(fmt);
-                e.thumbnail(avatar);
-                e.color(COLOR_OKAY);
+    let theme = discordhelpers::theme_color(ctx, guild_id).await;
+    let mut e = CreateEmbed::default();
+    e.title("Compiler Bot");
 
-                let str = format!("Built from commit [{}]({}{}{})",
-                                  hash_short, github, "/commit/", hash_long);
+    let fmt = format!(
+        "{}\n
+    {}
+    [Invitation link]({})
+    [Vote for us!]({})
+    [GitHub Repository]({})
+    [Statistics Tracker]({})
+    {}",
+        env!("CARGO_PKG_DESCRIPTION"),
+        "==========================",
+        invite,
+        topgg,
+        github,
+        stats,
+        "=========================="
+    );
 
-                e.fields(vec![
-                    ("Language", "Rust 2018", false),
-                    ("Software Version", env!("CARGO_PKG_VERSION"), false),
-                    ("Author", env!("CARGO_PKG_AUTHORS"), false),
-                    ("Build Information", str.as_str(), false),
-                ]);
-                e
-            });
-            m
-        })
-        .await;
+    e.description(fmt);
+    e.thumbnail(avatar);
+    e.color(theme);
 
-    if let Err(why) = msg {
-        warn!("Error sending embed: {:?}", why);
-    }
+    let str = format!("Built from commit [{}]({}{}{})",
+                      hash_short, github, "/commit/", hash_long);
 
-    debug!("Command executed");
-    Ok(())
+    e.fields(vec![
+        ("Language", "Rust 2018", false),
+        ("Software Version", env!("CARGO_PKG_VERSION"), false),
+        ("Author", env!("CARGO_PKG_AUTHORS"), false),
+        ("Build Information", str.as_str(), false),
+        ("Compilations In Progress", compiles.as_str(), false),
+        ("Largest Shard", largest_shard.as_str(), false),
+    ]);
+    e
 }
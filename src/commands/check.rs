@@ -0,0 +1,13 @@
+use serenity::framework::standard::{macros::command, Args, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::commands::compile;
+
+#[command]
+#[bucket = "nospam"]
+pub async fn check(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let mut parse_result = compile::parse_request(ctx, msg).await?;
+    parse_result.syntax_only = true;
+    compile::handle_request(ctx, msg, parse_result, false).await
+}
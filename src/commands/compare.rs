@@ -0,0 +1,116 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::futures::future::join_all;
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::{ConfigCache, StatsManagerCache};
+use crate::commands::compile;
+use crate::stats::metrics;
+use crate::utls::compileresult::CompileResult;
+use crate::utls::constants::MAX_COMPARE_LANGUAGES;
+use crate::utls::parser::{self, ParserResult};
+use crate::utls::discordhelpers;
+
+#[command]
+#[bucket = "nospam"]
+pub async fn compare(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let sides = sides(&msg.content, "compare", MAX_COMPARE_LANGUAGES)?;
+
+    let (loading_id, loading_name) = {
+        let data_read = ctx.data.read().await;
+        let botinfo = data_read.get::<ConfigCache>().unwrap().read().await;
+        let id = botinfo.get("LOADING_EMOJI_ID").unwrap().parse::<u64>().unwrap();
+        (id, botinfo.get("LOADING_EMOJI_NAME").unwrap().clone())
+    };
+    let reaction = match msg
+        .react(&ctx.http, discordhelpers::build_reaction(loading_id, &loading_name))
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            return Err(CommandError::from(format!(" Unable to react to message, am I missing permissions to react or use external emoji?\n{}", e)));
+        }
+    };
+
+    // every side runs at once, one failing doesn't hold up the others
+    let runs = sides.iter().map(|(language, code)| run_side(ctx, msg.author.id.0, language, code));
+    let outcomes = join_all(runs).await;
+    let results: Vec<(String, Result<CompileResult, String>)> = sides
+        .into_iter()
+        .map(|(language, _)| language)
+        .zip(outcomes)
+        .collect();
+
+    if msg.delete_reaction_emoji(&ctx.http, reaction.emoji.clone()).await.is_err() {
+        return Err(CommandError::from(
+            "Unable to remove reactions!\nAm I missing permission to manage messages?",
+        ));
+    }
+
+    let mut emb = discordhelpers::build_compare_embed(&msg.author, &results);
+    let theme = discordhelpers::theme_color(ctx, msg.guild_id).await;
+    discordhelpers::apply_theme(&mut emb, theme);
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+
+    debug!("Command executed");
+    Ok(())
+}
+
+/// The (language, code) of every code block, for commands running several languages
+/// against each other. `action` is what we're doing with them, for the errors
+pub fn sides(content: &str, action: &str, max: usize) -> Result<Vec<(String, String)>, CommandError> {
+    let mut sides = Vec::new();
+    for (language, code) in parser::code_blocks(content) {
+        match language {
+            Some(l) => sides.push((l, code)),
+            None => {
+                return Err(CommandError::from(format!(
+                    "Every code block needs a language to {}, like ```cpp",
+                    action
+                )));
+            }
+        }
+    }
+
+    if sides.len() < 2 {
+        return Err(CommandError::from(format!(
+            "Supply at least two code blocks with languages to {}",
+            action
+        )));
+    }
+    if sides.len() > max {
+        return Err(CommandError::from(format!(
+            "I can only {} up to {} languages at once",
+            action, max
+        )));
+    }
+    Ok(sides)
+}
+
+/// Compiles & runs a single side of the comparison just like ;run would
+pub async fn run_side(ctx: &Context, author: u64, language: &str, code: &str) -> Result<CompileResult, String> {
+    let mut parse_result = ParserResult {
+        target: String::from(language),
+        code: String::from(code),
+        ..Default::default()
+    };
+
+    let builder = compile::build_request(ctx, &mut parse_result, author)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !compile::execution_allowed(ctx, &builder.lang).await {
+        return Err(format!("Running {} programs is disabled", builder.lang));
+    }
+
+    let (result, _) = compile::dispatch(ctx, &builder, &parse_result)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let data = ctx.data.read().await;
+    let mut stats = data.get::<StatsManagerCache>().unwrap().lock().await;
+    if stats.should_track() {
+        stats.compilation(&builder.lang, !result.succeeded()).await;
+    }
+    metrics::record(&data, |m| m.compilation(&builder.lang, !result.succeeded()));
+    Ok(result)
+}
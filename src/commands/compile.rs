@@ -1,54 +1,177 @@
 use std::env;
+use std::time::{Duration, Instant};
 
 use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
 use serenity::model::prelude::*;
 use serenity::prelude::*;
 
+use tokio::sync::watch;
 use wandbox::*;
 
-use crate::cache::{ConfigCache, WandboxCache, StatsManagerCache, MessageDeleteCache};
-use crate::utls::{discordhelpers, parser, parser::*};
+use crate::cache::{CompileLimiterCache, ConfigCache, ExpandCache, GodboltCache, ProfilesCache, WandboxCache, StatsManagerCache, MessageDeleteCache, LastSourceCache, RerunCache, SettingsCache};
+use crate::apis::{execution, forwards, stream, threads};
+use crate::stats::metrics;
+use crate::utls::constants::{ASSEMBLERS, BACKENDS, DEFAULT_BACKEND, DM_EMOJI, EXPAND_EMOJI, MAX_SOURCE_FILE_BYTES, MAX_STDIN_FILE_BYTES, PERMLINK_TIMEOUT, QUEUE_NOTICE_DELAY, RERUN_EMOJI, STREAM_EDIT_INTERVAL, THREAD_LINE_THRESHOLD};
+use crate::utls::compileresult::{self, CompileResult};
+use crate::utls::reports::OutputRecord;
+use crate::utls::{abuse, discordhelpers, flagpresets, fuzzy, libraries, parser, parser::*, postprocess, runtimes, selftest, syntax};
 
 #[command]
 #[bucket = "nospam"]
 pub async fn compile(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
-    let success_id;
-    let success_name;
-    let loading_id;
-    let loading_name;
-    {
+    parse_and_handle(ctx, msg, false).await
+}
+
+/// Shared entry point of ;compile and ;run, `execute` decides whether the user sees their program's output
+pub async fn parse_and_handle(ctx: &Context, msg: &Message, execute: bool) -> CommandResult {
+    let parse_result = parse_request(ctx, msg).await?;
+    handle_request(ctx, msg, parse_result, execute).await
+}
+
+/// Parses the user's message, falling back to the code of the message they replied to
+pub async fn parse_request(ctx: &Context, msg: &Message) -> Result<ParserResult, CommandError> {
+    let mut default_target = {
         let data_read = ctx.data.read().await;
-        let botinfo_lock = data_read
-            .get::<ConfigCache>()
-            .expect("Expected ConfigCache in global cache")
-            .clone();
-        let botinfo = botinfo_lock.read().await;
-        success_id = botinfo
-            .get("SUCCESS_EMOJI_ID")
-            .unwrap()
-            .clone()
-            .parse::<u64>()
-            .unwrap();
-        success_name = botinfo.get("SUCCESS_EMOJI_NAME").unwrap().clone();
-        loading_id = botinfo
-            .get("LOADING_EMOJI_ID")
-            .unwrap()
-            .clone()
-            .parse::<u64>()
-            .unwrap();
-        loading_name = botinfo.get("LOADING_EMOJI_NAME").unwrap().clone();
+        let settings = data_read.get::<SettingsCache>().unwrap().read().await;
+        settings.default_language(msg.guild_id.map(|g| g.0).unwrap_or_default(), msg.channel_id.0)
+    };
+
+    // a reply without code of its own compiles the code of the message it's replying to
+    let file = attachment_source(msg).await?;
+    let mut input = msg.content.clone();
+    let has_url = msg.content.split_whitespace().any(|a| a == "<");
+    if !msg.content.contains("```") && !has_url && file.is_none() {
+        if let Some(code) = referenced_code(ctx, msg).await? {
+            if default_target.is_none() {
+                default_target = parser::code_block_language(&code);
+            }
+            input.push('\n');
+            input.push_str(&code);
+        }
     }
 
     // parse user input
-    let parse_result: ParserResult = parser::get_components(&msg.content, &msg.author).await?;
+    let mut parse_result: ParserResult = parser::get_components_with_file(&input, &msg.author, default_target, file).await?;
+    if parse_result.stdin_file {
+        if !parse_result.stdin.is_empty() {
+            return Err(CommandError::from(
+                "You've given input both inline and with --stdin-file, please pick one",
+            ));
+        }
+        parse_result.stdin = attachment_stdin(msg).await?;
+    }
+    Ok(parse_result)
+}
 
-    // build user input
+// The contents of the text file attached for --stdin-file
+async fn attachment_stdin(msg: &Message) -> Result<String, CommandError> {
+    let attachment = match msg.attachments.iter().find(|a| a.filename.to_lowercase().ends_with(".txt")) {
+        Some(a) => a,
+        None => {
+            return Err(CommandError::from(
+                "--stdin-file requires a .txt file attached to your message",
+            ));
+        }
+    };
+
+    download_text(attachment, "input", MAX_STDIN_FILE_BYTES).await
+}
+
+/// The first attachment with a source file extension we know of (see SOURCE_EXTENSIONS),
+/// compiled in place of a code block. Shared with ;asm
+pub async fn attachment_source(msg: &Message) -> Result<Option<SourceFile>, CommandError> {
+    let attachment = match msg.attachments.iter().find(|a| parser::extension_language(&a.filename).is_some()) {
+        Some(a) => a,
+        None => return Ok(None),
+    };
+
+    let code = download_text(attachment, "source", MAX_SOURCE_FILE_BYTES).await?;
+    Ok(Some(SourceFile {
+        name: attachment.filename.clone(),
+        code,
+    }))
+}
+
+// Only plain text is accepted, anything with NUL bytes or that isn't UTF-8 is likely a binary.
+// `kind` names the file in our errors, e.g. input
+async fn download_text(attachment: &Attachment, kind: &str, limit: u64) -> Result<String, CommandError> {
+    if attachment.size > limit {
+        return Err(CommandError::from(format!(
+            "Your {} file is too large, the limit is {} bytes",
+            kind, limit
+        )));
+    }
+
+    let bytes = match attachment.download().await {
+        Ok(b) => b,
+        Err(_e) => {
+            return Err(CommandError::from(format!("Unable to download your {} file", kind)));
+        }
+    };
+
+    let binary = CommandError::from(format!(
+        "Your {} file looks like a binary, only text files can be used as {}",
+        kind, kind
+    ));
+    if bytes.contains(&0) {
+        return Err(binary);
+    }
+    match String::from_utf8(bytes) {
+        Ok(s) => Ok(s),
+        Err(_e) => Err(binary),
+    }
+}
+
+// Everything from the first code block onward of the message being replied to, any
+// text before it is left out so it can't be mistaken for the replier's arguments.
+// Forwarded messages have no content of their own, their code is in the forwarded snapshot
+async fn referenced_code(ctx: &Context, msg: &Message) -> Result<Option<String>, CommandError> {
+    let reference = match &msg.message_reference {
+        Some(r) => r,
+        None => return Ok(None),
+    };
+    let message_id = match reference.message_id {
+        Some(id) => id,
+        None => return Ok(None),
+    };
+
+    let referenced = match ctx.http.get_message(reference.channel_id.0, message_id.0).await {
+        Ok(m) => m,
+        Err(_e) => {
+            return Err(CommandError::from(
+                "Unable to read the message you replied to, has it been deleted?",
+            ));
+        }
+    };
+
+    let mut content = referenced.content;
+    let mut forwarded = false;
+    if content.is_empty() {
+        if let Some(snapshot) = forwards::forwarded_content(&ctx.http, reference.channel_id.0, message_id.0).await {
+            content = snapshot;
+            forwarded = true;
+        }
+    }
+
+    match content.find("```") {
+        Some(index) => Ok(Some(String::from(&content[index..]))),
+        None if forwarded => Err(CommandError::from(
+            "The forwarded message you replied to doesn't contain a code block",
+        )),
+        None => Err(CommandError::from(
+            "The message you replied to doesn't contain a code block",
+        )),
+    }
+}
+
+/// Builds and validates a wandbox request from the user's parsed input, filling
+/// in the author's saved flags if they didn't pass any of their own
+pub async fn build_request(ctx: &Context, parse_result: &mut ParserResult, author: u64) -> Result<CompilationBuilder, CommandError> {
     let mut builder = CompilationBuilder::new();
     builder.code(&parse_result.code);
     builder.target(&parse_result.target);
     builder.stdin(&parse_result.stdin);
-    builder.save(true);
-    builder.options(parse_result.options);
+    builder.options(parse_result.options.clone());
 
     // aquire lock to our wandbox cache
     let data_read = ctx.data.read().await;
@@ -73,10 +196,629 @@ pub async fn compile(ctx: &Context, msg: &Message, _args: Args) -> CommandResult
         }
     };
 
-    // lets see if we can manually fix botched java compilations...
-    // for wandbox, "public class" is invalid, so lets do a quick replacement
+    if apply_user_flags(&data_read, parse_result, author, &builder.lang).await {
+        builder.options(parse_result.options.clone());
+    }
+
+    refuse_abuse(&builder.lang, &parse_result.code)?;
+
+    let profile = data_read.get::<ProfilesCache>().unwrap().for_language(&builder.lang);
+    if let Some(flag) = profile.disallowed_flag(&parse_result.options) {
+        return Err(CommandError::from(format!(
+            "The flag `{}` isn't allowed for {}",
+            flag, builder.lang
+        )));
+    }
+
     if builder.lang == "java" {
-        builder.code(&parse_result.code.replacen("public class", "class", 1));
+        builder.code(&wandbox_source(&builder.lang, &parse_result.code));
+    }
+
+    Ok(builder)
+}
+
+// lets see if we can manually fix botched java compilations...
+// for wandbox, "public class" is invalid, so lets do a quick replacement
+fn wandbox_source(language: &str, code: &str) -> String {
+    if language == "java" {
+        code.replacen("public class", "class", 1)
+    } else {
+        String::from(code)
+    }
+}
+
+// inline flags > the user's flags for this language > nothing. True if the user's were picked
+async fn apply_user_flags(data_read: &TypeMap, parse_result: &mut ParserResult, author: u64, language: &str) -> bool {
+    if !parse_result.options.is_empty() {
+        return false;
+    }
+    let settings = data_read.get::<SettingsCache>().unwrap().read().await;
+    match settings.user_flags(author, language) {
+        Some(flags) => {
+            parse_result.options = flags.clone();
+            parse_result.flags_source = FlagSource::User;
+            true
+        }
+        None => false,
+    }
+}
+
+/// The wandbox switches enabling the user's --lib libraries
+fn wandbox_switches(builder: &CompilationBuilder, names: &[String]) -> Result<Vec<String>, CommandError> {
+    names
+        .iter()
+        .map(|name| {
+            libraries::wandbox_switch(name, &builder.lang, &builder.compiler)
+                .ok_or_else(|| unknown_library("wandbox", &builder.lang, name))
+        })
+        .collect()
+}
+
+/// The godbolt (id, version) pairs of the user's --lib libraries
+pub fn godbolt_libraries(language: &str, names: &[String]) -> Result<Vec<(&'static str, &'static str)>, CommandError> {
+    names
+        .iter()
+        .map(|name| libraries::godbolt_library(name, language).ok_or_else(|| unknown_library("godbolt", language, name)))
+        .collect()
+}
+
+fn unknown_library(backend: &str, language: &str, name: &str) -> CommandError {
+    let available = libraries::available(backend, language);
+    if available.is_empty() {
+        return CommandError::from(format!("Libraries aren't supported for {} on {}", language, backend));
+    }
+
+    CommandError::from(format!(
+        "Unknown library `{}` for {} on {}, available libraries are: {}",
+        name, language, backend, available.join(", ")
+    ))
+}
+
+/// Runs a built request on wandbox while editing a message with the program's output as
+/// it comes in, at most every STREAM_EDIT_INTERVAL seconds to stay clear of rate limits.
+/// The message is handed back so it can be replaced with the final results
+async fn dispatch_streaming(ctx: &Context, msg: &Message, builder: &CompilationBuilder, parse_result: &ParserResult) -> Result<(CompileResult, bool, Message), CommandError> {
+    let (limiter, profile) = {
+        let data_read = ctx.data.read().await;
+        let limiter = data_read.get::<CompileLimiterCache>().unwrap().clone();
+        let profile = data_read.get::<ProfilesCache>().unwrap().for_language(&builder.lang);
+        (limiter, profile)
+    };
+    let switches = wandbox_switches(builder, &parse_result.libraries)?;
+    let _permit = limiter.acquire().await?;
+
+    let stream_embed = discordhelpers::build_stream_embed(&msg.author, "", parse_result.spoiler);
+    let mut live = discordhelpers::send_embed(msg.channel_id, &ctx.http, stream_embed).await?;
+
+    let (updates, latest) = watch::channel(String::new());
+    let source = wandbox_source(&builder.lang, &parse_result.code);
+    let started = Instant::now();
+    let request = tokio::time::timeout(
+        profile.time_limit(),
+        stream::compile_ndjson(&builder.compiler, &source, &parse_result.options, &switches, &parse_result.stdin, profile.output_limit(), updates),
+    );
+    tokio::pin!(request);
+
+    let mut ticker = tokio::time::interval(Duration::from_secs(STREAM_EDIT_INTERVAL));
+    let mut shown = String::new();
+    let outcome = loop {
+        tokio::select! {
+            outcome = &mut request => break outcome,
+            _ = ticker.tick() => {
+                let output = latest.borrow().clone();
+                if output != shown {
+                    let emb = discordhelpers::build_stream_embed(&msg.author, &output, parse_result.spoiler);
+                    if live.edit(&ctx.http, |m| m.embed(|mut e| { e.0 = emb.0; e })).await.is_err() {
+                        // we'll catch up on the next tick
+                    }
+                    shown = output;
+                }
+            }
+        }
+    };
+
+    let output = match outcome {
+        Ok(Ok(o)) => o,
+        Ok(Err(e)) => {
+            if live.delete(&ctx.http).await.is_err() {
+                warn!("Unable to clean up streamed output {}", live.id.0);
+            }
+            return Err(CommandError::from(format!("{}", e)));
+        }
+        Err(_) => {
+            if live.delete(&ctx.http).await.is_err() {
+                warn!("Unable to clean up streamed output {}", live.id.0);
+            }
+            return Err(CommandError::from(profile.timed_out(&builder.lang)));
+        }
+    };
+
+    let (result, truncated) = wandbox_result(output, builder, parse_result, &switches, started.elapsed(), profile.output_limit()).await;
+    Ok((result, truncated, live))
+}
+
+/// Sends a built request off to wandbox within the language's profile limits. Requests
+/// are sent by hand so we can stop reading once the output is over the profile's limit,
+/// the wandbox crate buffers the whole response (& can't select libraries or reach a
+/// self-hosted wandbox). The second value of the result is set if any output had to be cut off
+pub async fn dispatch(ctx: &Context, builder: &CompilationBuilder, parse_result: &ParserResult) -> Result<(CompileResult, bool), CommandError> {
+    let switches = wandbox_switches(builder, &parse_result.libraries)?;
+    let (limiter, profile) = {
+        let data_read = ctx.data.read().await;
+        let limiter = data_read.get::<CompileLimiterCache>().unwrap().clone();
+        let profile = data_read.get::<ProfilesCache>().unwrap().for_language(&builder.lang);
+        (limiter, profile)
+    };
+
+    // nobody is watching the output as it comes in, we only want the end result
+    let (updates, _) = watch::channel(String::new());
+    let source = wandbox_source(&builder.lang, &parse_result.code);
+    let started = Instant::now();
+    let request = stream::compile_ndjson(&builder.compiler, &source, &parse_result.options, &switches, &parse_result.stdin, profile.output_limit(), updates);
+    let output = {
+        let _permit = limiter.acquire().await?;
+        match tokio::time::timeout(profile.time_limit(), request).await {
+            Ok(Ok(o)) => o,
+            Ok(Err(e)) => return Err(CommandError::from(format!("{}", e))),
+            Err(_) => {
+                return Err(CommandError::from(profile.timed_out(&builder.lang)));
+            }
+        }
+    };
+
+    Ok(wandbox_result(output, builder, parse_result, &switches, started.elapsed(), profile.output_limit()).await)
+}
+
+// Maps what wandbox sent us into our result. Whatever doesn't fit in our limits
+// can still be read on the request's permlink
+async fn wandbox_result(
+    output: stream::StreamOutput,
+    builder: &CompilationBuilder,
+    parse_result: &ParserResult,
+    switches: &[String],
+    elapsed: Duration,
+    limit: usize,
+) -> (CompileResult, bool) {
+    let mut result = CompileResult::from_stream(&output, &parse_result.target, elapsed);
+    // collapsing first gives the collapsed output a chance to fit
+    result.collapse_output(compileresult::collapse_threshold());
+    let truncated = truncate_result(&mut result, limit) || output.truncated;
+    if !truncated {
+        return (result, false);
+    }
+
+    // only cut off results need one, & a slow wandbox mustn't hold up the ones we have
+    let source = wandbox_source(&builder.lang, &parse_result.code);
+    let permlink = stream::permlink(&builder.compiler, &source, &parse_result.options, switches, &parse_result.stdin, &output);
+    result.url = match tokio::time::timeout(Duration::from_secs(PERMLINK_TIMEOUT), permlink).await {
+        Ok(Ok(url)) => Some(url),
+        Ok(Err(e)) => {
+            warn!("Unable to save a permlink: {}", e);
+            None
+        }
+        Err(_) => {
+            warn!("Timed out saving a permlink");
+            None
+        }
+    };
+    (result, true)
+}
+
+/// The backend a guild's compilations should go to, falling back to DEFAULT_BACKEND
+/// (or the DEFAULT_BACKEND environment variable) when a guild hasn't chosen one
+pub async fn preferred_backend(ctx: &Context, guild: Option<GuildId>) -> String {
+    let chosen = match guild {
+        Some(g) => {
+            let data_read = ctx.data.read().await;
+            let settings = data_read.get::<SettingsCache>().unwrap().read().await;
+            settings.backend(g.0)
+        }
+        None => None,
+    };
+
+    chosen
+        .or_else(|| env::var("DEFAULT_BACKEND").ok())
+        .filter(|b| BACKENDS.iter().any(|(name, _)| name == b))
+        .unwrap_or_else(|| String::from(DEFAULT_BACKEND))
+}
+
+/// The godbolt compiler id & language a request should use when godbolt is the preferred
+/// backend. Requests godbolt can't handle go to wandbox instead, with the reason why
+async fn godbolt_target(ctx: &Context, guild: Option<GuildId>, parse_result: &ParserResult) -> (Option<(String, String)>, Option<String>) {
+    if preferred_backend(ctx, guild).await != "godbolt" {
+        return (None, None);
+    }
+
+    if !parse_result.stdin.is_empty() {
+        return (None, Some(String::from("Godbolt doesn't support stdin")));
+    }
+
+    let data_read = ctx.data.read().await;
+    let godbolt = data_read.get::<GodboltCache>().unwrap().read().await;
+    let compiler = match godbolt.resolve(&parse_result.target) {
+        Some(c) => c,
+        None => {
+            return (None, Some(format!("Godbolt doesn't support '{}'", parse_result.target)));
+        }
+    };
+    let language = godbolt
+        .cache
+        .iter()
+        .find(|entry| entry.compilers.iter().any(|c| c.id == compiler.id))
+        .map(|entry| entry.language.id.clone())
+        .unwrap_or_else(|| parse_result.target.clone());
+
+    (Some((compiler.id, language)), None)
+}
+
+/// The compiler behind the user's --runtime on the backend the request will go to, along
+/// with how it's shown in the footer. Godbolt is only used where godbolt_target would be
+async fn resolve_runtime(ctx: &Context, guild: Option<GuildId>, parse_result: &ParserResult, name: &str) -> Result<(String, String), CommandError> {
+    let language = parse_result.target.as_str();
+    let (wandbox_prefix, godbolt_prefix) = match runtimes::prefixes(language, name) {
+        Some(p) => p,
+        None => return Err(unknown_runtime(language, name)),
+    };
+    let use_godbolt = !godbolt_prefix.is_empty()
+        && parse_result.stdin.is_empty()
+        && preferred_backend(ctx, guild).await == "godbolt";
+
+    let data_read = ctx.data.read().await;
+    let compiler = if use_godbolt {
+        let godbolt = data_read.get::<GodboltCache>().unwrap().read().await;
+        godbolt
+            .cache
+            .iter()
+            .flat_map(|entry| entry.compilers.iter())
+            .find(|c| c.id.starts_with(godbolt_prefix))
+            .map(|c| c.id.clone())
+    } else if !wandbox_prefix.is_empty() {
+        let wbox = data_read.get::<WandboxCache>().unwrap().read().await;
+        wbox.get_compilers(language)
+            .unwrap_or_default()
+            .into_iter()
+            .find(|c| c.name.starts_with(wandbox_prefix))
+            .map(|c| c.name)
+    } else {
+        None
+    };
+
+    match compiler {
+        Some(compiler) => {
+            let label = format!("{} ({})", name, compiler);
+            Ok((compiler, label))
+        }
+        None => Err(CommandError::from(format!(
+            "The {} runtime for {} isn't available right now, please try again later",
+            name, language
+        ))),
+    }
+}
+
+fn unknown_runtime(language: &str, name: &str) -> CommandError {
+    let available = runtimes::available(language);
+    if available.is_empty() {
+        return CommandError::from(format!(
+            "There are no runtimes to pick from for {}, try picking a compiler from `;compilers {}` instead",
+            language, language
+        ));
+    }
+
+    let mut error = format!("Unknown runtime `{}` for {}", name, language);
+    if let Some(suggestion) = fuzzy::closest(name, available.iter().copied()) {
+        error.push_str(&format!(", did you mean `{}`?", suggestion));
+    }
+    error.push_str(&format!("\nAvailable runtimes are: {}", available.join(", ")));
+    CommandError::from(error)
+}
+
+/// Refuses code that only exists to exhaust the backend's resources, see abuse::find_abuse
+pub fn refuse_abuse(language: &str, code: &str) -> Result<(), CommandError> {
+    match abuse::find_abuse(language, code) {
+        Some(description) => Err(CommandError::from(format!(
+            "This looks like a {}, which only wastes the backend's resources. I won't be running it",
+            description
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Whether the language's profile lets programs be ran, rather than only compiled
+pub async fn execution_allowed(ctx: &Context, language: &str) -> bool {
+    let data_read = ctx.data.read().await;
+    data_read.get::<ProfilesCache>().unwrap().for_language(language).execution_allowed
+}
+
+/// The flag stopping the build before there's a program to run, for languages whose
+/// profile doesn't allow running them. Languages that can't stop there are refused
+pub fn compile_only_flag(language: &str) -> Result<&'static str, CommandError> {
+    syntax::compile_only_flag(language).ok_or_else(|| {
+        CommandError::from(format!(
+            "Running {} programs is disabled, and {} can't be compiled without running it",
+            language, language
+        ))
+    })
+}
+
+/// The godbolt assembler behind a raw assembly target (;run asm), if the target is one
+pub fn assembler_for(target: &str) -> Option<&'static str> {
+    ASSEMBLERS.iter().find(|(t, _)| *t == target).map(|(_, a)| *a)
+}
+
+/// Assembles, links & runs raw assembly on godbolt since wandbox has no assemblers,
+/// otherwise this behaves just like dispatch
+pub async fn dispatch_assembly(ctx: &Context, parse_result: &ParserResult, assembler: &str) -> Result<(CompileResult, bool), CommandError> {
+    let compiler_id = {
+        let data_read = ctx.data.read().await;
+        let godbolt = data_read.get::<GodboltCache>().unwrap().read().await;
+        godbolt
+            .cache
+            .iter()
+            .filter(|entry| entry.language.id == "assembly")
+            .flat_map(|entry| entry.compilers.iter())
+            .find(|c| c.name.to_lowercase().contains(assembler))
+            .map(|c| c.id.clone())
+    };
+
+    match compiler_id {
+        Some(id) => dispatch_godbolt(ctx, parse_result, &id, "assembly", true).await,
+        None => Err(CommandError::from(format!(
+            "No {} assembler is available right now, please try again later",
+            assembler
+        ))),
+    }
+}
+
+/// Compiles & runs the request on godbolt within the language's profile limits, without
+/// `execute` the program is only built. Otherwise this behaves just like dispatch
+pub async fn dispatch_godbolt(ctx: &Context, parse_result: &ParserResult, compiler_id: &str, language: &str, execute: bool) -> Result<(CompileResult, bool), CommandError> {
+    let (limiter, profile) = {
+        let data_read = ctx.data.read().await;
+        let limiter = data_read.get::<CompileLimiterCache>().unwrap().clone();
+        let profile = data_read.get::<ProfilesCache>().unwrap().for_language(language);
+        (limiter, profile)
+    };
+
+    if let Some(flag) = profile.disallowed_flag(&parse_result.options) {
+        return Err(CommandError::from(format!(
+            "The flag `{}` isn't allowed for {}",
+            flag, language
+        )));
+    }
+    refuse_abuse(language, &parse_result.code)?;
+    let libraries = godbolt_libraries(language, &parse_result.libraries)?;
+
+    let started = Instant::now();
+    let request = execution::compile_and_execute(compiler_id, &parse_result.code, &parse_result.options.join(" "), &libraries, execute, profile.output_limit());
+    let (compilation, exec, cut_off) = {
+        let _permit = limiter.acquire().await?;
+        match tokio::time::timeout(profile.time_limit(), request).await {
+            Ok(Ok(r)) => r,
+            Ok(Err(e)) => return Err(CommandError::from(format!("Godbolt request failed!\n\n{}", e))),
+            Err(_) => {
+                return Err(CommandError::from(profile.timed_out(language)));
+            }
+        }
+    };
+
+    // godbolt reports the whole build (compiler & linker) in the build result,
+    // older responses only carry the compiler's errors on the compilation itself
+    let mut errors = exec.build_result.as_ref().map(|b| b.stderr_text()).unwrap_or_default();
+    if errors.trim().is_empty() {
+        errors = compilation
+            .stderr
+            .iter()
+            .map(|line| line.text.clone())
+            .collect::<Vec<String>>()
+            .join("\n");
+    }
+
+    let mut result = CompileResult::from_godbolt(exec, compiler_id, started.elapsed());
+    result.compiler_output = errors;
+
+    // collapsing first gives the collapsed output a chance to fit
+    result.collapse_output(compileresult::collapse_threshold());
+    let truncated = truncate_result(&mut result, profile.output_limit()) || cut_off;
+    Ok((result, truncated))
+}
+
+/// Compiles & runs a hello world on the backend through the same paths a user's request
+/// takes, returning how long it took. See events::spawn_self_test
+pub async fn self_test(ctx: &Context, backend: &str) -> Result<u64, String> {
+    let mut parse_result = ParserResult {
+        target: String::from(selftest::SELF_TEST_LANGUAGE),
+        code: String::from(selftest::SELF_TEST_SOURCE),
+        ..Default::default()
+    };
+
+    let started = Instant::now();
+    let result = match backend {
+        "godbolt" => {
+            let compiler = {
+                let data_read = ctx.data.read().await;
+                let godbolt = data_read.get::<GodboltCache>().unwrap().read().await;
+                godbolt.resolve(&parse_result.target).map(|c| c.id)
+            };
+            let compiler = compiler.ok_or_else(|| format!("godbolt doesn't know '{}'", parse_result.target))?;
+            dispatch_godbolt(ctx, &parse_result, &compiler, selftest::SELF_TEST_LANGUAGE, true).await
+        }
+        _ => {
+            let builder = build_request(ctx, &mut parse_result, 0).await.map_err(|e| e.to_string())?;
+            dispatch(ctx, &builder, &parse_result).await
+        }
+    };
+
+    let (result, _) = result.map_err(|e| e.to_string())?;
+    selftest::verify(&result)?;
+    Ok(started.elapsed().as_millis() as u64)
+}
+
+// Only streamed requests come with a message of their own
+fn with_no_live(dispatched: Result<(CompileResult, bool), CommandError>) -> Result<(CompileResult, bool, Option<Message>), CommandError> {
+    dispatched.map(|(result, truncated)| (result, truncated, None))
+}
+
+fn truncate_result(result: &mut CompileResult, limit: usize) -> bool {
+    let truncated = truncate_output(&mut result.compiler_output, limit)
+        | truncate_output(&mut result.stdout, limit)
+        | truncate_output(&mut result.stderr, limit);
+    if truncated {
+        debug!("Output exceeded {} bytes and was truncated", limit);
+    }
+    truncated
+}
+
+fn truncate_output(output: &mut String, limit: usize) -> bool {
+    if output.len() <= limit {
+        return false;
+    }
+
+    let mut end = limit;
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+    output.truncate(end);
+    true
+}
+
+pub async fn handle_request(ctx: &Context, msg: &Message, mut parse_result: ParserResult, mut execute: bool) -> CommandResult {
+    let success_id;
+    let success_name;
+    let loading_id;
+    let loading_name;
+    {
+        let data_read = ctx.data.read().await;
+        let botinfo_lock = data_read
+            .get::<ConfigCache>()
+            .expect("Expected ConfigCache in global cache")
+            .clone();
+        let botinfo = botinfo_lock.read().await;
+        success_id = botinfo
+            .get("SUCCESS_EMOJI_ID")
+            .unwrap()
+            .clone()
+            .parse::<u64>()
+            .unwrap();
+        success_name = botinfo.get("SUCCESS_EMOJI_NAME").unwrap().clone();
+        loading_id = botinfo
+            .get("LOADING_EMOJI_ID")
+            .unwrap()
+            .clone()
+            .parse::<u64>()
+            .unwrap();
+        loading_name = botinfo.get("LOADING_EMOJI_NAME").unwrap().clone();
+    }
+
+    // remember this request for a little while so it can be re-ran with ;swap
+    {
+        let data_read = ctx.data.read().await;
+        let mut last_source = data_read.get::<LastSourceCache>().unwrap().lock().await;
+        last_source.insert(msg.author.id.0, (Instant::now(), parse_result.clone()));
+    }
+    let rerun = parse_result.clone();
+
+    // --runtime picks the backend compiler for the language, from here on that's our target
+    let mut runtime = None;
+    if let Some(name) = parse_result.runtime.clone() {
+        let (compiler, label) = resolve_runtime(ctx, msg.guild_id, &parse_result, &name).await?;
+        parse_result.target = compiler;
+        runtime = Some(label);
+    }
+
+    // build user input, raw assembly & guilds preferring godbolt skip wandbox entirely
+    let assembler = assembler_for(&parse_result.target);
+    let (godbolt, fallback) = match assembler {
+        Some(_) => (None, None),
+        None => godbolt_target(ctx, msg.guild_id, &parse_result).await,
+    };
+    let mut builder = match (assembler, &godbolt) {
+        (None, None) => Some(build_request(ctx, &mut parse_result, msg.author.id.0).await?),
+        _ => None,
+    };
+    let language = match (&builder, &godbolt) {
+        (Some(b), _) => b.lang.clone(),
+        (None, Some((_, lang))) => lang.clone(),
+        (None, None) => String::from("assembly"),
+    };
+
+    // build_request picked the user's flags for wandbox already, godbolt's language is only known now
+    if let Some((_, lang)) = &godbolt {
+        let data_read = ctx.data.read().await;
+        apply_user_flags(&data_read, &mut parse_result, msg.author.id.0, lang).await;
+    }
+
+    // headers like <thread> need a flag to link, unless the user picked their own flags
+    let mut auto_flags = Vec::new();
+    if parse_result.options.is_empty() && discordhelpers::auto_flags_enabled(ctx, msg.guild_id).await {
+        auto_flags = flagpresets::flags_for(&language, &parse_result.code);
+        if !auto_flags.is_empty() {
+            parse_result.options = auto_flags.clone();
+            parse_result.flags_source = FlagSource::Auto;
+            if let Some(builder) = builder.as_mut() {
+                builder.options(parse_result.options.clone());
+            }
+        }
+    }
+
+    // ;check swaps the full build for the toolchain's syntax-only pass, or for wandbox's
+    // interpreted languages a checker program which is run with the code as its input
+    // records & stats are about the user's code, even when a checker takes its place
+    let code = parse_result.code.clone();
+    let mut checker = false;
+    let mut stops_early = false;
+    if parse_result.syntax_only {
+        match (syntax::syntax_flag(&language), syntax::checker(&language)) {
+            (Some(flag), _) => {
+                parse_result.options.push(String::from(flag));
+                stops_early = true;
+            }
+            (None, Some(program)) if builder.is_some() => {
+                parse_result.stdin = parse_result.code.clone();
+                parse_result.code = String::from(program);
+                parse_result.stdin_file = false;
+                stops_early = true;
+                checker = true;
+            }
+            _ => {
+                return Err(CommandError::from(format!(
+                    "{} has no syntax-only mode, use the compile command instead",
+                    language
+                )));
+            }
+        }
+    }
+
+    // some languages may only be compiled, the backend must never get to run those.
+    // Wandbox builds stop before there's a program, languages that can't do that are refused.
+    // Godbolt only ever runs what it's asked to, not executing is all it takes there
+    let allowed = execution_allowed(ctx, &language).await;
+    let execution_disabled = execute && !allowed;
+    if !allowed {
+        if assembler.is_some() {
+            return Err(CommandError::from("Running assembly is disabled"));
+        }
+        if !stops_early && builder.is_some() {
+            parse_result.options.push(String::from(compile_only_flag(&language)?));
+            stops_early = true;
+        }
+        execute = false;
+    }
+    // the checker's verdict is what it prints & its exit code, so it's ran like any program
+    if checker {
+        execute = true;
+    }
+
+    // ;compile doesn't care for the program, so wandbox builds stop before there is one where
+    // they can. Any other language still runs on wandbox, its output just isn't shown
+    let mut ran_anyway = false;
+    if !execute && !stops_early && builder.is_some() {
+        match syntax::compile_only_flag(&language) {
+            Some(flag) => parse_result.options.push(String::from(flag)),
+            None => ran_anyway = true,
+        }
+    }
+    if let Some(builder) = builder.as_mut() {
+        builder.options(parse_result.options.clone());
     }
 
     // send out loading emote
@@ -94,14 +836,59 @@ pub async fn compile(ctx: &Context, msg: &Message, _args: Args) -> CommandResult
     };
 
     // dispatch our req
-    let mut result = match builder.dispatch().await {
+    // --stream only applies to runs posted in the channel, everything else waits for the results
+    let to_dm = parse_result.dm && msg.guild_id.is_some();
+    let streaming = parse_result.stream && execute && !to_dm;
+    let queue_position = {
+        let data_read = ctx.data.read().await;
+        data_read.get::<CompileLimiterCache>().unwrap().queue_position()
+    };
+
+    // with every slot taken the user is told they're queued, unless it's over before QUEUE_NOTICE_DELAY
+    let mut queued = None;
+    let dispatched = {
+        let request = async {
+            match (&builder, &godbolt, assembler) {
+                (Some(builder), _, _) if streaming => {
+                    dispatch_streaming(ctx, msg, builder, &parse_result)
+                        .await
+                        .map(|(result, truncated, message)| (result, truncated, Some(message)))
+                }
+                (Some(builder), _, _) => with_no_live(dispatch(ctx, builder, &parse_result).await),
+                (None, Some((compiler_id, lang)), _) => with_no_live(dispatch_godbolt(ctx, &parse_result, compiler_id, lang, execute).await),
+                (None, None, Some(assembler)) => with_no_live(dispatch_assembly(ctx, &parse_result, assembler).await),
+                (None, None, None) => unreachable!("only godbolt requests skip building a wandbox request"),
+            }
+        };
+        tokio::pin!(request);
+
+        match queue_position {
+            Some(position) => {
+                tokio::select! {
+                    dispatched = &mut request => dispatched,
+                    _ = tokio::time::delay_for(Duration::from_secs(QUEUE_NOTICE_DELAY)) => {
+                        let notice = format!("⏳ Queued, position ~{}", position);
+                        queued = msg.channel_id.say(&ctx.http, notice).await.ok();
+                        request.await
+                    }
+                }
+            }
+            None => request.await,
+        }
+    };
+    let (mut result, truncated, live) = match dispatched {
         Ok(r) => r,
         Err(e) => {
             // we failed, lets remove the loading react so it doesn't seem like we're still processing
             msg.delete_reaction_emoji(&ctx.http, reaction.emoji.clone())
                 .await?;
+            if let Some(notice) = queued {
+                if notice.delete(&ctx.http).await.is_err() {
+                    warn!("Unable to clean up queue notice {}", notice.id.0);
+                }
+            }
 
-            return Err(CommandError::from(format!("{}", e)));
+            return Err(e);
         }
     };
 
@@ -118,49 +905,253 @@ pub async fn compile(ctx: &Context, msg: &Message, _args: Args) -> CommandResult
         }
     }
 
+    result.libraries = parse_result.libraries.clone();
+    result.auto_flags = auto_flags;
+    result.runtime = runtime;
+    result.stdin_bytes = if parse_result.stdin_file { Some(parse_result.stdin.len()) } else { None };
+    result.timing = parse_result.timing;
+    result.spoiler = parse_result.spoiler;
+    postprocess::apply(&language, &mut result);
+
+    // compile-only requests don't care how the program itself did
+    let success = if execute { result.succeeded() } else { result.compiled() };
+
+    // --stderr-only hides the program's regular output
+    if parse_result.stderr_only {
+        result.stdout.clear();
+    }
+
+    // minimal mode boils successful runs down to a reaction on the command, see events::expand_output
+    let minimal = success && !to_dm && live.is_none() && discordhelpers::minimal_mode(ctx, msg.guild_id).await;
+
+    // long outputs can optionally be moved off into their own thread
+    let mut output_lines = result.compiler_output.lines().count();
+    if execute {
+        output_lines += result.stdout.lines().count() + result.stderr.lines().count();
+    }
+    let mut thread = None;
+    if !to_dm && !minimal && live.is_none() && msg.guild_id.is_some() && output_lines > THREAD_LINE_THRESHOLD {
+        if let Ok(toggle) = env::var("OUTPUT_IN_THREAD") {
+            if toggle == "1" || toggle.eq_ignore_ascii_case("true") {
+                let name = format!("Compilation results for {}", msg.author.name);
+                thread = threads::start_thread(msg.channel_id.0, msg.id.0, &name)
+                    .await
+                    .map(ChannelId);
+            }
+        }
+    }
+
     // Dispatch our request
-    let emb = discordhelpers::build_compilation_embed(&msg.author, &mut result);
-    let mut emb_msg = discordhelpers::embed_message(emb);
-    let compilation_embed = msg
-        .channel_id
-        .send_message(&ctx.http, |_| &mut emb_msg)
-        .await?;
+    let mut emb = discordhelpers::build_compilation_embed(&msg.author, &result, execute, parse_result.flags_source);
+    if truncated {
+        discordhelpers::add_truncation_note(&mut emb, &result);
+    }
+    if parse_result.ignored_blocks > 0 {
+        discordhelpers::add_ignored_blocks_note(&mut emb, parse_result.ignored_blocks);
+    }
+    if !parse_result.headers.is_empty() {
+        discordhelpers::add_headers_note(&mut emb, &parse_result.headers);
+    }
+    if execution_disabled {
+        discordhelpers::add_execution_disabled_note(&mut emb, &language);
+    }
+    if ran_anyway {
+        discordhelpers::add_ran_anyway_note(&mut emb, &language);
+    }
+    if let Some(reason) = &fallback {
+        discordhelpers::add_backend_fallback_note(&mut emb, reason);
+    }
+    if discordhelpers::logos_enabled(ctx, msg.guild_id).await {
+        discordhelpers::add_language_logo(&mut emb, &language);
+    }
+    if discordhelpers::first_compile(ctx, msg).await {
+        discordhelpers::add_onboarding_tip(&mut emb);
+    }
+    let theme = discordhelpers::theme_color(ctx, msg.guild_id).await;
+    discordhelpers::apply_theme(&mut emb, theme);
+    if parse_result.verbose {
+        let mut details = vec![
+            ("Backend", String::from(result.backend)),
+            ("Language", language.clone()),
+            ("Target", result.compiler.clone()),
+            ("Flags", parse_result.options.join(" ")),
+            ("Execute", execute.to_string()),
+            ("Raw status", result.status()),
+            ("Time", format!("{}ms", result.compile_time_ms)),
+        ];
+        // godbolt's compiler ids don't say much on their own
+        if let Some((compiler_id, _)) = &godbolt {
+            let data_read = ctx.data.read().await;
+            let godbolt = data_read.get::<GodboltCache>().unwrap().read().await;
+            if let Some(compiler) = godbolt.resolve(compiler_id) {
+                details.insert(3, ("Compiler", compiler.name));
+            }
+        }
+        discordhelpers::add_request_details(&mut emb, &details);
+    }
+
+    // the queue notice is only replaced by results that would've been posted in the channel
+    let plain = !minimal && live.is_none() && thread.is_none() && !to_dm;
+    if let Some(notice) = &queued {
+        if !plain && notice.delete(&ctx.http).await.is_err() {
+            warn!("Unable to clean up queue notice {}", notice.id.0);
+        }
+    }
+
+    // the command message stands in for our output, so it's left alone even with auto-delete on
+    if minimal {
+        msg.react(&ctx.http, discordhelpers::build_reaction(success_id, &success_name)).await?;
+        msg.react(&ctx.http, ReactionType::Unicode(String::from(EXPAND_EMOJI))).await?;
+        {
+            let data = ctx.data.read().await;
+            let mut expandable = data.get::<ExpandCache>().unwrap().lock().await;
+            let record = OutputRecord::new(msg, &language, &code);
+            expandable.insert(msg.id.0, (msg.author.id.0, emb, record));
+        }
+
+        track_compilation(ctx, msg, &language, &code, success).await;
+        debug!("Command executed");
+        return Ok(());
+    }
+
+    // --dm results go privately to the user, if that fails they land in the channel instead.
+    // The DM is tracked like any other output, so deleting the command still cleans it up
+    let mut direct = None;
+    if to_dm {
+        direct = discordhelpers::send_dm(ctx, &msg.author, emb.clone()).await;
+        match direct {
+            Some(_) => {
+                let sent = ReactionType::Unicode(String::from(DM_EMOJI));
+                if msg.react(&ctx.http, sent).await.is_err() {
+                    // the results arrived anyway, nothing to worry about
+                }
+            }
+            None => discordhelpers::add_dm_failed_note(&mut emb),
+        }
+    }
+    let compilation_embed = match (live, direct, thread) {
+        (Some(mut m), _, _) => {
+            m.edit(&ctx.http, |m| m.embed(|mut e| { e.0 = emb.0; e })).await?;
+            m
+        }
+        (None, Some(m), _) => m,
+        (None, None, Some(thread_id)) => {
+            match discordhelpers::send_embed(thread_id, &ctx.http, emb.clone()).await {
+                Ok(m) => m,
+                Err(_e) => {
+                    // don't leave an empty thread lying around, just post inline instead
+                    if thread_id.delete(&ctx.http).await.is_err() {
+                        warn!("Unable to clean up empty thread {}", thread_id.0);
+                    }
+
+                    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?
+                }
+            }
+        }
+        (None, None, None) => match discordhelpers::replace_output(ctx, msg, &emb).await {
+            // an edited command, its old results are updated in place
+            Some(m) => {
+                if let Some(notice) = &queued {
+                    if plain && notice.delete(&ctx.http).await.is_err() {
+                        warn!("Unable to clean up queue notice {}", notice.id.0);
+                    }
+                }
+                m
+            }
+            None => match queued {
+                Some(mut m) if plain => {
+                    m.edit(&ctx.http, |m| m.content("").embed(|mut e| { e.0 = emb.0; e })).await?;
+                    m
+                }
+                _ => discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?,
+            },
+        },
+    };
+
+    discordhelpers::record_output(
+        ctx,
+        &compilation_embed,
+        OutputRecord::new(msg, &language, &code),
+    ).await;
+    discordhelpers::record_command(ctx, msg, plain).await;
 
     // Success/fail react
     let reaction;
-    if result.status == "0" {
+    if success {
         reaction = discordhelpers::build_reaction(success_id, &success_name);
     } else {
         reaction = ReactionType::Unicode(String::from("❌"));
     }
     compilation_embed.react(&ctx.http, reaction).await?;
 
-    let data = ctx.data.read().await;
-    let stats = data.get::<StatsManagerCache>().unwrap().lock().await;
-    if stats.should_track() {
-        stats.compilation(&builder.lang, result.status == "1").await;
+    // runs can be repeated with new input through a reaction, see events::prompt_stdin
+    if execute {
+        let rerun_reaction = ReactionType::Unicode(String::from(RERUN_EMOJI));
+        if compilation_embed.react(&ctx.http, rerun_reaction).await.is_ok() {
+            let data = ctx.data.read().await;
+            let mut reruns = data.get::<RerunCache>().unwrap().lock().await;
+            reruns.insert(compilation_embed.id.0, (msg.author.id.0, rerun));
+        }
+    }
+
+    track_compilation(ctx, msg, &language, &code, success).await;
+
+    // once the command message is gone there's nothing left to link our output to
+    if discordhelpers::auto_delete_command(ctx, msg).await {
+        debug!("Command executed");
+        return Ok(());
+    }
+
+    let data_read = ctx.data.read().await;
+    let mut delete_cache = data_read.get::<MessageDeleteCache>().unwrap().lock().await;
+    delete_cache.insert(msg.id.0, compilation_embed.clone());
+    debug!("Command executed");
+    Ok(())
+}
+
+/// Publishes a finished compilation to our stats, failure rate alerts & the compile log
+async fn track_compilation(ctx: &Context, msg: &Message, language: &str, code: &str, success: bool) {
+    let alert = {
+        let data = ctx.data.read().await;
+        let mut stats = data.get::<StatsManagerCache>().unwrap().lock().await;
+        let alert = match msg.guild_id {
+            Some(guild) => stats.guild_compilation(guild.0, language, !success),
+            None => None,
+        };
+        if stats.should_track() {
+            stats.compilation(language, !success).await;
+        }
+        metrics::record(&data, |m| m.compilation(language, !success));
+        alert
+    };
+    if let (Some(alert), Some(guild)) = (alert, msg.guild_id) {
+        discordhelpers::report_failure_rate(ctx, guild.0, &alert).await;
     }
 
     let mut guild = String::from("<unknown>");
     if let Some(g) = msg.guild_id {
-        guild = g.to_string()
+        guild = g.to_string();
+
+        let log = {
+            let data = ctx.data.read().await;
+            let settings = data.get::<SettingsCache>().unwrap().read().await;
+            settings.compile_log(g.0)
+        };
+        if let Some(channel) = log {
+            let emb = discordhelpers::build_guild_complog_embed(success, language, &msg.author, msg.channel_id);
+            discordhelpers::manual_dispatch(ctx.http.clone(), channel, emb).await;
+        }
     }
     if let Ok(log) = env::var("COMPILE_LOG") {
         if let Ok(id) = log.parse::<u64>() {
             let emb = discordhelpers::build_complog_embed(
-                result.status == "1",
-                &parse_result.code,
-                &builder.lang,
+                !success,
+                code,
+                language,
                 &msg.author.tag(),
                 &guild,
             );
             discordhelpers::manual_dispatch(ctx.http.clone(), id, emb).await;
         }
     }
-
-    let data_read = ctx.data.read().await;
-    let mut delete_cache = data_read.get::<MessageDeleteCache>().unwrap().lock().await;
-    delete_cache.insert(msg.id.0, compilation_embed.clone());
-    debug!("Command executed");
-    Ok(())
 }
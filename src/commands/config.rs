@@ -0,0 +1,188 @@
+use std::env;
+
+use serenity::builder::CreateEmbed;
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::{SettingsCache, WandboxCache};
+use crate::utls::constants::MAX_PREFIX_LEN;
+use crate::utls::discordhelpers;
+
+#[command]
+#[only_in(guilds)]
+#[sub_commands(prefix, language, channels)]
+pub async fn config(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    require_admin(ctx, msg).await?;
+
+    let guild_id = msg.guild_id.unwrap();
+    let default_prefix = env::var("BOT_PREFIX").unwrap_or_default();
+    let (prefix, language, channels) = {
+        let data = ctx.data.read().await;
+        let settings = data.get::<SettingsCache>().unwrap().read().await;
+        match settings.guild(guild_id.0) {
+            Some(g) => {
+                let channels = if g.allowed_channels.is_empty() {
+                    String::from("Anywhere")
+                } else {
+                    g.allowed_channels.iter().map(|c| format!("<#{}>", c)).collect::<Vec<String>>().join(", ")
+                };
+                (g.prefix.clone(), g.language.clone(), channels)
+            }
+            None => (None, None, String::from("Anywhere")),
+        }
+    };
+
+    let mut emb = CreateEmbed::default();
+    emb.title("Server configuration");
+    emb.color(discordhelpers::theme_color(ctx, msg.guild_id).await);
+    emb.field("Prefix", match prefix {
+        Some(p) => format!("`{}` (and `{}`)", p, default_prefix),
+        None => format!("`{}` (default)", default_prefix),
+    }, true);
+    emb.field("Default language", language.map(|l| format!("`{}`", l)).unwrap_or_else(|| String::from("None")), true);
+    emb.field("Compile channels", channels, false);
+    emb.footer(|f| f.text(format!(
+        "Change these with {}config prefix, {}config language & {}config channels",
+        default_prefix, default_prefix, default_prefix
+    )));
+
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+    debug!("Command executed");
+    Ok(())
+}
+
+#[command]
+async fn prefix(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require_admin(ctx, msg).await?;
+
+    let prefix = match args.parse::<String>() {
+        Ok(p) => p.trim().to_string(),
+        Err(_e) => {
+            return Err(CommandError::from(
+                "No prefix specified!\nSupply a prefix, or 'clear' to go back to just the default",
+            ));
+        }
+    };
+    if prefix.len() > MAX_PREFIX_LEN || prefix.contains(char::is_whitespace) || prefix.contains('`') {
+        return Err(CommandError::from(format!(
+            "Prefixes can be at most {} characters long, without spaces or backticks",
+            MAX_PREFIX_LEN
+        )));
+    }
+
+    let cleared = prefix == "clear";
+    {
+        let data = ctx.data.read().await;
+        let mut settings = data.get::<SettingsCache>().unwrap().write().await;
+        settings.guild_mut(msg.guild_id.unwrap().0).prefix = if cleared { None } else { Some(prefix.clone()) };
+        settings.write();
+    }
+
+    let reply = if cleared {
+        String::from("Cleared this server's prefix, only the default prefix will work")
+    } else {
+        format!("Set this server's prefix to `{}`, the default prefix & mentions still work too", prefix)
+    };
+    msg.channel_id.say(&ctx.http, reply).await?;
+    debug!("Command executed");
+    Ok(())
+}
+
+#[command]
+async fn language(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require_admin(ctx, msg).await?;
+
+    let language = match args.parse::<String>() {
+        Ok(s) => s.trim().to_lowercase(),
+        Err(_e) => {
+            return Err(CommandError::from(
+                "No language specified!\nSupply a language, or 'clear' to remove this server's default",
+            ));
+        }
+    };
+
+    let data = ctx.data.read().await;
+    if language != "clear" {
+        let wbox = data.get::<WandboxCache>().unwrap().read().await;
+        if wbox.get_compilers(&language).is_none() {
+            return Err(CommandError::from(format!(
+                "Could not find language '{}'",
+                &language
+            )));
+        }
+    }
+
+    let reply = {
+        let mut settings = data.get::<SettingsCache>().unwrap().write().await;
+        let guild = settings.guild_mut(msg.guild_id.unwrap().0);
+        let reply = if language == "clear" {
+            guild.language = None;
+            String::from("Cleared this server's default language")
+        } else {
+            guild.language = Some(language.clone());
+            format!("Set this server's default language to `{}`, channel languages still take priority", &language)
+        };
+        settings.write();
+        reply
+    };
+
+    msg.channel_id.say(&ctx.http, reply).await?;
+    debug!("Command executed");
+    Ok(())
+}
+
+#[command]
+async fn channels(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    require_admin(ctx, msg).await?;
+
+    let mut allowed = Vec::new();
+    if args.current() != Some("clear") {
+        for arg in args.iter::<ChannelId>() {
+            match arg {
+                Ok(channel) => allowed.push(channel.0),
+                Err(_e) => {
+                    return Err(CommandError::from(
+                        "Invalid channel, please mention channels like #bot-spam or use 'clear'",
+                    ));
+                }
+            }
+        }
+        if allowed.is_empty() {
+            return Err(CommandError::from(
+                "No channels specified!\nMention the channels compiling is allowed in, or 'clear' to allow it everywhere",
+            ));
+        }
+    }
+
+    {
+        let data = ctx.data.read().await;
+        let mut settings = data.get::<SettingsCache>().unwrap().write().await;
+        let guild = settings.guild_mut(msg.guild_id.unwrap().0);
+        guild.allowed_channels.clear();
+        guild.allowed_channels.extend(allowed.iter().copied());
+        settings.write();
+    }
+
+    let reply = if allowed.is_empty() {
+        String::from("No channel restrictions remain, compiling is allowed everywhere")
+    } else {
+        format!(
+            "Compiling is now only allowed in {}",
+            allowed.iter().map(|c| format!("<#{}>", c)).collect::<Vec<String>>().join(", ")
+        )
+    };
+    msg.channel_id.say(&ctx.http, reply).await?;
+    debug!("Command executed");
+    Ok(())
+}
+
+async fn require_admin(ctx: &Context, msg: &Message) -> Result<(), CommandError> {
+    if discordhelpers::is_admin(ctx, msg).await {
+        Ok(())
+    } else {
+        Err(CommandError::from(
+            "Only server administrators may view or change this server's configuration",
+        ))
+    }
+}
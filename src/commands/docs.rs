@@ -0,0 +1,41 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::utls::{discordhelpers, docs};
+
+#[command]
+pub async fn docs(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let language = match args.single::<String>() {
+        Ok(s) => s.to_lowercase(),
+        Err(_e) => {
+            return Err(CommandError::from(format!(
+                "No language specified!\nI have documentation for: {}",
+                docs::languages().join(", ")
+            )));
+        }
+    };
+    let language = docs::normalize(&language);
+
+    let query = args.rest().trim();
+    let query = if query.is_empty() { None } else { Some(query) };
+
+    let link = match docs::lookup(language, query) {
+        Some(l) => l,
+        None => {
+            return Err(CommandError::from(format!(
+                "I don't have documentation for '{}'\nI have documentation for: {}",
+                language,
+                docs::languages().join(", ")
+            )));
+        }
+    };
+
+    let mut emb = discordhelpers::build_docs_embed(&msg.author, language, query, &link);
+    let theme = discordhelpers::theme_color(ctx, msg.guild_id).await;
+    discordhelpers::apply_theme(&mut emb, theme);
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+
+    debug!("Command executed");
+    Ok(())
+}
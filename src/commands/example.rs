@@ -0,0 +1,58 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::commands::compile;
+use crate::utls::parser::{self, ParserResult};
+use crate::utls::{discordhelpers, programs};
+
+#[command]
+#[bucket = "nospam"]
+pub async fn example(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let names = || programs::list().iter().map(|(name, _)| *name).collect::<Vec<&str>>().join(", ");
+    let name = match args.single::<String>() {
+        Ok(name) => name.to_lowercase(),
+        Err(_) => {
+            return Err(CommandError::from(format!(
+                "Usage: `;example <name> [language]` or `;example list`\nAvailable examples: {}",
+                names()
+            )));
+        }
+    };
+
+    if name == "list" {
+        let emb = discordhelpers::build_programs_embed(&msg.author, &programs::list(), programs::PROGRAMS_VERSION);
+        discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+        debug!("Command executed");
+        return Ok(());
+    }
+
+    let language = args.single::<String>().ok().map(|l| String::from(parser::normalize_language(&l.to_lowercase())));
+
+    let (language, code) = match programs::find(&name, language.as_deref()) {
+        Some(program) => program,
+        None => {
+            let available = programs::list().into_iter().find(|(n, _)| *n == name);
+            return Err(CommandError::from(match (available, language) {
+                (Some((_, languages)), Some(language)) => format!(
+                    "'{}' isn't available in {}, try one of: {}",
+                    name, language, languages.join(", ")
+                ),
+                _ => format!("There's no example called '{}'\nAvailable examples: {}", name, names()),
+            }));
+        }
+    };
+
+    // show off the source before we show its output
+    msg.channel_id
+        .say(&ctx.http, format!("```{}\n{}\n```", language, code))
+        .await?;
+
+    let parse_result = ParserResult {
+        target: String::from(language),
+        code: String::from(code),
+        ..Default::default()
+    };
+
+    compile::handle_request(ctx, msg, parse_result, true).await
+}
@@ -0,0 +1,46 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::SettingsCache;
+use crate::utls::constants::MAX_OUTPUT_EXPIRY;
+use crate::utls::{discordhelpers, schedule};
+
+#[command]
+#[only_in(guilds)]
+pub async fn expiry(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    if !discordhelpers::is_admin(ctx, msg).await {
+        return Err(CommandError::from(
+            "Only server administrators may change when compilation results expire",
+        ));
+    }
+
+    let usage = "Usage: `expiry <duration | off>`, e.g. `expiry 5m` or `expiry 1h30m`";
+    let minutes = match args.current().map(|s| s.to_lowercase()).as_deref() {
+        Some("off") => None,
+        Some(duration) => match schedule::parse_duration(duration) {
+            Some(m) if m > 0 && m <= MAX_OUTPUT_EXPIRY => Some(m),
+            Some(_) => {
+                return Err(CommandError::from(format!(
+                    "Results can expire after at most {} hours",
+                    MAX_OUTPUT_EXPIRY / 60
+                )));
+            }
+            None => return Err(CommandError::from(usage)),
+        },
+        None => return Err(CommandError::from(usage)),
+    };
+
+    let data = ctx.data.read().await;
+    let mut settings = data.get::<SettingsCache>().unwrap().write().await;
+    settings.guild_mut(msg.guild_id.unwrap().0).output_expiry = minutes;
+    settings.write();
+
+    let reply = match minutes {
+        Some(m) => format!("Compilation results will now be deleted {} minute(s) after they're posted", m),
+        None => String::from("Compilation results will no longer expire"),
+    };
+    msg.channel_id.say(&ctx.http, reply).await?;
+    debug!("Command executed");
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use std::borrow::Cow;
+
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::http::AttachmentType;
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::{OwnersCache, StatsManagerCache};
+use crate::utls::constants::GUILD_STATS_DAYS;
+use crate::utls::discordhelpers;
+
+#[command]
+#[only_in(guilds)]
+pub async fn exportstats(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let owner = {
+        let data = ctx.data.read().await;
+        data.get::<OwnersCache>().unwrap().contains(&msg.author.id)
+    };
+    if !owner && !discordhelpers::is_admin(ctx, msg).await {
+        return Err(CommandError::from(
+            "Only server administrators may export this server's statistics",
+        ));
+    }
+
+    let format = match args.current().map(|s| s.to_lowercase()).as_deref() {
+        None | Some("json") => "json",
+        Some("csv") => "csv",
+        _ => return Err(CommandError::from("Usage: `exportstats [json | csv]`")),
+    };
+
+    let guild_id = msg.guild_id.unwrap().0;
+    let export = {
+        let data = ctx.data.read().await;
+        let mut stats = data.get::<StatsManagerCache>().unwrap().lock().await;
+        match stats.guild_stats(guild_id) {
+            Some(s) if format == "csv" => s.to_csv(),
+            Some(s) => s.to_json(),
+            None => {
+                return Err(CommandError::from(
+                    "Nothing has been recorded for this server yet",
+                ));
+            }
+        }
+    };
+
+    let file = AttachmentType::Bytes {
+        data: Cow::from(export.into_bytes()),
+        filename: format!("{}-stats.{}", guild_id, format),
+    };
+    msg.channel_id
+        .send_files(&ctx.http, vec![file], |m| {
+            m.content(format!(
+                "Here's this server's usage, daily data covers the last {} days",
+                GUILD_STATS_DAYS
+            ))
+        })
+        .await?;
+
+    debug!("Command executed");
+    Ok(())
+}
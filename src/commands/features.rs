@@ -0,0 +1,112 @@
+use std::env;
+
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::{OwnersCache, SettingsCache, StatsManagerCache};
+use crate::commands::compile;
+use crate::utls::constants::DEFAULT_SUPPORTER_COOLDOWN;
+use crate::utls::{compileresult, discordhelpers, persistence, trusted};
+
+#[command]
+#[only_in(guilds)]
+pub async fn features(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let owner = {
+        let data = ctx.data.read().await;
+        data.get::<OwnersCache>().unwrap().contains(&msg.author.id)
+    };
+    if !owner && !discordhelpers::is_admin(ctx, msg).await {
+        return Err(CommandError::from(
+            "Only server administrators may view this server's features",
+        ));
+    }
+
+    let guild_id = msg.guild_id.unwrap();
+    let backend = compile::preferred_backend(ctx, Some(guild_id)).await;
+    let mut guild = Vec::new();
+    {
+        let data = ctx.data.read().await;
+        let settings = data.get::<SettingsCache>().unwrap().read().await;
+        let chosen_backend = settings.backend(guild_id.0).is_some();
+        let tips = settings.tips_enabled(guild_id.0);
+        let logos = settings.logos_enabled(guild_id.0);
+        let minimal = settings.minimal_mode(guild_id.0);
+        let auto_flags = settings.auto_flags_enabled(guild_id.0);
+        let ack = settings.ack_reaction(guild_id.0);
+        match settings.guild(guild_id.0) {
+            Some(g) => {
+                let channels = if g.allowed_channels.is_empty() {
+                    String::from("Anywhere")
+                } else {
+                    g.allowed_channels.iter().map(|c| format!("<#{}>", c)).collect::<Vec<String>>().join(", ")
+                };
+                let roles = if g.required_roles.is_empty() {
+                    String::from("None")
+                } else {
+                    g.required_roles.iter().map(|(category, role)| format!("{}: <@&{}>", category, role)).collect::<Vec<String>>().join("\n")
+                };
+                let supporters = match g.supporter_role {
+                    Some(role) => format!("<@&{}>", role),
+                    None => String::from("None"),
+                };
+
+                guild.push(("Prefix", g.prefix.clone().unwrap_or_else(|| String::from("Default"))));
+                guild.push(("Default language", g.language.clone().unwrap_or_else(|| String::from("None"))));
+                guild.push(("Compile channels", channels));
+                guild.push(("Required roles", roles));
+                guild.push(("Auto-delete commands", on_off(g.auto_delete_command)));
+                guild.push(("Minimum account age", g.min_account_age.map(|d| format!("{} days", d)).unwrap_or_else(|| String::from("Off"))));
+                guild.push(("Result expiry", g.output_expiry.map(|m| format!("{} minutes", m)).unwrap_or_else(|| String::from("Off"))));
+                guild.push(("Theme", g.theme.map(|t| format!("#{:06X}", t)).unwrap_or_else(|| String::from("Default"))));
+                guild.push(("Supporter role", supporters));
+                guild.push(("Booster exemption", on_off(g.booster_exemption)));
+                guild.push(("Supporter cooldown", format!("{}%", g.supporter_cooldown.unwrap_or(DEFAULT_SUPPORTER_COOLDOWN))));
+                guild.push(("Snippets", g.snippets.len().to_string()));
+                guild.push(("Compile log", g.compile_log.map(|c| format!("<#{}>", c)).unwrap_or_else(|| String::from("Off"))));
+                guild.push(("Announcements", g.announcement_channel.map(|c| format!("<#{}>", c)).unwrap_or_else(|| String::from("Off"))));
+            }
+            None => guild.push(("Settings", String::from("Nothing has been configured, defaults are in use"))),
+        }
+        let backend = if chosen_backend { backend } else { format!("{} (default)", backend) };
+        guild.push(("Backend", backend));
+        guild.push(("First compilation tips", on_off(tips)));
+        guild.push(("Language logos", on_off(logos)));
+        guild.push(("Minimal mode", on_off(minimal)));
+        guild.push(("Automatic flags", on_off(auto_flags)));
+        guild.push(("Acknowledgement reaction", on_off(ack)));
+    }
+
+    // owners get to see the global configuration these are layered on top of
+    let global = if owner {
+        let tracking = {
+            let data = ctx.data.read().await;
+            let stats = data.get::<StatsManagerCache>().unwrap().lock().await;
+            stats.should_track()
+        };
+        let threads = env::var("OUTPUT_IN_THREAD")
+            .map(|t| t == "1" || t.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+
+        Some(vec![
+            ("Trusted guild", on_off(trusted::is_trusted(guild_id.0))),
+            ("Statistics tracking", on_off(tracking)),
+            ("Output in threads", on_off(threads)),
+            ("Output collapse threshold", compileresult::collapse_threshold().to_string()),
+            ("Backup interval", format!("{} minutes", persistence::backup_interval())),
+            ("Rate limit threshold", format!("{}ms", discordhelpers::ratelimit_threshold())),
+        ])
+    } else {
+        None
+    };
+
+    let emb = discordhelpers::build_features_embed(&msg.author, &guild, global.as_deref());
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+
+    debug!("Command executed");
+    Ok(())
+}
+
+fn on_off(enabled: bool) -> String {
+    String::from(if enabled { "On" } else { "Off" })
+}
@@ -0,0 +1,69 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::apis::formatter;
+use crate::utls::constants::DIFF_CONTEXT_LINES;
+use crate::utls::{diff, discordhelpers, parser};
+
+#[command]
+#[bucket = "nospam"]
+pub async fn fmtdiff(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let code = match parser::first_code_block(&msg.content) {
+        Some(c) => c,
+        None => {
+            return Err(CommandError::from(
+                "Unable to find a code block to format!\nDid you forget to wrap your code in ```?",
+            ));
+        }
+    };
+
+    // an explicit language wins over the code block's tag
+    let language = match args.single::<String>() {
+        Ok(s) if !s.starts_with('`') => Some(s.to_lowercase()),
+        _ => parser::code_block_language(&msg.content),
+    };
+    let language = match language {
+        Some(l) => l,
+        None => {
+            return Err(CommandError::from(format!(
+                "No language specified!\nI can format: {}",
+                formatter::languages().join(", ")
+            )));
+        }
+    };
+
+    let formatter_name = match formatter::formatter_for(&language) {
+        Some(f) => f,
+        None => {
+            return Err(CommandError::from(format!(
+                "I don't have a formatter for '{}'\nI can format: {}",
+                language,
+                formatter::languages().join(", ")
+            )));
+        }
+    };
+
+    let formatted = match formatter::format(&language, &code).await {
+        Ok(Ok(f)) => f,
+        Ok(Err(e)) => {
+            return Err(CommandError::from(format!(
+                "{} was unable to format your code\n```\n{}\n```",
+                formatter_name,
+                discordhelpers::conform_external_str(&e)
+            )));
+        }
+        Err(e) => {
+            return Err(CommandError::from(format!("Formatter request failed!\n\n{}", e)));
+        }
+    };
+
+    let changes = diff::unified(&code, &formatted, DIFF_CONTEXT_LINES);
+    let mut emb = discordhelpers::build_fmtdiff_embed(&msg.author, formatter_name, changes.as_deref());
+    let theme = discordhelpers::theme_color(ctx, msg.guild_id).await;
+    discordhelpers::apply_theme(&mut emb, theme);
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+
+    debug!("Command executed");
+    Ok(())
+}
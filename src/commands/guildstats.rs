@@ -0,0 +1,35 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::StatsManagerCache;
+use crate::utls::discordhelpers;
+
+#[command]
+#[only_in(guilds)]
+pub async fn guildstats(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    if !discordhelpers::is_admin(ctx, msg).await {
+        return Err(CommandError::from(
+            "Only server administrators may view this server's statistics",
+        ));
+    }
+
+    let guild_id = msg.guild_id.unwrap();
+    let guild_name = match guild_id.to_partial_guild(&ctx.http).await {
+        Ok(g) => g.name,
+        Err(_) => String::from("Server"),
+    };
+
+    let mut emb = {
+        let data = ctx.data.read().await;
+        let mut stats = data.get::<StatsManagerCache>().unwrap().lock().await;
+        discordhelpers::build_guild_stats_embed(&guild_name, stats.guild_stats(guild_id.0))
+    };
+
+    let theme = discordhelpers::theme_color(ctx, msg.guild_id).await;
+    discordhelpers::apply_theme(&mut emb, theme);
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+
+    debug!("Command executed");
+    Ok(())
+}
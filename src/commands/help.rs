@@ -1,5 +1,3 @@
-use std::env;
-
 use serenity::{
     builder::CreateEmbed,
     framework::standard::{macros::command, Args, CommandResult},
@@ -10,9 +8,69 @@ use serenity::{
 use crate::utls::constants::*;
 use crate::utls::discordhelpers;
 
+// Discord allows 25 fields to an embed, so commands are listed a category per field
+static CATEGORIES: &[(&str, &[(&str, &str)])] = &[
+    ("Compiling", &[
+        ("compile", "Compiles a script without running it"),
+        ("run", "Compiles and runs a script"),
+        ("check", "Checks your code's syntax"),
+        ("asm", "Outputs the assembly for the input code"),
+        ("swap", "Re-runs your last compilation with another compiler"),
+        ("compare", "Runs programs side by side for comparison"),
+        ("race", "Ranks the same program in several languages by speed"),
+        ("optcompare", "Compares the assembly of your code at each optimization level"),
+        ("example", "Runs one of our example programs"),
+        ("random", "Compiles a random example program"),
+        ("snippet", "Runs this server's saved snippets"),
+        ("fmtdiff", "Shows what formatting would change"),
+    ]),
+    ("Languages", &[
+        ("languages", "Displays all supported languages"),
+        ("compilers", "Displays the compilers for the specified language"),
+        ("libs", "Lists libraries usable with --lib"),
+        ("version", "Shows the runtime version for a language"),
+        ("docs", "Links a language's documentation"),
+        ("backends", "Lists the available compilation backends"),
+        ("myflags", "Saves your default flags for a language"),
+    ]),
+    ("Bot", &[
+        ("invite", "Grabs the bot's invite link"),
+        ("botinfo", "Displays information about the bot"),
+        ("perms", "Shows which permissions I have in this channel"),
+        ("shard", "Displays this server's shard"),
+    ]),
+    ("Server settings", &[
+        ("config", "Shows or changes this server's configuration"),
+        ("features", "Shows this server's active features"),
+        ("preview", "Previews results with this server's settings"),
+        ("settheme", "Sets this server's embed color"),
+        ("logos", "Toggles language logos on results"),
+        ("minimal", "Toggles minimal compilation output"),
+        ("tips", "Toggles first compilation tips"),
+        ("ackreaction", "Reacts to compile commands as soon as they're seen"),
+        ("autoflags", "Toggles automatic flags for headers needing them"),
+        ("autodelete", "Deletes command messages after compiling"),
+        ("expiry", "Deletes results after a while"),
+    ]),
+    ("Server administration", &[
+        ("setbackend", "Sets this server's compilation backend"),
+        ("setchannellang", "Sets this channel's default language"),
+        ("allowchannel", "Restricts compiling to a channel"),
+        ("denychannel", "Removes a compiling channel restriction"),
+        ("requirerole", "Requires a role for a command category"),
+        ("accountage", "Sets a minimum account age for compiling"),
+        ("supporters", "Reduces the cooldown for supporters"),
+        ("autocompile", "Runs every code block posted in this channel"),
+        ("setcompilelog", "Logs this server's compilations to a channel"),
+        ("announcements", "Subscribes a channel to announcements"),
+        ("guildstats", "Displays this server's usage statistics"),
+        ("exportstats", "Exports this server's usage statistics"),
+    ]),
+];
+
 #[command]
 pub async fn help(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
-    let prefix = env::var("BOT_PREFIX").expect("Bot prefix is not set!");
+    let prefix = discordhelpers::command_prefix(ctx, msg.guild_id).await;
     if !args.is_empty() {
         let cmd = args.parse::<String>().unwrap();
         let mut emb = CreateEmbed::default();
@@ -43,7 +101,53 @@ pub async fn help(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
                     ),
                     false,
                 );
-                "Sends a compilation request\n\n"
+                "Compiles your code without showing its output, reporting whether it compiled and any compiler errors or warnings\n\n\
+                C, C++, Rust and Haskell stop once the code is built without linking it. Other languages can't be built without running them, their program runs but its output is hidden\n\n\
+                Use `run` instead to also execute the program and see its output\n\n\
+                Code can also be attached as a source file (e.g. `main.cpp`) or given as a raw paste link with `< <url>`, the language can be left out when the file name tells us\n\n\
+                Put your program's input in a code block before your code, or anywhere when it's tagged ```stdin\n\n\
+                Add a header in its own code block starting with `// name.h` (or tagged `h`, which is included as `solution.h`) to #include it from your code\n\n\
+                Pass `--dm` before your code block to receive the results in your DMs instead\n\n\
+                Pass `--verbose` before your code block to see the exact request sent to the compiler"
+            }
+            "run" => {
+                emb.title("Run command");
+                emb.field(
+                    "Example",
+                    format!(
+                        "{}run c++\n\
+          \\`\\`\\`\n\
+          #include <iostream>\n\n\
+          int main() {{ \n\
+          \tstd::cout << \"Hello, world\";\n\
+          }}\n\
+          \\`\\`\\`\n",
+                        prefix
+                    ),
+                    false,
+                );
+                "Compiles and executes your code, displaying the program's output and exit code\n\n\
+                Use `compile` instead if you only want to check that your code compiles\n\n\
+                Use `asm` (nasm) or `gas` as the language to assemble & run raw assembly, see `asm` for viewing a compiler's assembly\n\n\
+                Reply to someone else's message with just `run <language>` to run the code in their message\n\n\
+                Too long for a message? Attach it as a source file (e.g. `main.cpp`) or give a raw paste link with `< <url>`, the language can be left out when the file name tells us. A code block is then taken as input\n\n\
+                Edit your message within a minute of sending it and your results are updated to match\n\n\
+                React with ➕ on your results to run the same code again with different input\n\n\
+                Add a header in its own code block starting with `// name.h` (or tagged `h`, which is included as `solution.h`) to #include it from your code\n\n\
+                Pass `--stderr-only` before your code block to only see what your program wrote to stderr\n\n\
+                Pass `--stream` before your code block to watch your program's output as it's produced\n\n\
+                Pass `--lib <name>` before your code block to build with a library, see `libs` for what's available\n\n\
+                Pass `--runtime <name>` before your code block to pick the runtime, like `--runtime 2` or `--runtime pypy` for python\n\n\
+                Pass `--timing` before your code block to see how long compiling & running took, where the backend tells us\n\n\
+                Pass `--spoiler` before your code block to hide your program's output behind a spoiler\n\n\
+                Pass `--stdin-file` before your code block and attach a .txt file to use it as your program's input\n\n\
+                Pass `--dm` before your code block to receive the results in your DMs instead\n\n\
+                Pass `--verbose` before your code block to see the exact request sent to the compiler"
+            }
+            "swap" => {
+                emb.title("Swap command");
+                emb.field("Example", format!("{}swap clang-head", prefix), false);
+                "Re-runs your last compilation with a different compiler or language"
             }
             "compilers" => {
                 emb.title("Compilers command");
@@ -71,13 +175,206 @@ pub async fn help(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
                     ),
                     false,
                 );
-                "Sends an assembly request, displaying the assembly output\n\n"
+                "Sends an assembly request, displaying the assembly output\n\n\
+                Code can also be attached as a source file (e.g. `main.cpp`), the language can be left out when the file name tells us\n\n\
+                Pass `--exec` before your code block to also run the program and display its output, \
+                or `--verbose` to see the exact request sent to the compiler"
             }
             "botinfo" => {
                 emb.title("Bot info command");
                 emb.field("Example", format!("{}botinfo", prefix), false);
                 "Outputs information about the bot"
             }
+            "setchannellang" => {
+                emb.title("Set channel language command");
+                emb.field("Example", format!("{}setchannellang python", prefix), false);
+                "Sets the default language used in this channel when none is specified (admin only)"
+            }
+            "allowchannel" => {
+                emb.title("Allow channel command");
+                emb.field("Example", format!("{}allowchannel #bot-spam", prefix), false);
+                "Restricts compile commands to the given channel, can be used multiple times (admin only)"
+            }
+            "denychannel" => {
+                emb.title("Deny channel command");
+                emb.field("Example", format!("{}denychannel #bot-spam", prefix), false);
+                "Removes a channel from the compile allow list, once empty compiling is allowed everywhere (admin only)"
+            }
+            "random" => {
+                emb.title("Random example command");
+                emb.field("Example", format!("{}random c++", prefix), false);
+                "Compiles a random example program for the given language"
+            }
+            "requirerole" => {
+                emb.title("Require role command");
+                emb.field("Example", format!("{}requirerole compile @Verified", prefix), false);
+                "Requires a role to use a category of commands, admins always bypass this (admin only)"
+            }
+            "guildstats" => {
+                emb.title("Guild stats command");
+                emb.field("Example", format!("{}guildstats", prefix), false);
+                "Displays how this server uses the bot: compilations, top commands and top languages\n\nOnly server administrators may use this command"
+            }
+            "autodelete" => {
+                emb.title("Auto delete command");
+                emb.field("Example", format!("{}autodelete on", prefix), false);
+                "Deletes the command message once the compilation results have been posted (admin only)\n\nRequires the Manage Messages permission, use `off` to disable"
+            }
+            "docs" => {
+                emb.title("Docs command");
+                emb.field("Example", format!("{}docs c++ std::vector", prefix), false);
+                "Links the documentation for a language, searching it if a query is given\n\nLeave out the query to get the language's main documentation page"
+            }
+            "exportstats" => {
+                emb.title("Export stats command");
+                emb.field("Example", format!("{}exportstats csv", prefix), false);
+                "Exports this server's usage statistics as a JSON (default) or CSV file (admin only)\n\nDaily compilation data is kept for the last 30 days, and statistics reset when the bot restarts"
+            }
+            "settheme" => {
+                emb.title("Set theme command");
+                emb.field("Example", format!("{}settheme #5dbcd2", prefix), false);
+                "Sets the accent color used by this server's embeds, errors always stay red (admin only)\n\nUse `reset` to go back to the default color"
+            }
+            "shard" => {
+                emb.title("Shard command");
+                emb.field("Example", format!("{}shard", prefix), false);
+                "Displays which shard serves this server, along with its latency and status"
+            }
+            "myflags" => {
+                emb.title("My flags command");
+                emb.field("Example", format!("{}myflags c++ -O2 -Wall", prefix), false);
+                "Saves your own default compiler flags for a language, these are used whenever you compile without passing any flags\n\nUse `clear` to remove them, or leave out the flags to see your current ones"
+            }
+            "supporters" => {
+                emb.title("Supporters command");
+                emb.field("Example", format!("{}supporters role @Patron", prefix), false);
+                "Gives your server's supporters a reduced compile cooldown (admin only)\n\nSupporters can be recognized by role (`role <@role | none>`) or by boosting the server (`boosters <on | off>`), and `cooldown <0-100>` sets the percentage of the normal cooldown they get, 0 removes it entirely"
+            }
+            "fmtdiff" => {
+                emb.title("Format diff");
+                emb.field("Example", format!("{}fmtdiff c++\n\\`\\`\\`\nint main(){{return 0;}}\n\\`\\`\\`", prefix), false);
+                "Shows what a formatter would change in your code without replacing it. The language is taken from the code block if none is given"
+            }
+            "version" => {
+                emb.title("Runtime version");
+                emb.field("Example", format!("{}version python", prefix), false);
+                "Shows the exact compiler or interpreter version used for a language, handy for citing what your code ran on. Uses this channel's default language if none is given"
+            }
+            "setbackend" => {
+                emb.title("Set backend command");
+                emb.field("Example", format!("{}setbackend godbolt", prefix), false);
+                "Sets the backend this server's compilations are sent to, requests it can't handle still go to wandbox. Use 'clear' to go back to the default. Administrators only"
+            }
+            "backends" => {
+                emb.title("Backends command");
+                emb.field("Example", format!("{}backends", prefix), false);
+                "Lists the backends compilations can be sent to, and which one this server uses"
+            }
+            "tips" => {
+                emb.title("Tips command");
+                emb.field("Example", format!("{}tips off", prefix), false);
+                "Toggles the one-time tip users get with their very first compilation. Administrators only"
+            }
+            "compare" => {
+                emb.title("Compare command");
+                emb.field("Example", format!("{}compare\n\\`\\`\\`cpp\n...\n\\`\\`\\`\n\\`\\`\\`rust\n...\n\\`\\`\\`", prefix), false);
+                "Runs the same program written in up to 3 languages and shows their output side by side. Every code block needs a language tag"
+            }
+            "snippet" => {
+                emb.title("Snippet command");
+                emb.field("Example", format!("{}snippet run hello", prefix), false);
+                "Runs one of this server's saved snippets. Use `snippet list` to see them, administrators can add snippets with `snippet save <name> <language> <code block>` and remove them with `snippet delete <name>`"
+            }
+            "logos" => {
+                emb.title("Logos command");
+                emb.field("Example", format!("{}logos off", prefix), false);
+                "Toggles the language logo shown as the thumbnail of compilation results. Administrators only"
+            }
+            "features" => {
+                emb.title("Features command");
+                emb.field("Example", format!("{}features", prefix), false);
+                "Shows which optional behaviors are active in this server and how they're configured. Administrators only"
+            }
+            "minimal" => {
+                emb.title("Minimal command");
+                emb.field("Example", format!("{}minimal on", prefix), false);
+                "Toggles minimal mode. Successful compilations only get a reaction on the command, the results are posted once the user reacts with 🔍. Failures are always posted in full. Administrators only"
+            }
+            "libs" => {
+                emb.title("Libs command");
+                emb.field("Example", format!("{}libs c++", prefix), false);
+                "Lists the libraries a language can be built with on each backend. Libraries are selected with --lib, e.g. ;run c++ --lib boost"
+            }
+            "announcements" => {
+                emb.title("Announcements command");
+                emb.field("Example", format!("{}announcements #bot-news", prefix), false);
+                "Picks the channel our announcements, like planned maintenance, are posted in. Use `off` to stop receiving them. Administrators only"
+            }
+            "preview" => {
+                emb.title("Preview command");
+                emb.field("Example", format!("{}preview", prefix), false);
+                "Shows sample compilation results the way they'd look in this server, using its theme and other settings. Administrators only"
+            }
+            "expiry" => {
+                emb.title("Expiry command");
+                emb.field("Example", format!("{}expiry 5m", prefix), false);
+                "Deletes compilation results a while after they're posted, to keep channels clean (admin only)\n\nDurations look like 30m, 2h or 1h30m, use `off` to disable. This is separate from `autodelete`, which removes the command message"
+            }
+            "check" => {
+                emb.title("Check command");
+                emb.field("Example", format!("{}check c++ \\`\\`\\`int main() {{}}\\`\\`\\`", prefix), false);
+                "Checks your code for syntax errors without building it, which is quicker than a full compile\n\nSupported for C, C++, Rust, Haskell, Python, Ruby, JavaScript and Lua, other languages are refused"
+            }
+            "autoflags" => {
+                emb.title("Automatic flags");
+                emb.field("Example", format!("{}autoflags off", prefix), false);
+                "Toggles adding flags like -pthread or -lm when the code includes a header needing them, flags given inline or through myflags always take precedence"
+            }
+            "accountage" => {
+                emb.title("Minimum account age");
+                emb.field("Example", format!("{}accountage 7", prefix), false);
+                "Keeps accounts younger than the given number of days from compiling code, which stops most throwaway accounts. Members of trusted servers & recent voters aren't affected\n\nUse 'off' to let accounts of any age compile again"
+            }
+            "setcompilelog" => {
+                emb.title("Compile log");
+                emb.field("Example", format!("{}setcompilelog #compile-log", prefix), false);
+                "Logs who compiled what language in this server, and whether it succeeded, to the given channel. The code itself is never logged\n\nUse 'off' to stop logging compilations"
+            }
+            "example" => {
+                emb.title("Example programs");
+                emb.field("Example", format!("{}example quicksort rust", prefix), false);
+                "Shows & runs one of our curated example programs, like fibonacci or quicksort. Leave out the language to get the first one the example is available in\n\nUse `example list` to see every example along with its languages"
+            }
+            "autocompile" => {
+                emb.title("Auto-compile");
+                emb.field("Example", format!("{}autocompile on", prefix), false);
+                "Runs every message in this channel that has a code block, no command needed. The language comes from this channel's default (see setchannellang) or the code block's language tag, code blocks in languages we don't know are left alone\n\nUse 'off' to go back to only running commands"
+            }
+            "perms" => {
+                emb.title("Permissions");
+                emb.field("Example", format!("{}perms", prefix), false);
+                "Checks which of the permissions I need I have in this channel. If I'm not responding properly, this is the first thing to check"
+            }
+            "ackreaction" => {
+                emb.title("Acknowledgement reaction");
+                emb.field("Example", format!("{}ackreaction on", prefix), false);
+                "Reacts with ⏳ to compile commands as soon as they're accepted, the reaction is removed once the results are in. Handy when the backends are slow, this needs the Add Reactions permission\n\nOff by default"
+            }
+            "optcompare" => {
+                emb.title("Optimization comparison");
+                emb.field("Example", format!("{}optcompare c++\n\\`\\`\\`cpp\nint square(int n) {{ return n * n; }}\n\\`\\`\\`", prefix), false);
+                "Compiles your code at -O0, -O1, -O2 & -O3 and compares how many lines of assembly each level produces. Pass your own levels (e.g. `-O2 -Os`) to compare those instead, up to 4 at once. Other flags apply to every level\n\nPass `--exec` to also run the program at each level and compare how long it took"
+            }
+            "race" => {
+                emb.title("Race");
+                emb.field("Example", format!("{}race\n\\`\\`\\`cpp\nint main() {{}}\n\\`\\`\\`\n\\`\\`\\`py\nprint()\n\\`\\`\\`", prefix), false);
+                "Runs the same problem solved in up to 4 languages, one code block each, and ranks them by how long they took. Programs that fail to compile or crash are listed as DNF"
+            }
+            "config" => {
+                emb.title("Config command");
+                emb.field("Example", format!("{}config language python", prefix), false);
+                "Shows this server's configuration, administrators can change it with:\n`config prefix <prefix|clear>` - a prefix accepted alongside the default one\n`config language <language|clear>` - the language used when none is given, channel languages take priority\n`config channels <#channel...|clear>` - the channels compile commands are restricted to"
+            }
             _ => {
                 emb.title("Command not found");
                 emb.color(COLOR_FAIL);
@@ -87,31 +384,25 @@ pub async fn help(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
         };
 
         emb.description(description);
+        let theme = discordhelpers::theme_color(ctx, msg.guild_id).await;
+        discordhelpers::apply_theme(&mut emb, theme);
 
-        let mut emb_msg = discordhelpers::embed_message(emb);
-        msg.channel_id
-            .send_message(&ctx.http, |_| &mut emb_msg)
-            .await?;
+        discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
 
         return Ok(());
     }
 
-    let prefix = env::var("BOT_PREFIX").expect("Prefix has not been set!");
-    msg.channel_id.send_message(&ctx.http, |m| {
-        m.embed(|e| {
-            e.thumbnail(ICON_HELP);
-            e.description(format!("For help with a specific command, type `{}help <command>`\n\nStruggling? Check out [our wiki](https://github.com/Headline/discord-compiler-bot/wiki)", prefix));
-            e.color(COLOR_OKAY);
-            e.title("Commands");
-            e.field("invite", "``` Grabs the bot's invite link ```", false);
-            e.field("compile", "``` Compiles a script ```", false);
-            e.field("compilers", "``` Displays the compilers for the specified language ```", false);
-            e.field("languages", "``` Displays all supported languages ```", false);
-            e.field("asm", "```\nOutputs the assembly for the input code```", false);
-            e.field("botinfo", "``` Displays information about the bot ```", false);
-            e
-        })
-    }).await?;
+    let prefix = discordhelpers::command_prefix(ctx, msg.guild_id).await;
+    let mut emb = CreateEmbed::default();
+    emb.thumbnail(ICON_HELP);
+    emb.description(format!("For help with a specific command, type `{}help <command>`\n\nStruggling? Check out [our wiki](https://github.com/Headline/discord-compiler-bot/wiki)", prefix));
+    emb.color(discordhelpers::theme_color(ctx, msg.guild_id).await);
+    emb.title("Commands");
+    for (category, commands) in CATEGORIES.iter() {
+        let lines: Vec<String> = commands.iter().map(|(name, about)| format!("`{}` - {}", name, about)).collect();
+        emb.field(category, lines.join("\n"), false);
+    }
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
 
     debug!("Command executed");
     Ok(())
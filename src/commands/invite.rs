@@ -10,10 +10,11 @@ use crate::utls::discordhelpers;
 pub async fn invite(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
     let invite = env::var("INVITE_LINK").expect("Expected invite link envvar");
 
-    let emb = discordhelpers::build_invite_embed(&invite);
+    let mut emb = discordhelpers::build_invite_embed(&invite);
+    let theme = discordhelpers::theme_color(ctx, msg.guild_id).await;
+    discordhelpers::apply_theme(&mut emb, theme);
 
-    let mut emb_msg = discordhelpers::embed_message(emb);
-    msg.channel_id.send_message(&ctx.http, |_| &mut emb_msg).await?;
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
 
     Ok(())
 }
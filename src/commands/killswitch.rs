@@ -0,0 +1,32 @@
+use serenity::framework::standard::{macros::command, Args, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::KillSwitchCache;
+
+#[command]
+#[owners_only]
+pub async fn killswitch(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let reason = args.rest().trim();
+
+    let data = ctx.data.read().await;
+    let mut kill_switch = data.get::<KillSwitchCache>().unwrap().write().await;
+    let reply = if reason.is_empty() {
+        match &*kill_switch {
+            Some(reason) => format!("Compiling is switched off: {}\nUse `;killswitch off` to switch it back on", reason),
+            None => String::from("Compiling is switched on, use `;killswitch <reason>` to switch it off"),
+        }
+    } else if reason.eq_ignore_ascii_case("off") {
+        *kill_switch = None;
+        info!("Kill switch lifted by {}", msg.author.tag());
+        String::from("Compiling is switched back on")
+    } else {
+        *kill_switch = Some(String::from(reason));
+        info!("Kill switch engaged by {}: {}", msg.author.tag(), reason);
+        format!("Compiling is switched off: {}", reason)
+    };
+    msg.channel_id.say(&ctx.http, reply).await?;
+
+    debug!("Command executed");
+    Ok(())
+}
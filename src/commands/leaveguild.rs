@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::utls::constants::{CONFIRM_EMOJI, CONFIRM_TIMEOUT};
+
+#[command]
+#[owners_only]
+pub async fn leaveguild(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = match args.single::<u64>() {
+        Ok(id) => GuildId(id),
+        Err(_e) => {
+            return Err(CommandError::from("Supply the id of the guild to leave, optionally followed by a reason"));
+        }
+    };
+    let reason = match args.rest().trim() {
+        "" => "no reason given",
+        r => r,
+    };
+
+    let name = match guild_id.to_partial_guild(&ctx.http).await {
+        Ok(g) => g.name,
+        Err(_e) => {
+            return Err(CommandError::from(format!(
+                "Unable to find guild `{}`, am I still in it?",
+                guild_id.0
+            )));
+        }
+    };
+
+    let mut prompt = msg
+        .channel_id
+        .say(&ctx.http, format!(
+            "React with {} within {} seconds to leave **{}** (`{}`)",
+            CONFIRM_EMOJI, CONFIRM_TIMEOUT, name, guild_id.0
+        ))
+        .await?;
+    prompt.react(&ctx.http, ReactionType::Unicode(String::from(CONFIRM_EMOJI))).await?;
+
+    let confirmation = prompt
+        .await_reaction(ctx)
+        .author_id(msg.author.id)
+        .filter(|r| matches!(&r.emoji, ReactionType::Unicode(s) if s == CONFIRM_EMOJI))
+        .timeout(Duration::from_secs(CONFIRM_TIMEOUT))
+        .await;
+    if confirmation.is_none() {
+        prompt.edit(&ctx.http, |m| m.content("Timed out, staying put")).await?;
+        return Ok(());
+    }
+
+    // discord follows up with a guild_delete, which takes care of stats & bot lists
+    guild_id.leave(&ctx.http).await?;
+    info!("{} made us leave {} ({}): {}", msg.author.tag(), name, guild_id.0, reason);
+
+    prompt.edit(&ctx.http, |m| m.content(format!("Left **{}** (`{}`)", name, guild_id.0))).await?;
+    debug!("Command executed");
+    Ok(())
+}
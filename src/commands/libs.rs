@@ -0,0 +1,26 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::utls::constants::BACKENDS;
+use crate::utls::{discordhelpers, libraries, parser};
+
+#[command]
+pub async fn libs(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let language = match args.current() {
+        Some(l) => String::from(parser::normalize_language(&l.to_lowercase())),
+        None => return Err(CommandError::from("Usage: `libs <language>`")),
+    };
+    let available: Vec<(&str, Vec<&str>)> = BACKENDS
+        .iter()
+        .map(|(backend, _)| (*backend, libraries::available(backend, &language)))
+        .collect();
+
+    let mut emb = discordhelpers::build_libraries_embed(&msg.author, &language, &available);
+    let theme = discordhelpers::theme_color(ctx, msg.guild_id).await;
+    discordhelpers::apply_theme(&mut emb, theme);
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+
+    debug!("Command executed");
+    Ok(())
+}
@@ -8,3 +8,55 @@ pub mod ping;
 pub mod block;
 pub mod unblock;
 pub mod invite;
+pub mod swap;
+pub mod setchannellang;
+pub mod recenterrors;
+pub mod reloadconfig;
+pub mod allowchannel;
+pub mod random;
+pub mod requirerole;
+pub mod guildstats;
+pub mod autodelete;
+pub mod run;
+pub mod docs;
+pub mod exportstats;
+pub mod settheme;
+pub mod shard;
+pub mod blockchannel;
+pub mod top;
+pub mod myflags;
+pub mod supporters;
+pub mod fmtdiff;
+pub mod version;
+pub mod setbackend;
+pub mod backends;
+pub mod leaveguild;
+pub mod tips;
+pub mod compare;
+pub mod snippet;
+pub mod recentguilds;
+pub mod logos;
+pub mod features;
+pub mod minimal;
+pub mod libs;
+pub mod schedule;
+pub mod announcements;
+pub mod preview;
+pub mod setpresence;
+pub mod session;
+pub mod expiry;
+pub mod check;
+pub mod usagechart;
+pub mod autoflags;
+pub mod killswitch;
+pub mod accountage;
+pub mod whichshard;
+pub mod setcompilelog;
+pub mod example;
+pub mod autocompile;
+pub mod perms;
+pub mod ackreaction;
+pub mod optcompare;
+pub mod restartshard;
+pub mod race;
+pub mod config;
@@ -0,0 +1,63 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::{SettingsCache, WandboxCache};
+use crate::utls::parser;
+
+#[command]
+pub async fn myflags(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let language = match args.single::<String>() {
+        Ok(s) => String::from(parser::normalize_language(&s.trim().to_lowercase())),
+        Err(_e) => {
+            return Err(CommandError::from(
+                "No language specified!\nUsage: `myflags <language> [flags... | clear]`",
+            ));
+        }
+    };
+    let data = ctx.data.read().await;
+    {
+        let wbox = data.get::<WandboxCache>().unwrap().read().await;
+        if wbox.get_compilers(&language).is_none() {
+            return Err(CommandError::from(format!(
+                "Could not find language '{}'",
+                &language
+            )));
+        }
+    }
+
+    let flags: Vec<String> = args.rest().split_whitespace().map(String::from).collect();
+    let user = msg.author.id.0;
+
+    // no flags, just show what we have
+    if flags.is_empty() {
+        let settings = data.get::<SettingsCache>().unwrap().read().await;
+        let reply = match settings.user_flags(user, &language) {
+            Some(f) => format!("Your flags for `{}` are `{}`", &language, f.join(" ")),
+            None => format!("You have no flags set for `{}`", &language),
+        };
+        msg.channel_id.say(&ctx.http, reply).await?;
+        return Ok(());
+    }
+
+    let mut settings = data.get::<SettingsCache>().unwrap().write().await;
+    if flags.len() == 1 && flags[0] == "clear" {
+        let reply = if settings.clear_user_flags(user, &language) {
+            settings.write();
+            format!("Cleared your flags for `{}`", &language)
+        } else {
+            format!("You have no flags set for `{}`", &language)
+        };
+        msg.channel_id.say(&ctx.http, reply).await?;
+        return Ok(());
+    }
+
+    settings.user_mut(user).flags.insert(language.clone(), flags.clone());
+    settings.write();
+
+    msg.channel_id
+        .say(&ctx.http, format!("Set your flags for `{}` to `{}`", &language, flags.join(" ")))
+        .await?;
+    debug!("Command executed");
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::futures::future::join_all;
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::{ConfigCache, SettingsCache};
+use crate::commands::asm;
+use crate::utls::constants::{DEFAULT_OPT_LEVELS, MAX_OPT_LEVELS};
+use crate::utls::discordhelpers;
+use crate::utls::parser::{self, ParserResult};
+
+#[command]
+#[bucket = "optcompare"]
+pub async fn optcompare(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let default_target = {
+        let data_read = ctx.data.read().await;
+        let settings = data_read.get::<SettingsCache>().unwrap().read().await;
+        settings.default_language(msg.guild_id.map(|g| g.0).unwrap_or_default(), msg.channel_id.0)
+    };
+
+    let mut result: ParserResult = match parser::get_components(&msg.content, &msg.author, default_target).await {
+        Ok(r) => r,
+        Err(e) => {
+            return Err(CommandError::from(format!("{}", e)));
+        }
+    };
+
+    // --exec times each level instead of only assembling it, just like ;asm
+    let execute = result.options.iter().any(|o| o == "--exec");
+    result.options.retain(|o| o != "--exec");
+
+    // any -O flags the user passed are the levels to compare, the other flags apply to every level
+    let mut levels: Vec<String> = Vec::new();
+    for option in result.options.iter().filter(|o| o.starts_with("-O")) {
+        if !levels.contains(option) {
+            levels.push(option.clone());
+        }
+    }
+    result.options.retain(|o| !o.starts_with("-O"));
+    if levels.is_empty() {
+        levels = DEFAULT_OPT_LEVELS.iter().map(|l| String::from(*l)).collect();
+    }
+    if levels.len() > MAX_OPT_LEVELS {
+        return Err(CommandError::from(format!(
+            "I can only compare up to {} optimization levels at once",
+            MAX_OPT_LEVELS
+        )));
+    }
+
+    let (loading_id, loading_name) = {
+        let data_read = ctx.data.read().await;
+        let botinfo = data_read.get::<ConfigCache>().unwrap().read().await;
+        let id = botinfo.get("LOADING_EMOJI_ID").unwrap().parse::<u64>().unwrap();
+        (id, botinfo.get("LOADING_EMOJI_NAME").unwrap().clone())
+    };
+    let reaction = match msg
+        .react(&ctx.http, discordhelpers::build_reaction(loading_id, &loading_name))
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            return Err(CommandError::from(format!(" Unable to react to message, am I missing permissions to react or use external emoji?\n{}", e)));
+        }
+    };
+
+    // every level is compiled at once, one failing doesn't hold up the others
+    let requests: Vec<ParserResult> = levels
+        .iter()
+        .map(|level| {
+            let mut request = result.clone();
+            request.options.push(level.clone());
+            request
+        })
+        .collect();
+    let outcomes = join_all(requests.iter().map(|request| asm::dispatch_request(ctx, request, execute))).await;
+    let results: Vec<(String, Result<(usize, Option<u64>), String>)> = levels
+        .into_iter()
+        .zip(outcomes)
+        .map(|(level, outcome)| {
+            let outcome = match outcome {
+                Ok((response, _)) if response.asm_size.is_none() => {
+                    let errors: Vec<&str> = response.stderr.iter().map(|e| e.text.as_str()).collect();
+                    Err(format!("```\n{}\n```", errors.join("\n")))
+                }
+                Ok((response, execution)) => {
+                    let lines = response
+                        .asm
+                        .as_ref()
+                        .map(|asm| asm.iter().filter(|a| a.text.as_ref().map(|t| !t.trim().is_empty()).unwrap_or(false)).count())
+                        .unwrap_or(0);
+                    Ok((lines, execution.and_then(|e| e.run_time_ms)))
+                }
+                Err(e) => Err(e.to_string()),
+            };
+            (level, outcome)
+        })
+        .collect();
+
+    if msg.delete_reaction_emoji(&ctx.http, reaction.emoji.clone()).await.is_err() {
+        return Err(CommandError::from(
+            "Unable to remove reactions!\nAm I missing permission to manage messages?",
+        ));
+    }
+
+    let mut emb = discordhelpers::build_optcompare_embed(&msg.author, &result.target, &results, execute);
+    let theme = discordhelpers::theme_color(ctx, msg.guild_id).await;
+    discordhelpers::apply_theme(&mut emb, theme);
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+
+    debug!("Command executed");
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::ConfigCache;
+use crate::utls::discordhelpers;
+
+#[command]
+pub async fn perms(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let guild_id = match msg.guild_id {
+        Some(id) => id,
+        None => {
+            msg.channel_id.say(&ctx.http, "Permissions don't apply in DMs, I can do everything I need to here").await?;
+            debug!("Command executed");
+            return Ok(());
+        }
+    };
+
+    let bot_id = {
+        let data = ctx.data.read().await;
+        let info = data.get::<ConfigCache>().unwrap().read().await;
+        info.get("BOT_ID").unwrap().parse::<u64>().unwrap()
+    };
+
+    let permissions = match discordhelpers::channel_permissions(ctx, guild_id, msg.channel_id, UserId(bot_id)).await {
+        Some(p) => p,
+        None => {
+            return Err(CommandError::from("Unable to look up my permissions in this channel, please try again later"));
+        }
+    };
+
+    let checks = [
+        ("Send Messages", permissions.send_messages()),
+        ("Embed Links", permissions.embed_links()),
+        ("Add Reactions", permissions.add_reactions()),
+        ("Read Message History", permissions.read_message_history()),
+        ("Manage Messages", permissions.manage_messages()),
+    ];
+
+    // plain text, this has to get through even when embeds can't
+    let mut reply = String::from("My permissions in this channel:\n");
+    for (name, granted) in checks.iter() {
+        let mark = if *granted { "✅" } else { "❌" };
+        reply.push_str(&format!("{} {}\n", mark, name));
+    }
+    if checks.iter().any(|(_, granted)| !granted) {
+        reply.push_str("\nAsk a server admin to grant the missing permissions to my role or in this channel's settings");
+    }
+
+    msg.channel_id.say(&ctx.http, reply).await?;
+    debug!("Command executed");
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::utls::compileresult::CompileResult;
+use crate::utls::discordhelpers;
+use crate::utls::parser::FlagSource;
+
+#[command]
+#[only_in(guilds)]
+pub async fn preview(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    if !discordhelpers::is_admin(ctx, msg).await {
+        return Err(CommandError::from(
+            "Only server administrators may preview this server's embeds",
+        ));
+    }
+
+    // nothing gets compiled, this only shows what this server's settings do to our results
+    let result = CompileResult {
+        backend: "wandbox",
+        compiler: String::from("gcc-head"),
+        stdout: String::from("Hello, world!"),
+        exit_code: Some(0),
+        compile_time_ms: 250,
+        ..Default::default()
+    };
+
+    let mut emb = discordhelpers::build_compilation_embed(&msg.author, &result, true, FlagSource::None);
+    if discordhelpers::logos_enabled(ctx, msg.guild_id).await {
+        discordhelpers::add_language_logo(&mut emb, "c++");
+    }
+    let theme = discordhelpers::theme_color(ctx, msg.guild_id).await;
+    discordhelpers::apply_theme(&mut emb, theme);
+    if discordhelpers::minimal_mode(ctx, msg.guild_id).await {
+        emb.field("Preview", "Sample results, minimal mode is on so these are only posted when asked for", false);
+    } else {
+        emb.field("Preview", "Sample results, nothing was compiled", false);
+    }
+
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+
+    debug!("Command executed");
+    Ok(())
+}
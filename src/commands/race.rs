@@ -0,0 +1,77 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::futures::future::join_all;
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::ConfigCache;
+use crate::commands::compare;
+use crate::utls::compileresult::{CompileResult, Outcome};
+use crate::utls::constants::MAX_RACE_LANGUAGES;
+use crate::utls::discordhelpers;
+
+#[command]
+#[bucket = "race"]
+pub async fn race(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let sides = compare::sides(&msg.content, "race", MAX_RACE_LANGUAGES)?;
+
+    let (loading_id, loading_name) = {
+        let data_read = ctx.data.read().await;
+        let botinfo = data_read.get::<ConfigCache>().unwrap().read().await;
+        let id = botinfo.get("LOADING_EMOJI_ID").unwrap().parse::<u64>().unwrap();
+        (id, botinfo.get("LOADING_EMOJI_NAME").unwrap().clone())
+    };
+    let reaction = match msg
+        .react(&ctx.http, discordhelpers::build_reaction(loading_id, &loading_name))
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            return Err(CommandError::from(format!(" Unable to react to message, am I missing permissions to react or use external emoji?\n{}", e)));
+        }
+    };
+
+    // everyone starts at once, same as ;compare
+    let runs = sides.iter().map(|(language, code)| compare::run_side(ctx, msg.author.id.0, language, code));
+    let outcomes = join_all(runs).await;
+
+    // only programs that ran to completion are ranked, the rest did not finish
+    let mut finished: Vec<(String, CompileResult)> = Vec::new();
+    let mut dnf: Vec<(String, String)> = Vec::new();
+    for ((language, _), outcome) in sides.into_iter().zip(outcomes) {
+        match outcome {
+            Ok(result) => match result.outcome() {
+                Outcome::Success => finished.push((language, result)),
+                Outcome::CompileError => dnf.push((language, String::from("Failed to compile"))),
+                Outcome::RuntimeError => dnf.push((language, result.status())),
+                Outcome::TimedOut => dnf.push((language, String::from("Timed out"))),
+            },
+            Err(e) => dnf.push((language, e)),
+        }
+    }
+
+    if msg.delete_reaction_emoji(&ctx.http, reaction.emoji.clone()).await.is_err() {
+        return Err(CommandError::from(
+            "Unable to remove reactions!\nAm I missing permission to manage messages?",
+        ));
+    }
+
+    // only run times are comparable, a backend that doesn't time the run leaves us with the
+    // whole request which includes compiling & queueing. Those aren't ranked among the rest
+    let mut ranking: Vec<(String, u64)> = Vec::new();
+    let mut untimed: Vec<(String, u64)> = Vec::new();
+    for (language, result) in finished {
+        match result.run_time_ms {
+            Some(ms) => ranking.push((language, ms)),
+            None => untimed.push((language, result.compile_time_ms)),
+        }
+    }
+    ranking.sort_by_key(|(_, ms)| *ms);
+
+    let mut emb = discordhelpers::build_race_embed(&msg.author, &ranking, &untimed, &dnf);
+    let theme = discordhelpers::theme_color(ctx, msg.guild_id).await;
+    discordhelpers::apply_theme(&mut emb, theme);
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+
+    debug!("Command executed");
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::commands::compile;
+use crate::utls::examples;
+use crate::utls::parser::{self, ParserResult};
+
+#[command]
+#[bucket = "nospam"]
+pub async fn random(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let language = match args.parse::<String>() {
+        Ok(s) => String::from(parser::normalize_language(&s.trim().to_lowercase())),
+        Err(_e) => {
+            return Err(CommandError::from(format!(
+                "No language specified!\nI have examples for: {}",
+                examples::languages().join(", ")
+            )));
+        }
+    };
+    let code = match examples::random_example(&language) {
+        Some(c) => c,
+        None => {
+            return Err(CommandError::from(format!(
+                "I don't have any examples for '{}'\nI have examples for: {}",
+                &language,
+                examples::languages().join(", ")
+            )));
+        }
+    };
+
+    // show off the source before we show its output
+    msg.channel_id
+        .say(&ctx.http, format!("```{}\n{}\n```", &language, code))
+        .await?;
+
+    let parse_result = ParserResult {
+        target: language,
+        code: String::from(code),
+        ..Default::default()
+    };
+
+    compile::handle_request(ctx, msg, parse_result, true).await
+}
@@ -0,0 +1,21 @@
+use serenity::framework::standard::{macros::command, Args, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::ErrorLogCache;
+use crate::utls::discordhelpers;
+
+#[command]
+#[owners_only]
+pub async fn recenterrors(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let emb = {
+        let data = ctx.data.read().await;
+        let errors = data.get::<ErrorLogCache>().unwrap().lock().await;
+        discordhelpers::build_recent_errors_embed(&errors)
+    };
+
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+
+    debug!("Command executed");
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use serenity::framework::standard::{macros::command, Args, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::GuildLogCache;
+use crate::utls::constants::{DEFAULT_RECENT_GUILDS, GUILD_LOG_SIZE};
+use crate::utls::discordhelpers;
+
+#[command]
+#[owners_only]
+pub async fn recentguilds(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let amount = args
+        .single::<usize>()
+        .unwrap_or(DEFAULT_RECENT_GUILDS)
+        .max(1)
+        .min(GUILD_LOG_SIZE);
+
+    let emb = {
+        let data = ctx.data.read().await;
+        let log = data.get::<GuildLogCache>().unwrap().lock().await;
+        discordhelpers::build_recent_guilds_embed(&log, amount)
+    };
+
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+
+    debug!("Command executed");
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::env;
+
+use serenity::builder::CreateEmbed;
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::{ConfigCache, RuntimeVersionCache};
+use crate::utls::constants::*;
+use crate::utls::discordhelpers;
+
+// These are consumed once at startup, changing them live would leave
+// us in an inconsistent state so we only let the user know
+static RESTART_REQUIRED: [&str; 11] = [
+    "BOT_TOKEN",
+    "BOT_PREFIX",
+    "BOT_ID",
+    "SHARD_COUNT",
+    "DBL_TOKEN",
+    "DBL_WEBHOOK_PORT",
+    "DBL_WEBHOOK_PASSWORD",
+    "VOTE_CHANNEL",
+    "STATS_API_LINK",
+    "STATS_API_KEY",
+    "METRICS_PORT",
+];
+
+#[command]
+#[owners_only]
+pub async fn reloadconfig(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    // parse the entire file up front, if anything is malformed we bail
+    // before touching the live configuration
+    let iter = match dotenv::dotenv_iter() {
+        Ok(i) => i,
+        Err(e) => {
+            return Err(CommandError::from(format!("Unable to read .env file\n{}", e)));
+        }
+    };
+
+    let mut new_config = HashMap::new();
+    for item in iter {
+        match item {
+            Ok((key, value)) => {
+                new_config.insert(key, value);
+            }
+            Err(e) => {
+                return Err(CommandError::from(format!(
+                    "Malformed .env file, no changes have been applied\n{}",
+                    e
+                )));
+            }
+        }
+    }
+
+    // emoji ids get unwrapped everywhere, make sure they're sane
+    for (key, value) in &new_config {
+        if key.ends_with("_EMOJI_ID") && value.parse::<u64>().is_err() {
+            return Err(CommandError::from(format!(
+                "Invalid value for {}, no changes have been applied",
+                key
+            )));
+        }
+    }
+
+    let mut applied = Vec::new();
+    let mut needs_restart = Vec::new();
+    {
+        let data = ctx.data.read().await;
+        let mut info = data.get::<ConfigCache>().unwrap().write().await;
+        for (key, value) in &new_config {
+            if env::var(key).unwrap_or_default() == *value {
+                continue;
+            }
+
+            if RESTART_REQUIRED.contains(&key.as_str()) {
+                needs_restart.push(key.clone());
+                continue;
+            }
+
+            env::set_var(key, value);
+            if let Some(entry) = info.get_mut(key.as_str()) {
+                *entry = value.clone();
+            }
+            applied.push(key.clone());
+        }
+
+        // ;version answers from what it resolved before, which the new settings may well change
+        data.get::<RuntimeVersionCache>().unwrap().lock().await.clear();
+    }
+
+    applied.sort();
+    needs_restart.sort();
+
+    let mut emb = CreateEmbed::default();
+    emb.title("Configuration reloaded");
+    emb.color(COLOR_OKAY);
+    if applied.is_empty() && needs_restart.is_empty() {
+        emb.description("No changes found");
+    }
+    if !applied.is_empty() {
+        emb.field("Applied", format!("```\n{}\n```", applied.join("\n")), false);
+    }
+    if !needs_restart.is_empty() {
+        emb.field("Requires restart", format!("```\n{}\n```", needs_restart.join("\n")), false);
+    }
+
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+
+    info!("Configuration reloaded by {}, {} key(s) applied", msg.author.tag(), applied.len());
+    debug!("Command executed");
+    Ok(())
+}
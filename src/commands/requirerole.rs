@@ -0,0 +1,62 @@
+use serenity::builder::CreateEmbed;
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::SettingsCache;
+use crate::utls::constants::{COLOR_OKAY, ROLE_CATEGORIES};
+use crate::utls::discordhelpers;
+
+#[command]
+#[only_in(guilds)]
+pub async fn requirerole(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    if !discordhelpers::is_admin(ctx, msg).await {
+        return Err(CommandError::from(
+            "Only server administrators may change role requirements",
+        ));
+    }
+
+    let guild_id = msg.guild_id.unwrap().0;
+    let usage = format!(
+        "Usage: `requirerole <category> <@role | clear>`\nCategories: {}",
+        ROLE_CATEGORIES.join(", ")
+    );
+
+    let mut category = match args.single::<String>() {
+        Ok(s) => s.to_lowercase(),
+        Err(_e) => return Err(CommandError::from(usage)),
+    };
+    if category == "run" {
+        category = String::from("compile");
+    }
+    if !ROLE_CATEGORIES.contains(&category.as_str()) {
+        return Err(CommandError::from(format!("Unknown category '{}'\n{}", category, usage)));
+    }
+
+    let data = ctx.data.read().await;
+    if args.current() == Some("clear") {
+        let mut settings = data.get::<SettingsCache>().unwrap().write().await;
+        settings.guild_mut(guild_id).required_roles.remove(&category);
+        settings.write();
+
+        msg.channel_id.say(&ctx.http, format!("Removed the role requirement for `{}` commands", category)).await?;
+        return Ok(());
+    }
+
+    let role = match args.single::<RoleId>() {
+        Ok(r) => r,
+        Err(_e) => return Err(CommandError::from(usage)),
+    };
+
+    let mut settings = data.get::<SettingsCache>().unwrap().write().await;
+    settings.guild_mut(guild_id).required_roles.insert(category.clone(), role.0);
+    settings.write();
+
+    // embedded so we don't ping the role
+    let mut emb = CreateEmbed::default();
+    emb.color(COLOR_OKAY);
+    emb.description(format!("`{}` commands now require the <@&{}> role", category, role.0));
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+    debug!("Command executed");
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use serenity::client::bridge::gateway::ShardId;
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::{ConfigCache, ShardManagerCache, StatsManagerCache};
+
+#[command]
+#[owners_only]
+pub async fn restartshard(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let usage = "Usage: `;restartshard <shard id>`";
+    let shard_id = match args.current() {
+        Some(id) => id.parse::<u64>().map_err(|_| CommandError::from(usage))?,
+        None => return Err(CommandError::from(usage)),
+    };
+
+    let data = ctx.data.read().await;
+    let configured = data.get::<ConfigCache>().unwrap().read().await
+        .get("SHARD_COUNT")
+        .and_then(|c| c.parse::<u64>().ok())
+        .unwrap_or_default();
+    let ready = data.get::<StatsManagerCache>().unwrap().lock().await.shard_count();
+
+    let mut shard_manager = data.get::<ShardManagerCache>().unwrap().lock().await;
+    {
+        let runners = shard_manager.runners.lock().await;
+        let expected = if configured > 0 { configured } else { runners.len() as u64 };
+
+        // a restart mid boot would leave all_shards_ready waiting on a shard that's starting over
+        if ready < expected {
+            return Err(CommandError::from(format!(
+                "Shards are still starting up ({}/{} ready), try again once they're all ready",
+                ready, expected
+            )));
+        }
+
+        if !runners.contains_key(&ShardId(shard_id)) {
+            let mut hosted: Vec<u64> = runners.keys().map(|id| id.0).collect();
+            hosted.sort_unstable();
+            let hosted: Vec<String> = hosted.iter().map(|id| id.to_string()).collect();
+            return Err(CommandError::from(format!(
+                "Shard {} isn't managed by this instance, the shards running here are: {}",
+                shard_id,
+                hosted.join(", ")
+            )));
+        }
+    }
+
+    // the restarted shard reconnects with a fresh session, see the ready handler
+    warn!("Restarting shard {} as requested by {}", shard_id, msg.author.tag());
+    shard_manager.restart(ShardId(shard_id)).await;
+
+    msg.channel_id.say(&ctx.http, format!("Shard {} is restarting, it'll be back once it has reconnected", shard_id)).await?;
+    debug!("Command executed");
+    Ok(())
+}
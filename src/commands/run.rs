@@ -0,0 +1,11 @@
+use serenity::framework::standard::{macros::command, Args, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::commands::compile;
+
+#[command]
+#[bucket = "nospam"]
+pub async fn run(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    compile::parse_and_handle(ctx, msg, true).await
+}
@@ -0,0 +1,96 @@
+use chrono::Utc;
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::ScheduleCache;
+use crate::utls::discordhelpers;
+use crate::utls::schedule::{self, Maintenance};
+
+#[command]
+#[owners_only]
+#[sub_commands(maintenance, list, cancel)]
+pub async fn schedule(_ctx: &Context, _msg: &Message, _args: Args) -> CommandResult {
+    Err(CommandError::from(
+        "Usage: `schedule maintenance \"<time>\" <duration> \"<reason>\"`, `schedule list` or `schedule cancel <number>`",
+    ))
+}
+
+#[command]
+async fn maintenance(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let usage = "Usage: `schedule maintenance \"2024-06-01T02:00Z\" 30m \"DB upgrade\"`";
+    let start = match args.single_quoted::<String>().ok().and_then(|t| schedule::parse_time(&t)) {
+        Some(t) => t,
+        None => return Err(CommandError::from(format!("Invalid start time, times are in UTC\n{}", usage))),
+    };
+    if start <= Utc::now() {
+        return Err(CommandError::from("Maintenance can only be scheduled in the future"));
+    }
+    let minutes = match args.single::<String>().ok().and_then(|d| schedule::parse_duration(&d)) {
+        Some(m) => m,
+        None => return Err(CommandError::from(format!("Invalid duration, try something like 30m or 1h30m\n{}", usage))),
+    };
+    let reason = match args.single_quoted::<String>() {
+        Ok(r) if !r.trim().is_empty() => r,
+        _ => String::from("Scheduled maintenance"),
+    };
+
+    let maintenance = Maintenance {
+        start: start.timestamp(),
+        minutes,
+        reason,
+    };
+    {
+        let data = ctx.data.read().await;
+        let mut schedule = data.get::<ScheduleCache>().unwrap().write().await;
+        schedule.add(maintenance.clone());
+    }
+
+    info!("{} scheduled maintenance at {} for {} minutes", msg.author.tag(), start, minutes);
+    msg.channel_id.say(&ctx.http, format!(
+        "Maintenance scheduled from {} until {} UTC",
+        maintenance.starts().format("%Y-%m-%d %H:%M"),
+        maintenance.ends().format("%Y-%m-%d %H:%M")
+    )).await?;
+    debug!("Command executed");
+    Ok(())
+}
+
+#[command]
+async fn list(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let emb = {
+        let data = ctx.data.read().await;
+        let schedule = data.get::<ScheduleCache>().unwrap().read().await;
+        discordhelpers::build_schedule_embed(&schedule)
+    };
+
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+    debug!("Command executed");
+    Ok(())
+}
+
+#[command]
+async fn cancel(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let number = match args.single::<usize>() {
+        Ok(n) if n > 0 => n,
+        _ => return Err(CommandError::from("Usage: `schedule cancel <number>`, see `schedule list` for the numbers")),
+    };
+
+    let cancelled = {
+        let data = ctx.data.read().await;
+        let mut schedule = data.get::<ScheduleCache>().unwrap().write().await;
+        schedule.cancel(number - 1)
+    };
+
+    match cancelled {
+        Some(m) => {
+            msg.channel_id.say(&ctx.http, format!(
+                "Cancelled the maintenance planned for {} UTC",
+                m.starts().format("%Y-%m-%d %H:%M")
+            )).await?;
+        }
+        None => return Err(CommandError::from(format!("There's no scheduled maintenance number {}", number))),
+    }
+    debug!("Command executed");
+    Ok(())
+}
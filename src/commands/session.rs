@@ -0,0 +1,65 @@
+use std::collections::BTreeSet;
+
+use serenity::client::bridge::gateway::ShardId;
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::{SessionCache, ShardManagerCache};
+use crate::utls::constants::SESSIONS_PER_PAGE;
+use crate::utls::discordhelpers;
+
+#[command]
+#[owners_only]
+pub async fn session(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let page = args.single::<usize>().unwrap_or(1).max(1);
+
+    let lines = {
+        let data = ctx.data.read().await;
+        let sessions = data.get::<SessionCache>().unwrap().lock().await;
+        let shard_manager = data.get::<ShardManagerCache>().unwrap().lock().await;
+        let runners = shard_manager.runners.lock().await;
+
+        // shards we've heard from may have since lost their runner and vice versa
+        let mut shard_ids: BTreeSet<u64> = runners.keys().map(|id| id.0).collect();
+        shard_ids.extend(sessions.shard_ids());
+
+        let mut lines = Vec::new();
+        for shard_id in shard_ids {
+            let (latency, stage) = match runners.get(&ShardId(shard_id)) {
+                Some(runner) => (
+                    runner.latency.map(|l| format!("{}ms", l.as_millis())).unwrap_or_else(|| String::from("-")),
+                    format!("{:?}", runner.stage),
+                ),
+                None => (String::from("-"), String::from("NoRunner")),
+            };
+
+            let line = match sessions.get(shard_id) {
+                Some(s) => format!(
+                    "#{:<3} {:<12} {:>6} {} identifies:{} resumes:{} since {}",
+                    shard_id, stage, latency, s.session_id, s.identifies, s.resumes,
+                    s.last_connect.format("%m-%d %H:%M")
+                ),
+                None => format!("#{:<3} {:<12} {:>6} not connected yet", shard_id, stage, latency),
+            };
+            lines.push(line);
+        }
+        lines
+    };
+
+    if lines.is_empty() {
+        return Err(CommandError::from("No shards are currently running"));
+    }
+
+    let pages = (lines.len() + SESSIONS_PER_PAGE - 1) / SESSIONS_PER_PAGE;
+    if page > pages {
+        return Err(CommandError::from(format!("There are only {} page(s) of shards", pages)));
+    }
+
+    let shown = &lines[(page - 1) * SESSIONS_PER_PAGE..(page * SESSIONS_PER_PAGE).min(lines.len())];
+    let emb = discordhelpers::build_sessions_embed(shown, page, pages, lines.len());
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+
+    debug!("Command executed");
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::SettingsCache;
+use crate::utls::constants::BACKENDS;
+use crate::utls::discordhelpers;
+
+#[command]
+#[only_in(guilds)]
+pub async fn setbackend(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    if !discordhelpers::is_admin(ctx, msg).await {
+        return Err(CommandError::from(
+            "Only server administrators may change this server's backend",
+        ));
+    }
+
+    let names: Vec<&str> = BACKENDS.iter().map(|(name, _)| *name).collect();
+    let backend = match args.parse::<String>() {
+        Ok(s) => s.trim().to_lowercase(),
+        Err(_e) => {
+            return Err(CommandError::from(format!(
+                "No backend specified!\nSupply one of: {}, or 'clear' to use the default",
+                names.join(", ")
+            )));
+        }
+    };
+
+    if backend != "clear" && !names.contains(&backend.as_str()) {
+        return Err(CommandError::from(format!(
+            "Unknown backend '{}'\nSupply one of: {}, or 'clear' to use the default",
+            backend,
+            names.join(", ")
+        )));
+    }
+
+    let guild_id = msg.guild_id.unwrap();
+    {
+        let data = ctx.data.read().await;
+        let mut settings = data.get::<SettingsCache>().unwrap().write().await;
+        settings.guild_mut(guild_id.0).backend = if backend == "clear" { None } else { Some(backend.clone()) };
+        settings.write();
+    }
+
+    if backend == "clear" {
+        msg.channel_id.say(&ctx.http, "This server now uses the default backend").await?;
+    } else {
+        msg.channel_id.say(&ctx.http, format!(
+            "Compilations in this server now go to `{}` whenever it supports the language",
+            backend
+        )).await?;
+    }
+
+    debug!("Command executed");
+    Ok(())
+}
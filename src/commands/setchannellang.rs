@@ -0,0 +1,53 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::{SettingsCache, WandboxCache};
+use crate::utls::discordhelpers;
+
+#[command]
+#[only_in(guilds)]
+pub async fn setchannellang(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    if !discordhelpers::is_admin(ctx, msg).await {
+        return Err(CommandError::from(
+            "Only server administrators may change a channel's default language",
+        ));
+    }
+
+    let language = match args.parse::<String>() {
+        Ok(s) => s.trim().to_lowercase(),
+        Err(_e) => {
+            return Err(CommandError::from(
+                "No language specified!\nSupply a language, or 'clear' to remove this channel's default",
+            ));
+        }
+    };
+
+    let data = ctx.data.read().await;
+    if language == "clear" {
+        let mut settings = data.get::<SettingsCache>().unwrap().write().await;
+        settings.channel_mut(msg.channel_id.0).language = None;
+        settings.write();
+
+        msg.channel_id.say(&ctx.http, "Cleared this channel's default language").await?;
+        return Ok(());
+    }
+
+    {
+        let wbox = data.get::<WandboxCache>().unwrap().read().await;
+        if wbox.get_compilers(&language).is_none() {
+            return Err(CommandError::from(format!(
+                "Could not find language '{}'",
+                &language
+            )));
+        }
+    }
+
+    let mut settings = data.get::<SettingsCache>().unwrap().write().await;
+    settings.channel_mut(msg.channel_id.0).language = Some(language.clone());
+    settings.write();
+
+    msg.channel_id.say(&ctx.http, format!("Set this channel's default language to `{}`", &language)).await?;
+    debug!("Command executed");
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::{SettingsCache, ShardManagerCache, StatsManagerCache};
+use crate::utls::discordhelpers;
+
+#[command]
+#[owners_only]
+pub async fn setpresence(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let text = args.rest().trim();
+    if text.is_empty() {
+        return Err(CommandError::from(
+            "Usage: `;setpresence <text>` or `;setpresence clear` to go back to our server count",
+        ));
+    }
+
+    let custom = if text.eq_ignore_ascii_case("clear") {
+        None
+    } else {
+        Some(String::from(text))
+    };
+
+    let data = ctx.data.read().await;
+    {
+        let mut settings = data.get::<SettingsCache>().unwrap().write().await;
+        settings.set_presence(custom.clone());
+        settings.write();
+    }
+
+    let server_count = data.get::<StatsManagerCache>().unwrap().lock().await.server_count();
+    let shard_manager = data.get::<ShardManagerCache>().unwrap().lock().await;
+    discordhelpers::refresh_presence(&data, &shard_manager, server_count).await;
+
+    let reply = match &custom {
+        Some(text) => format!("Presence set to `{}` until cleared with `;setpresence clear`", text),
+        None => String::from("Custom presence cleared, back to our server count"),
+    };
+    msg.channel_id.say(&ctx.http, reply).await?;
+
+    info!("Presence changed by {}", msg.author.tag());
+    debug!("Command executed");
+    Ok(())
+}
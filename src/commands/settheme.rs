@@ -0,0 +1,56 @@
+use serenity::builder::CreateEmbed;
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::SettingsCache;
+use crate::utls::constants::COLOR_OKAY;
+use crate::utls::discordhelpers;
+
+#[command]
+#[only_in(guilds)]
+pub async fn settheme(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    if !discordhelpers::is_admin(ctx, msg).await {
+        return Err(CommandError::from(
+            "Only server administrators may change the embed theme",
+        ));
+    }
+
+    let input = match args.current() {
+        Some(s) => s.to_lowercase(),
+        None => return Err(CommandError::from("Usage: `settheme <#RRGGBB | reset>`")),
+    };
+
+    let theme = if input == "reset" {
+        None
+    } else {
+        match discordhelpers::parse_hex_color(&input) {
+            Some(c) => Some(c),
+            None => {
+                return Err(CommandError::from(format!(
+                    "'{}' is not a valid color, please use the #RRGGBB format",
+                    input
+                )));
+            }
+        }
+    };
+
+    {
+        let data = ctx.data.read().await;
+        let mut settings = data.get::<SettingsCache>().unwrap().write().await;
+        settings.guild_mut(msg.guild_id.unwrap().0).theme = theme;
+        settings.write();
+    }
+
+    // show off the new color
+    let mut emb = CreateEmbed::default();
+    emb.color(theme.unwrap_or(COLOR_OKAY));
+    match theme {
+        Some(c) => emb.description(format!("Embeds in this server will now use #{:06x}", c)),
+        None => emb.description("Embeds in this server are back to the default color"),
+    };
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+
+    debug!("Command executed");
+    Ok(())
+}
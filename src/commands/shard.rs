@@ -0,0 +1,47 @@
+use serenity::client::bridge::gateway::ShardId;
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::{ConfigCache, ShardManagerCache};
+use crate::utls::discordhelpers;
+
+#[command]
+pub async fn shard(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let emb = {
+        let data = ctx.data.read().await;
+        let shard_manager = data.get::<ShardManagerCache>().unwrap().lock().await;
+        let runners = shard_manager.runners.lock().await;
+
+        // we may only host some of the shards, the configured count is the one discord uses
+        let configured = data.get::<ConfigCache>().unwrap().read().await
+            .get("SHARD_COUNT")
+            .and_then(|c| c.parse::<u64>().ok())
+            .unwrap_or_default();
+        let shard_count = if configured > 0 { configured } else { runners.len() as u64 };
+        if shard_count == 0 {
+            return Err(CommandError::from("No shards are currently running"));
+        }
+
+        // DMs are always handled by shard 0
+        let shard_id = match msg.guild_id {
+            Some(guild) => discordhelpers::shard_for_guild(guild.0, shard_count),
+            None => 0,
+        };
+
+        match runners.get(&ShardId(shard_id)) {
+            Some(runner) => discordhelpers::build_shard_embed(shard_id, shard_count, runner.latency, &runner.stage),
+            None => {
+                return Err(CommandError::from(format!(
+                    "Shard {} isn't managed by this instance",
+                    shard_id
+                )));
+            }
+        }
+    };
+
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+
+    debug!("Command executed");
+    Ok(())
+}
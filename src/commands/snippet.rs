@@ -0,0 +1,190 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::{GodboltCache, SettingsCache, WandboxCache};
+use crate::commands::compile;
+use crate::utls::constants::{MAX_SNIPPETS, MAX_SNIPPET_LEN, MAX_SNIPPET_NAME_LEN};
+use crate::utls::parser::{self, ParserResult};
+use crate::utls::settings::Snippet;
+use crate::utls::discordhelpers;
+
+#[command]
+#[only_in(guilds)]
+#[sub_commands(save, run, list, delete)]
+pub async fn snippet(_ctx: &Context, _msg: &Message, _args: Args) -> CommandResult {
+    Err(CommandError::from(
+        "Usage: `snippet run <name>`, `snippet list`, or for administrators `snippet save <name> <language> <code block>` & `snippet delete <name>`",
+    ))
+}
+
+#[command]
+async fn save(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    if !discordhelpers::is_admin(ctx, msg).await {
+        return Err(CommandError::from("Only server administrators may save snippets"));
+    }
+
+    let name = snippet_name(&mut args)?;
+    let language = match args.single::<String>() {
+        Ok(l) if !l.starts_with('`') => String::from(parser::normalize_language(&l.to_lowercase())),
+        _ => {
+            return Err(CommandError::from(
+                "No language specified!\nUsage: `snippet save <name> <language> <code block>`",
+            ));
+        }
+    };
+    let code = match parser::first_code_block(&msg.content) {
+        Some(c) => c,
+        None => {
+            return Err(CommandError::from(
+                "You must attach a code-block containing the snippet's code to your message",
+            ));
+        }
+    };
+    if code.len() > MAX_SNIPPET_LEN {
+        return Err(CommandError::from(format!(
+            "Snippets can be at most {} characters long",
+            MAX_SNIPPET_LEN
+        )));
+    }
+
+    // a bad language would only show itself once someone runs the snippet
+    let known = {
+        let data = ctx.data.read().await;
+        let wbox = data.get::<WandboxCache>().unwrap().read().await;
+        let godbolt = data.get::<GodboltCache>().unwrap().read().await;
+        wbox.get_compilers(&language).is_some()
+            || godbolt.resolve(&language).is_some()
+            || compile::assembler_for(&language).is_some()
+    };
+    if !known {
+        return Err(CommandError::from(format!(
+            "Could not find language '{}'",
+            &language
+        )));
+    }
+
+    let guild_id = msg.guild_id.unwrap().0;
+    {
+        let data = ctx.data.read().await;
+        let mut settings = data.get::<SettingsCache>().unwrap().write().await;
+        let snippets = &mut settings.guild_mut(guild_id).snippets;
+        if !snippets.contains_key(&name) && snippets.len() >= MAX_SNIPPETS {
+            return Err(CommandError::from(format!(
+                "This server already has {} snippets, delete one before saving another",
+                MAX_SNIPPETS
+            )));
+        }
+        snippets.insert(name.clone(), Snippet {
+            language: language.clone(),
+            code,
+            author: msg.author.id.0,
+        });
+        settings.write();
+    }
+
+    msg.channel_id
+        .say(&ctx.http, format!("Saved `{}` ({}), run it with `snippet run {}`", name, language, name))
+        .await?;
+    debug!("Command executed");
+    Ok(())
+}
+
+#[command]
+#[bucket = "nospam"]
+async fn run(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let name = snippet_name(&mut args)?;
+    let snippet = {
+        let data = ctx.data.read().await;
+        let settings = data.get::<SettingsCache>().unwrap().read().await;
+        settings.snippet(msg.guild_id.unwrap().0, &name).cloned()
+    };
+    let snippet = match snippet {
+        Some(s) => s,
+        None => {
+            return Err(CommandError::from(format!(
+                "There's no snippet named `{}`, see `snippet list` for this server's snippets",
+                name
+            )));
+        }
+    };
+
+    let parse_result = ParserResult {
+        target: snippet.language,
+        code: snippet.code,
+        ..Default::default()
+    };
+
+    compile::handle_request(ctx, msg, parse_result, true).await
+}
+
+#[command]
+async fn list(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let mut snippets: Vec<String> = {
+        let data = ctx.data.read().await;
+        let settings = data.get::<SettingsCache>().unwrap().read().await;
+        match settings.guild(msg.guild_id.unwrap().0) {
+            Some(g) => g
+                .snippets
+                .iter()
+                .map(|(name, s)| format!("`{}` ({})", name, s.language))
+                .collect(),
+            None => Vec::new(),
+        }
+    };
+    snippets.sort();
+
+    let reply = if snippets.is_empty() {
+        String::from("This server has no snippets yet")
+    } else {
+        format!("Snippets in this server: {}", snippets.join(", "))
+    };
+    msg.channel_id.say(&ctx.http, reply).await?;
+    debug!("Command executed");
+    Ok(())
+}
+
+#[command]
+async fn delete(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    if !discordhelpers::is_admin(ctx, msg).await {
+        return Err(CommandError::from("Only server administrators may delete snippets"));
+    }
+
+    let name = snippet_name(&mut args)?;
+    let removed = {
+        let data = ctx.data.read().await;
+        let mut settings = data.get::<SettingsCache>().unwrap().write().await;
+        let removed = settings.guild_mut(msg.guild_id.unwrap().0).snippets.remove(&name).is_some();
+        if removed {
+            settings.write();
+        }
+        removed
+    };
+
+    let reply = if removed {
+        format!("Deleted `{}`", name)
+    } else {
+        format!("There's no snippet named `{}`", name)
+    };
+    msg.channel_id.say(&ctx.http, reply).await?;
+    debug!("Command executed");
+    Ok(())
+}
+
+// names are kept short & simple so they're easy to type out
+fn snippet_name(args: &mut Args) -> Result<String, CommandError> {
+    let name = match args.single::<String>() {
+        Ok(n) => n.to_lowercase(),
+        Err(_e) => return Err(CommandError::from("No snippet name specified!")),
+    };
+
+    let valid = name.len() <= MAX_SNIPPET_NAME_LEN
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(CommandError::from(format!(
+            "Snippet names may only contain letters, numbers, - and _, and be at most {} characters long",
+            MAX_SNIPPET_NAME_LEN
+        )));
+    }
+    Ok(name)
+}
@@ -0,0 +1,69 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::SettingsCache;
+use crate::utls::discordhelpers;
+
+static USAGE: &str = "Usage: `supporters role <@role | none>`, `supporters boosters <on | off>` or `supporters cooldown <0-100>`";
+
+#[command]
+#[only_in(guilds)]
+pub async fn supporters(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    if !discordhelpers::is_admin(ctx, msg).await {
+        return Err(CommandError::from(
+            "Only server administrators may change supporter perks",
+        ));
+    }
+
+    let setting = match args.single::<String>() {
+        Ok(s) => s.to_lowercase(),
+        Err(_e) => return Err(CommandError::from(USAGE)),
+    };
+
+    let data = ctx.data.read().await;
+    let mut settings = data.get::<SettingsCache>().unwrap().write().await;
+    let guild = settings.guild_mut(msg.guild_id.unwrap().0);
+
+    let reply = match setting.as_str() {
+        "role" => {
+            if args.current().map(|s| s.eq_ignore_ascii_case("none")).unwrap_or(false) {
+                guild.supporter_role = None;
+                String::from("Supporters are no longer recognized by role")
+            } else {
+                let role = match args.single::<RoleId>() {
+                    Ok(r) => r,
+                    Err(_e) => return Err(CommandError::from(USAGE)),
+                };
+                guild.supporter_role = Some(role.0);
+                format!("Members with <@&{}> now get a reduced compile cooldown", role.0)
+            }
+        }
+        "boosters" => {
+            match args.current().map(|s| s.to_lowercase()).as_deref() {
+                Some("on") => guild.booster_exemption = true,
+                Some("off") => guild.booster_exemption = false,
+                _ => return Err(CommandError::from(USAGE)),
+            };
+            if guild.booster_exemption {
+                String::from("Server boosters now get a reduced compile cooldown")
+            } else {
+                String::from("Server boosters no longer get a reduced compile cooldown")
+            }
+        }
+        "cooldown" => {
+            let percentage = match args.single::<u8>() {
+                Ok(p) if p <= 100 => p,
+                _ => return Err(CommandError::from("The cooldown must be a percentage between 0 and 100")),
+            };
+            guild.supporter_cooldown = Some(percentage);
+            format!("Supporters now get {}% of the normal compile cooldown", percentage)
+        }
+        _ => return Err(CommandError::from(USAGE)),
+    };
+    settings.write();
+
+    msg.channel_id.say(&ctx.http, reply).await?;
+    debug!("Command executed");
+    Ok(())
+}
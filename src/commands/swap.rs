@@ -0,0 +1,40 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::LastSourceCache;
+use crate::commands::compile;
+use crate::utls::constants::LAST_SOURCE_TTL;
+use crate::utls::parser;
+
+#[command]
+#[bucket = "nospam"]
+pub async fn swap(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let compiler = match args.parse::<String>() {
+        Ok(s) => s.trim().to_lowercase(),
+        Err(_e) => {
+            return Err(CommandError::from(
+                "No compiler specified!\nPlease try giving me a compiler or language to swap to",
+            ));
+        }
+    };
+
+    // grab the author's last request, if it hasn't gone stale yet
+    let mut parse_result = {
+        let data_read = ctx.data.read().await;
+        let mut last_source = data_read.get::<LastSourceCache>().unwrap().lock().await;
+        match last_source.get_mut(&msg.author.id.0) {
+            Some((time, result)) if time.elapsed().as_secs() < LAST_SOURCE_TTL => result.clone(),
+            _ => {
+                return Err(CommandError::from(
+                    "I couldn't find a recent compilation of yours to re-run.\nTry compiling something first!",
+                ));
+            }
+        }
+    };
+
+    // same treatment the parser gives the target
+    parse_result.target = String::from(parser::normalize_language(&compiler));
+
+    compile::handle_request(ctx, msg, parse_result, true).await
+}
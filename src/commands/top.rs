@@ -0,0 +1,34 @@
+use serenity::framework::standard::{macros::command, Args, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::StatsManagerCache;
+use crate::utls::discordhelpers;
+
+// this reveals usage across every guild we're in, so it's kept to the owners
+#[command]
+#[owners_only]
+pub async fn top(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let amount = args.single::<usize>().unwrap_or(10).max(1).min(25);
+
+    let top_guilds = {
+        let data = ctx.data.read().await;
+        let stats = data.get::<StatsManagerCache>().unwrap().lock().await;
+        stats.top_guilds(amount)
+    };
+
+    let mut guilds = Vec::new();
+    for (guild_id, count) in top_guilds {
+        let name = match GuildId(guild_id).to_partial_guild(&ctx.http).await {
+            Ok(g) => format!("{} ({})", g.name, guild_id),
+            Err(_) => format!("{} (left)", guild_id),
+        };
+        guilds.push((name, count));
+    }
+
+    let emb = discordhelpers::build_top_guilds_embed(&guilds);
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+
+    debug!("Command executed");
+    Ok(())
+}
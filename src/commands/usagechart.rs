@@ -0,0 +1,52 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::http::AttachmentType;
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::StatsManagerCache;
+use crate::utls::chart;
+use crate::utls::constants::CHART_COMMANDS;
+
+#[command]
+#[owners_only]
+pub async fn usagechart(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let ranking = {
+        let data = ctx.data.read().await;
+        let stats = data.get::<StatsManagerCache>().unwrap().lock().await;
+        stats.command_ranking(CHART_COMMANDS)
+    };
+
+    if ranking.is_empty() {
+        msg.channel_id.say(&ctx.http, "No commands have been recorded since the last restart, there's nothing to chart yet").await?;
+        debug!("Command executed");
+        return Ok(());
+    }
+
+    // drawing blocks, so it's kept off the async runtime
+    let path = std::env::temp_dir().join(format!("usagechart-{}.png", msg.id.0));
+    let render_path = path.clone();
+    let rendered = tokio::task::spawn_blocking(move || {
+        chart::render_bar_chart(&render_path, "Command usage", &ranking).map_err(|e| e.to_string())
+    })
+    .await;
+
+    match rendered {
+        Ok(Ok(())) => (),
+        Ok(Err(e)) => return Err(CommandError::from(format!("Unable to render the chart\n{}", e))),
+        Err(e) => return Err(CommandError::from(format!("Unable to render the chart\n{}", e))),
+    }
+
+    let sent = msg
+        .channel_id
+        .send_files(&ctx.http, vec![AttachmentType::Path(&path)], |m| {
+            m.content(format!("Top {} commands across tracked guilds since the last restart", CHART_COMMANDS))
+        })
+        .await;
+    if let Err(e) = std::fs::remove_file(&path) {
+        warn!("Unable to remove chart {}: {}", path.display(), e);
+    }
+    sent?;
+
+    debug!("Command executed");
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use wandbox::CompilationBuilder;
+
+use crate::cache::{RuntimeVersionCache, SettingsCache, WandboxCache};
+use crate::utls::discordhelpers;
+
+#[command]
+pub async fn version(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let data = ctx.data.read().await;
+    let target = match args.parse::<String>() {
+        Ok(s) => s.trim().to_lowercase(),
+        Err(_e) => {
+            let settings = data.get::<SettingsCache>().unwrap().read().await;
+            match settings.default_language(msg.guild_id.map(|g| g.0).unwrap_or_default(), msg.channel_id.0) {
+                Some(l) => l,
+                None => {
+                    return Err(CommandError::from(
+                        "No language specified!\nPlease try giving me a language or compiler",
+                    ));
+                }
+            }
+        }
+    };
+
+    let cached = data.get::<RuntimeVersionCache>().unwrap().lock().await.get_mut(&target).cloned();
+    let compiler = match cached {
+        Some(c) => c,
+        None => {
+            // resolve it exactly like a compilation would, so we report what ;run uses
+            let wbox = data.get::<WandboxCache>().unwrap().read().await;
+            let mut builder = CompilationBuilder::new();
+            builder.target(&target);
+            if builder.build(&wbox).is_err() {
+                return Err(CommandError::from(format!(
+                    "Could not find language or compiler '{}'",
+                    &target
+                )));
+            }
+
+            let compiler = wbox
+                .get_compilers(&builder.lang)
+                .and_then(|compilers| compilers.into_iter().find(|c| c.name == builder.compiler));
+            match compiler {
+                Some(c) => {
+                    let mut cache = data.get::<RuntimeVersionCache>().unwrap().lock().await;
+                    cache.insert(target.clone(), c.clone());
+                    c
+                }
+                None => {
+                    return Err(CommandError::from(format!(
+                        "Unable to find version information for '{}'",
+                        &target
+                    )));
+                }
+            }
+        }
+    };
+
+    let mut emb = discordhelpers::build_version_embed(&msg.author, &target, &compiler);
+    let theme = discordhelpers::theme_color(ctx, msg.guild_id).await;
+    discordhelpers::apply_theme(&mut emb, theme);
+    discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+
+    debug!("Command executed");
+    Ok(())
+}
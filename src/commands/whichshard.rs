@@ -0,0 +1,56 @@
+use serenity::client::bridge::gateway::ShardId;
+use serenity::framework::standard::{macros::command, Args, CommandError, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+use crate::cache::{ConfigCache, ShardManagerCache};
+use crate::utls::discordhelpers;
+
+#[command]
+#[owners_only]
+pub async fn whichshard(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let usage = "Usage: `;whichshard <guild id>`";
+    let guild_id = match args.current() {
+        Some(id) => id.parse::<u64>().map_err(|_| CommandError::from(usage))?,
+        None => return Err(CommandError::from(usage)),
+    };
+
+    // anything without a timestamp can't be a snowflake discord handed out
+    if guild_id >> 22 == 0 {
+        return Err(CommandError::from(format!("{} isn't a valid guild id", guild_id)));
+    }
+
+    let reply = {
+        let data = ctx.data.read().await;
+        let shard_manager = data.get::<ShardManagerCache>().unwrap().lock().await;
+        let runners = shard_manager.runners.lock().await;
+
+        // we may only host some of the shards, the configured count is the one discord uses
+        let configured = data.get::<ConfigCache>().unwrap().read().await
+            .get("SHARD_COUNT")
+            .and_then(|c| c.parse::<u64>().ok())
+            .unwrap_or_default();
+        let shard_count = if configured > 0 { configured } else { runners.len() as u64 };
+        if shard_count == 0 {
+            return Err(CommandError::from("No shards are currently running"));
+        }
+
+        let shard_id = discordhelpers::shard_for_guild(guild_id, shard_count);
+        match runners.get(&ShardId(shard_id)) {
+            Some(runner) => Ok(discordhelpers::build_shard_embed(shard_id, shard_count, runner.latency, &runner.stage)),
+            None => Err(format!("Guild {} is on shard {} of {}, which isn't managed by this instance", guild_id, shard_id, shard_count)),
+        }
+    };
+
+    match reply {
+        Ok(emb) => {
+            discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await?;
+        }
+        Err(text) => {
+            msg.channel_id.say(&ctx.http, text).await?;
+        }
+    }
+
+    debug!("Command executed");
+    Ok(())
+}
@@ -1,24 +1,42 @@
 use serenity::{
     async_trait,
-    framework::standard:: {
-        macros::hook, CommandResult, DispatchError
+    builder::CreateEmbed,
+    framework::{
+        standard::{macros::hook, CommandError, CommandResult, DispatchError},
+        Framework
     },
     model::{
-        channel::Message,
-        event::ResumedEvent,
+        channel::{Message, Reaction, ReactionType},
+        event::{MessageUpdateEvent, ResumedEvent},
         guild::{Guild, GuildUnavailable},
-        id::{ChannelId, MessageId},
-        gateway::Ready
+        id::{ChannelId, GuildId, MessageId, RoleId},
+        gateway::Ready,
+        user::CurrentUser
     },
     prelude::*,
+    http::Http,
     futures::lock::MutexGuard
 };
 
+use std::env;
+use std::sync::Arc;
+
 use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+use serde_json::Value;
 
+use crate::apis::{self, interactions};
 use crate::cache::*;
-use crate::utls::discordhelpers;
-use crate::stats::statsmanager::StatsManager;
+use crate::slashcommands;
+use crate::utls::constants::{ALERT_CHECK_INTERVAL, BACKENDS, BLOCKED_EMOJI, COMPILE_COMMANDS, EDIT_RERUN_WINDOW, EXPAND_EMOJI, EXPIRY_CHECK_INTERVAL, LOG_THROTTLE_WINDOW, NOSPAM_DELAY, RATELIMIT_PROBE_INTERVAL, SCHEDULE_CHECK_INTERVAL, REPORT_EMOJI, RERUN_EMOJI, STDIN_PROMPT_TIMEOUT};
+use crate::commands::compile;
+use crate::utls::{discordhelpers, limiter, parser, persistence, selftest, throttle, trusted};
+use crate::utls::blocklist::BlockEntry;
+use crate::utls::gates::{self, Gates, Refusal, Requester};
+use crate::utls::selftest::SelfTestMode;
+use crate::utls::fuzzy;
+use crate::stats::metrics;
+use crate::stats::statsmanager::{self, StatsManager};
 
 pub struct Handler; // event handler for serenity
 
@@ -31,10 +49,17 @@ trait ShardsReadyHandler {
 impl ShardsReadyHandler for Handler {
     async fn all_shards_ready(&self, ctx: &Context, stats: & mut MutexGuard<'_, StatsManager>, ready : &Ready) {
         let data = ctx.data.read().await;
-        let mut info = data.get::<ConfigCache>().unwrap().write().await;
-        info.insert("BOT_AVATAR", ready.user.avatar_url().unwrap());
+        let bot_id = {
+            let mut info = cache_or_return!(&data, ConfigCache).write().await;
+            info.insert("BOT_AVATAR", bot_avatar(&ready.user));
+            info.get("BOT_ID").unwrap().parse::<u64>().unwrap()
+        };
+
+        // slash commands are registered globally, so we only need to do this once.
+        // Config is left unlocked for the requests, handlers would be stuck waiting on it
+        interactions::register_commands(bot_id).await;
 
-        let shard_manager = data.get::<ShardManagerCache>().unwrap().lock().await;
+        let shard_manager = cache_or_return!(&data, ShardManagerCache).lock().await;
         let guild_count = stats.get_boot_vec_sum();
 
         // update stats
@@ -42,24 +67,336 @@ impl ShardsReadyHandler for Handler {
             stats.post_servers(guild_count).await;
         }
 
-        discordhelpers::send_global_presence(&shard_manager, guild_count).await;
+        discordhelpers::refresh_presence(&data, &shard_manager, guild_count).await;
+        spawn_presence_task(ctx.data.clone());
+        spawn_health_task(ctx.data.clone(), ctx.http.clone());
+        spawn_ratelimit_task(ctx.data.clone(), ctx.http.clone());
+        spawn_cache_stats_task(ctx.data.clone());
+        spawn_log_flush_task(ctx.data.clone());
+        spawn_backup_task();
+        spawn_guild_stats_task(ctx.data.clone());
+        spawn_schedule_task(ctx.data.clone(), ctx.http.clone());
+        spawn_expiry_task(ctx.data.clone(), ctx.http.clone());
+        spawn_self_test(ctx.clone());
 
         info!("Ready in {} guilds", guild_count);
     }
 }
 
+// Runs a hello world on every backend so a broken deployment shows itself right away.
+// Should the primary backend fail we either exit or switch compiling off, see selftest::mode
+fn spawn_self_test(ctx: Context) {
+    let mode = selftest::mode();
+    if mode == SelfTestMode::Off {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let primary = compile::preferred_backend(&ctx, None).await;
+        let mut primary_failed = false;
+        for (backend, _) in BACKENDS.iter() {
+            match compile::self_test(&ctx, backend).await {
+                Ok(elapsed) => info!("Self-test passed on {} in {}ms", backend, elapsed),
+                Err(e) => {
+                    error!("Self-test failed on {}: {}", backend, e);
+                    primary_failed |= *backend == primary;
+                }
+            }
+        }
+
+        if !primary_failed {
+            return;
+        }
+
+        if mode == SelfTestMode::Strict {
+            error!("Primary backend {} failed its self-test, refusing to start", primary);
+            std::process::exit(1);
+        }
+
+        warn!("Primary backend {} failed its self-test, compiling is switched off until ;killswitch off", primary);
+        let data = ctx.data.read().await;
+        let mut kill_switch = cache_or_return!(&data, KillSwitchCache).write().await;
+        *kill_switch = Some(format!("{} failed its startup self-test", primary));
+    });
+}
+
+// Periodically refreshes every shard's presence, each cycle is offset by a
+// random amount so multiple instances don't fall into lockstep
+fn spawn_presence_task(data: Arc<RwLock<TypeMap>>) {
+    let interval = discordhelpers::presence_interval();
+    if interval == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        loop {
+            let jitter = discordhelpers::presence_jitter();
+            let offset = if jitter > 0 { rand::thread_rng().gen_range(0, jitter) } else { 0 };
+            tokio::time::delay_for(std::time::Duration::from_millis(interval * 1000 + offset)).await;
+
+            let data = data.read().await;
+            let server_count = cache_or_return!(&data, StatsManagerCache).lock().await.server_count();
+            let shard_manager = cache_or_return!(&data, ShardManagerCache).lock().await;
+            discordhelpers::refresh_presence(&data, &shard_manager, server_count).await;
+        }
+    });
+}
+
+// Deletes outputs whose guild's expiry is up. Outputs someone already deleted
+// by hand simply fail to delete, there's nothing else left to clean up for them
+fn spawn_expiry_task(data: Arc<RwLock<TypeMap>>, http: Arc<Http>) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::delay_for(std::time::Duration::from_secs(EXPIRY_CHECK_INTERVAL)).await;
+
+            let due = {
+                let data = data.read().await;
+                let mut expirations = cache_or_return!(&data, ExpiryCache).lock().await;
+                expirations.take_due(Utc::now())
+            };
+
+            for pending in due {
+                if let Err(e) = http.delete_message(pending.channel, pending.message).await {
+                    debug!("Unable to delete expired output {}: {}", pending.message, e);
+                }
+            }
+        }
+    });
+}
+
+// Summarizes the warnings the log throttle held back since its last flush
+fn spawn_log_flush_task(data: Arc<RwLock<TypeMap>>) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::delay_for(std::time::Duration::from_secs(LOG_THROTTLE_WINDOW)).await;
+
+            let data = data.read().await;
+            let summaries = cache_or_return!(&data, LogThrottleCache).lock().unwrap().flush();
+            for (key, suppressed) in summaries {
+                warn!("{} x{} more times in the last minute", key, suppressed);
+            }
+        }
+    });
+}
+
+// Alerts the owners once a service we post to has been failing for too long,
+// see HealthMonitor for when that actually happens
+fn spawn_health_task(data: Arc<RwLock<TypeMap>>, http: Arc<Http>) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::delay_for(std::time::Duration::from_secs(ALERT_CHECK_INTERVAL)).await;
+
+            let data = data.read().await;
+            let alerts = cache_or_return!(&data, HealthMonitorCache).lock().unwrap().take_alerts();
+            if alerts.is_empty() {
+                continue;
+            }
+
+            let owners = cache_or_return!(&data, OwnersCache);
+            for alert in &alerts {
+                warn!("{} has failed {} times in a row, alerting owners", alert.service, alert.failures);
+                for owner in owners.iter() {
+                    let emb = discordhelpers::build_service_alert_embed(alert);
+                    let sent = match owner.create_dm_channel(&http).await {
+                        Ok(dm) => discordhelpers::send_embed(dm.id, &http, emb).await.is_ok(),
+                        Err(_) => false,
+                    };
+                    if !sent {
+                        warn!("Unable to alert owner {}", owner.0);
+                    }
+                }
+            }
+        }
+    });
+}
+
+// Serenity doesn't tell us about global rate limits, it just holds every request
+// until they're over. So we time a cheap request every so often instead, one that
+// takes far too long means everything else we send is stuck waiting as well
+fn spawn_ratelimit_task(data: Arc<RwLock<TypeMap>>, http: Arc<Http>) {
+    let threshold = discordhelpers::ratelimit_threshold();
+    if threshold == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut limited = false;
+        loop {
+            tokio::time::delay_for(std::time::Duration::from_secs(RATELIMIT_PROBE_INTERVAL)).await;
+
+            let started = std::time::Instant::now();
+            let probe = tokio::time::timeout(
+                std::time::Duration::from_millis(threshold),
+                http.get_current_user(),
+            ).await;
+            let slow = match probe {
+                Ok(Ok(_)) => false,
+                Ok(Err(e)) => {
+                    // failures say nothing about rate limits, check again next time
+                    let data = data.read().await;
+                    throttle::warn(cache_or_return!(&data, LogThrottleCache), "Rate limit probe failed", &format!("Rate limit probe failed: {}", e));
+                    continue;
+                }
+                Err(_) => true,
+            };
+
+            let data = data.read().await;
+            let shard_manager = cache_or_return!(&data, ShardManagerCache).lock().await;
+            if slow {
+                if !limited {
+                    warn!("Requests to discord are taking over {}ms, we're likely globally rate limited", threshold);
+                }
+                // other presence updates may have replaced ours in the meantime
+                discordhelpers::send_ratelimit_presence(&shard_manager).await;
+                limited = true;
+            } else if limited {
+                info!("No longer rate limited, probe took {}ms", started.elapsed().as_millis());
+                let server_count = cache_or_return!(&data, StatsManagerCache).lock().await.server_count();
+                discordhelpers::refresh_presence(&data, &shard_manager, server_count).await;
+                limited = false;
+            }
+        }
+    });
+}
+
+// Logs how big our in-memory caches are every so often, a cache that keeps
+// growing between reports is a good hint something is leaking
+fn spawn_cache_stats_task(data: Arc<RwLock<TypeMap>>) {
+    let interval = discordhelpers::cache_stats_interval();
+    if interval == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::delay_for(std::time::Duration::from_secs(interval * 60)).await;
+
+            let data = data.read().await;
+            let delete_cache = cache_or_return!(&data, MessageDeleteCache).lock().await.len();
+            let last_source = cache_or_return!(&data, LastSourceCache).lock().await.len();
+            let output_records = cache_or_return!(&data, OutputRecordCache).lock().await.len();
+            let cooldowns = cache_or_return!(&data, InteractionCooldownCache).lock().await.len();
+            let guild_stats = cache_or_return!(&data, StatsManagerCache).lock().await.tracked_guilds();
+            let limiter = cache_or_return!(&data, CompileLimiterCache);
+
+            info!(
+                "Cache sizes: message delete {}, last source {}, output records {}, cooldowns {}, guild stats {} | compilations in flight {}/{}",
+                delete_cache,
+                last_source,
+                output_records,
+                cooldowns,
+                guild_stats,
+                limiter.in_flight(),
+                limiter.max()
+            );
+        }
+    });
+}
+
+// Snapshots everything we persist to disk every so often, keeping the
+// last few around in case a bad write or a crash corrupts the originals
+fn spawn_backup_task() {
+    let interval = persistence::backup_interval();
+    if interval == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::delay_for(std::time::Duration::from_secs(interval * 60)).await;
+
+            match persistence::backup(&persistence::backup_dir(), persistence::backup_retention()) {
+                Ok(path) => info!("Backed up persisted state to {}", path.display()),
+                Err(e) => error!("Unable to back up persisted state: {}", e),
+            }
+        }
+    });
+}
+
+// Flushes the per guild usage counters to disk so ;guildstats survives restarts
+fn spawn_guild_stats_task(data: Arc<RwLock<TypeMap>>) {
+    let interval = statsmanager::guild_stats_flush_interval();
+    if interval == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::delay_for(std::time::Duration::from_secs(interval * 60)).await;
+
+            let snapshot = {
+                let data = data.read().await;
+                let mut stats = cache_or_return!(&data, StatsManagerCache).lock().await;
+                stats.take_guild_stats_snapshot()
+            };
+            if let Some(json) = snapshot {
+                statsmanager::write_guild_stats(&json);
+            }
+        }
+    });
+}
+
+// Opens & closes scheduled maintenance windows, letting subscribed channels know
+fn spawn_schedule_task(data: Arc<RwLock<TypeMap>>, http: Arc<Http>) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::delay_for(std::time::Duration::from_secs(SCHEDULE_CHECK_INTERVAL)).await;
+
+            let data = data.read().await;
+            let update = cache_or_return!(&data, ScheduleCache).write().await.tick(Utc::now());
+            if update.started.is_none() && update.finished.is_none() {
+                continue;
+            }
+
+            let channels = cache_or_return!(&data, SettingsCache).read().await.announcement_channels();
+            if let Some(finished) = &update.finished {
+                info!("Maintenance finished: {}", finished.reason);
+                for channel in &channels {
+                    let emb = discordhelpers::build_maintenance_embed(finished, false);
+                    discordhelpers::manual_dispatch(http.clone(), *channel, emb).await;
+                }
+            }
+            if let Some(started) = &update.started {
+                info!("Maintenance started for {} minutes: {}", started.minutes, started.reason);
+                for channel in &channels {
+                    let emb = discordhelpers::build_maintenance_embed(started, true);
+                    discordhelpers::manual_dispatch(http.clone(), *channel, emb).await;
+                }
+            }
+        }
+    });
+}
+
 #[async_trait]
 impl EventHandler for Handler {
     async fn guild_create(&self, ctx: Context, guild: Guild) {
+        let first_sight = {
+            let data = ctx.data.read().await;
+            let mut seen = cache_or_return!(&data, SeenGuildsCache).lock().await;
+            seen.insert(guild.id.0)
+        };
+
+        // guilds are re-delivered on every reconnect, so only ones we haven't seen
+        // yet can be new. Without a join time we have to take their word for it
         let now: DateTime<Utc> = Utc::now();
-        if guild.joined_at + Duration::seconds(30) > now {
+        let new_join = first_sight && match joined_at(&guild) {
+            Some(joined) => joined + Duration::seconds(30) > now,
+            None => true,
+        };
+
+        if new_join {
             let data = ctx.data.read().await;
+            {
+                let mut log = cache_or_return!(&data, GuildLogCache).lock().await;
+                log.joined(guild.id.0, &guild.name, guild.member_count);
+            }
 
             // publish new server to stats
-            let mut stats = data.get::<StatsManagerCache>().unwrap().lock().await;
+            let mut stats = cache_or_return!(&data, StatsManagerCache).lock().await;
             if stats.should_track() {
                 stats.new_server().await;
             }
+            metrics::record(&data, |m| m.guild_joined(ctx.shard_id));
             let server_count = stats.server_count();
             let shard_count = stats.shard_count();
 
@@ -67,7 +404,7 @@ impl EventHandler for Handler {
             // post new server to join log
             let id;
             {
-                let info = data.get::<ConfigCache>().unwrap().read().await;
+                let info = cache_or_return!(&data, ConfigCache).read().await;
                 id = info.get("BOT_ID").unwrap().parse::<u64>().unwrap();
 
                 if let Some(log) = info.get("JOIN_LOG") {
@@ -79,30 +416,48 @@ impl EventHandler for Handler {
             }
 
             // update DBL site
-            {
-                let dbl = data.get::<DBLCache>().unwrap().read().await;
+            apis::dbl::post_stats(&data, id, server_count, shard_count, "guild_create").await;
 
-                let new_stats = dbl::types::ShardStats::Cumulative {
-                    server_count,
-                    shard_count: Some(shard_count)
-                };
+            // update shard guild count & presence
+            let shard_manager = cache_or_return!(&data, ShardManagerCache).lock().await;
+            discordhelpers::refresh_presence(&data, &shard_manager, server_count).await;
 
-                if dbl.update_stats(id, new_stats).await.is_err() {
-                    warn!("Failed to post stats to dbl");
-                }
+            if discordhelpers::log_guild_events() {
+                info!("Joining {}", guild.name);
             }
+        }
+    }
 
-            // update shard guild count & presence
-            let shard_manager = data.get::<ShardManagerCache>().unwrap().lock().await;
-            discordhelpers::send_global_presence(&shard_manager, server_count).await;
+    async fn message(&self, ctx: Context, msg: Message) {
+        if !msg.author.bot && msg.guild_id.is_some() && msg.content.contains("```") {
+            auto_compile(&ctx, &msg).await;
+        }
+    }
+
+    async fn message_update(&self, ctx: Context, update: MessageUpdateEvent) {
+        // embeds resolving on a message are an update too, those come without content
+        let content = match update.content {
+            Some(c) => c,
+            None => return,
+        };
+        if update.author.map(|a| a.bot).unwrap_or(false) {
+            return;
+        }
+        rerun_edited(&ctx, update.channel_id, update.id, content).await;
+    }
 
-            info!("Joining {}", guild.name);
+    async fn reaction_add(&self, ctx: Context, reaction: Reaction) {
+        match &reaction.emoji {
+            ReactionType::Unicode(s) if s == REPORT_EMOJI => report_output(&ctx, &reaction).await,
+            ReactionType::Unicode(s) if s == RERUN_EMOJI => prompt_stdin(&ctx, &reaction).await,
+            ReactionType::Unicode(s) if s == EXPAND_EMOJI => expand_output(&ctx, &reaction).await,
+            _ => (),
         }
     }
 
     async fn message_delete(&self, ctx: Context, _channel_id: ChannelId, id: MessageId) {
         let data = ctx.data.read().await;
-        let mut delete_cache = data.get::<MessageDeleteCache>().unwrap().lock().await;
+        let mut delete_cache = cache_or_return!(&data, MessageDeleteCache).lock().await;
         if let Some(msg) = delete_cache.get_mut(id.as_u64()) {
             if msg.delete(ctx.http).await.is_err() {
                 // ignore for now
@@ -113,13 +468,24 @@ impl EventHandler for Handler {
 
     async fn guild_delete(&self, ctx: Context, incomplete: GuildUnavailable) {
         let data = ctx.data.read().await;
-        let mut stats = data.get::<StatsManagerCache>().unwrap().lock().await;
+
+        // outages keep the guild around, anything else means a rejoin is a real join
+        if !incomplete.unavailable {
+            let mut seen = cache_or_return!(&data, SeenGuildsCache).lock().await;
+            seen.remove(&incomplete.id.0);
+
+            let mut log = cache_or_return!(&data, GuildLogCache).lock().await;
+            log.left(incomplete.id.0);
+        }
+
+        let mut stats = cache_or_return!(&data, StatsManagerCache).lock().await;
         if stats.should_track() {
             stats.leave_server().await;
         }
+        metrics::record(&data, |m| m.guild_left(ctx.shard_id));
 
         // post new server to join log
-        let info = data.get::<ConfigCache>().unwrap().read().await;
+        let info = cache_or_return!(&data, ConfigCache).read().await;
         let id = info.get("BOT_ID").unwrap().parse::<u64>().unwrap();
         if let Some(log) = info.get("JOIN_LOG") {
             if let Ok(id) = log.parse::<u64>() {
@@ -129,94 +495,524 @@ impl EventHandler for Handler {
         }
 
         // update DBL site
-        {
-            let new_stats = dbl::types::ShardStats::Cumulative {
-                server_count: stats.server_count(),
-                shard_count: Some(stats.shard_count())
-            };
-
-            let dbl = data.get::<DBLCache>().unwrap().read().await;
-            if dbl.update_stats(id, new_stats).await.is_err() {
-                warn!("Failed to post stats to dbl");
-            }
-        }
+        apis::dbl::post_stats(&data, id, stats.server_count(), stats.shard_count(), "guild_delete").await;
 
         // update shard guild count & presence
-        let shard_manager = data.get::<ShardManagerCache>().unwrap().lock().await;
-        discordhelpers::send_global_presence(&shard_manager, stats.server_count()).await;
+        let shard_manager = cache_or_return!(&data, ShardManagerCache).lock().await;
+        discordhelpers::refresh_presence(&data, &shard_manager, stats.server_count()).await;
 
-        info!("Leaving {}", &incomplete.id);
+        if discordhelpers::log_guild_events() {
+            info!("Leaving {}", &incomplete.id);
+        }
     }
 
     async fn ready(&self, ctx: Context, ready: Ready) {
         info!("[Shard {}] Ready", ctx.shard_id);
 
         let data = ctx.data.read().await;
-        let mut stats = data.get::<StatsManagerCache>().unwrap().lock().await;
+
+        // every ready is a fresh session, even the ones we skip below
+        if let Some(sessions) = get_cache::<SessionCache>(&data) {
+            sessions.lock().await.ready(ctx.shard_id, &ready.session_id);
+        }
+
+        // prefer the shard count we started the client with, the ready
+        // payload is only used as a fallback (e.g. when autosharding)
+        let total_shards_to_spawn = {
+            let info = cache_or_return!(&data, ConfigCache).read().await;
+            match info.get("SHARD_COUNT").and_then(|c| c.parse::<u64>().ok()) {
+                Some(count) if count > 0 => count,
+                _ => ready.shard.map(|s| s[1]).unwrap_or(1),
+            }
+        };
+
+        let mut stats = cache_or_return!(&data, StatsManagerCache).lock().await;
 
         // occasionally we can have a ready event fire well after execution
         // this check prevents us from double calling all_shards_ready
-        let total_shards_to_spawn = ready.shard.unwrap()[1];
         if stats.shard_count()+1 > total_shards_to_spawn {
             info!("Skipping duplicate ready event...");
             return;
         }
 
         let guild_count = ready.guilds.len() as u64;
-        stats.add_shard(guild_count);
+        stats.add_shard(ctx.shard_id, guild_count);
+        metrics::record(&data, |m| m.shard_ready(ctx.shard_id, guild_count));
 
         if stats.shard_count() == total_shards_to_spawn {
             self.all_shards_ready(&ctx, & mut stats, &ready).await;
         }
     }
 
-    async fn resume(&self, _: Context, _: ResumedEvent) {
-        info!("Resumed");
+    async fn guild_role_delete(&self, ctx: Context, guild_id: GuildId, removed_role_id: RoleId) {
+        // a deleted role can never be satisfied, so drop any requirements on it
+        let data = ctx.data.read().await;
+        let mut settings = cache_or_return!(&data, SettingsCache).write().await;
+        if settings.remove_role(guild_id.0, removed_role_id.0) {
+            settings.write();
+            info!("Removed role requirements for deleted role {} in {}", removed_role_id.0, guild_id.0);
+        }
+    }
+
+    async fn resume(&self, ctx: Context, _: ResumedEvent) {
+        info!("[Shard {}] Resumed", ctx.shard_id);
+
+        let data = ctx.data.read().await;
+        if let Some(sessions) = get_cache::<SessionCache>(&data) {
+            sessions.lock().await.resumed(ctx.shard_id);
+        }
+    }
+
+    // serenity doesn't know about interactions yet, so they show up as unknown events
+    async fn unknown(&self, ctx: Context, name: String, raw: Value) {
+        if name == "INTERACTION_CREATE" {
+            slashcommands::handle_interaction(&ctx, raw).await;
+        }
+    }
+}
+
+// Runs an edited compile command again through the framework, so the before & after hooks
+// treat it just like a fresh invocation. The command puts its new results in place of the
+// old ones, see discordhelpers::replace_output. Only commands discordhelpers::record_command
+// kept qualify, and only shortly after they were sent so old messages don't suddenly recompile
+async fn rerun_edited(ctx: &Context, channel_id: ChannelId, id: MessageId, content: String) {
+    let framework = {
+        let data = ctx.data.read().await;
+        let answered = cache_or_return!(&data, MessageDeleteCache).lock().await.contains_key(&id.0);
+        if !answered {
+            return;
+        }
+
+        let mut contents = cache_or_return!(&data, CommandContentCache).lock().await;
+        match contents.get_mut(&id.0) {
+            Some(previous) if *previous != content => (),
+            _ => return,
+        }
+
+        cache_or_return!(&data, FrameworkCache).clone()
+    };
+
+    let msg = match ctx.http.get_message(channel_id.0, id.0).await {
+        Ok(m) => m,
+        Err(_) => return,
+    };
+    if msg.timestamp.timestamp() + EDIT_RERUN_WINDOW < Utc::now().timestamp() {
+        return;
+    }
+
+    framework.dispatch(ctx.clone(), msg).await;
+}
+
+// Large guilds can arrive with incomplete data, a zeroed join time
+// tells us as little as a missing one
+fn joined_at(guild: &Guild) -> Option<DateTime<Utc>> {
+    if guild.joined_at.timestamp() <= 0 {
+        return None;
+    }
+    Some(guild.joined_at)
+}
+
+// Forwards a flagged output to the moderation channel
+async fn report_output(ctx: &Context, reaction: &Reaction) {
+    let log = match env::var("MODERATION_CHANNEL").ok().and_then(|c| c.parse::<u64>().ok()) {
+        Some(id) => id,
+        None => return,
+    };
+
+    // only the first flag counts, any after that would just be noise for moderators
+    if !unflagged_output(ctx, reaction).await {
+        return;
+    }
+
+    let user = match reaction.user(ctx).await {
+        Ok(u) => u,
+        Err(_) => return,
+    };
+    if user.bot {
+        return;
+    }
+    let output = match reaction.message(&ctx.http).await {
+        Ok(m) => m,
+        Err(_) => return,
+    };
+
+    // the output is only flagged once we know the report can be sent, & someone
+    // else may have flagged it while we were asking discord about it
+    let emb = {
+        let data = ctx.data.read().await;
+        let mut records = cache_or_return!(&data, OutputRecordCache).lock().await;
+        let record = match records.get_mut(&reaction.message_id.0) {
+            Some(r) => r,
+            None => return,
+        };
+        if record.flagged_by.is_some() {
+            return;
+        }
+        record.flagged_by = Some(user);
+        discordhelpers::build_report_embed(record, &output)
+    };
+    discordhelpers::manual_dispatch(ctx.http.clone(), log, emb).await;
+}
+
+// Whether the reaction is on an output we hold a record of which nobody has flagged yet
+async fn unflagged_output(ctx: &Context, reaction: &Reaction) -> bool {
+    let data = ctx.data.read().await;
+    let mut records = cache_or_return!(&data, OutputRecordCache, false).lock().await;
+    match records.get_mut(&reaction.message_id.0) {
+        Some(record) => record.flagged_by.is_none(),
+        None => false,
+    }
+}
+
+// Posts the results of a minimal mode run, only the user who ran it may ask for them
+async fn expand_output(ctx: &Context, reaction: &Reaction) {
+    let author = {
+        let data = ctx.data.read().await;
+        let mut expandable = cache_or_return!(&data, ExpandCache).lock().await;
+        match expandable.get_mut(&reaction.message_id.0) {
+            Some((author, _, _)) => *author,
+            None => return,
+        }
+    };
+    let user = match reaction.user(ctx).await {
+        Ok(u) => u,
+        Err(_) => return,
+    };
+    if user.id.0 != author {
+        return;
+    }
+
+    let (emb, record) = {
+        let data = ctx.data.read().await;
+        let mut expandable = cache_or_return!(&data, ExpandCache).lock().await;
+        match expandable.remove(&reaction.message_id.0) {
+            Some((_, emb, record)) => (emb, record),
+            None => return,
+        }
+    };
+
+    let output = match discordhelpers::send_embed(reaction.channel_id, &ctx.http, emb).await {
+        Ok(m) => m,
+        Err(_) => return,
+    };
+    discordhelpers::record_output(ctx, &output, record).await;
+
+    // deleting the command cleans up the results, same as it would have without minimal mode
+    let data = ctx.data.read().await;
+    let mut delete_cache = cache_or_return!(&data, MessageDeleteCache).lock().await;
+    delete_cache.insert(reaction.message_id.0, output);
+}
+
+// Asks the author of a run for new input, then runs their code again with it
+async fn prompt_stdin(ctx: &Context, reaction: &Reaction) {
+    let (author, mut parse_result) = {
+        let data = ctx.data.read().await;
+        let reruns = cache_or_return!(&data, RerunCache).lock().await;
+        match reruns.get_mut(&reaction.message_id.0) {
+            Some(entry) => entry.clone(),
+            None => return,
+        }
+    };
+
+    let user = match reaction.user(ctx).await {
+        Ok(u) => u,
+        Err(_) => return,
+    };
+    if user.id.0 != author {
+        return;
+    }
+
+    {
+        let data = ctx.data.read().await;
+        let mut reruns = cache_or_return!(&data, RerunCache).lock().await;
+        reruns.remove(&reaction.message_id.0);
+    }
+
+    let prompt = match reaction
+        .channel_id
+        .say(&ctx.http, format!("<@{}> reply with the input for your program, you have {} seconds", author, STDIN_PROMPT_TIMEOUT))
+        .await
+    {
+        Ok(m) => m,
+        Err(_) => return,
+    };
+
+    let reply = user
+        .await_reply(ctx)
+        .channel_id(reaction.channel_id)
+        .timeout(std::time::Duration::from_secs(STDIN_PROMPT_TIMEOUT))
+        .await;
+    if prompt.delete(&ctx.http).await.is_err() {
+        // missing permissions, just ignore...
+    }
+
+    let reply = match reply {
+        Some(r) => r,
+        None => return,
+    };
+
+    // input may come wrapped in a code block, same as it would for ;run
+    parse_result.stdin = match parser::first_code_block(&reply.content) {
+        Some(stdin) => stdin,
+        None => reply.content.clone(),
+    };
+
+    // the reply runs code just like ;run would, so it faces the same gates
+    {
+        let data = ctx.data.read().await;
+        if !admit(ctx, &data, &reply, "run").await {
+            return;
+        }
+    }
+
+    if let Err(e) = compile::handle_request(ctx, &reply, parse_result, true).await {
+        let emb = discordhelpers::build_fail_embed(&reply.author, &format!("{}", e));
+        if discordhelpers::send_embed(reply.channel_id, &ctx.http, emb).await.is_err() {
+            // missing permissions, just ignore...
+        }
     }
 }
 
 #[hook]
-pub async fn before(ctx: &Context, msg : &Message, _: &str) -> bool {
+pub async fn before(ctx: &Context, msg : &Message, command_name: &str) -> bool {
     let data = ctx.data.read().await;
     {
-        let stats = data.get::<StatsManagerCache>().unwrap().lock().await;
+        let stats = cache_or_return!(&data, StatsManagerCache, false).lock().await;
         if stats.should_track() {
             stats.post_request().await;
         }
     }
 
-    // we'll go with 0 if we couldn't grab guild id
-    let mut guild_id = 0;
-    if let Some(id) = msg.guild_id {
-        guild_id = id.0;
+    if !admit(ctx, &data, msg, command_name).await {
+        return false;
     }
 
-    // check user against our blocklist
-    {
-        let blocklist = data.get::<BlocklistCache>().unwrap().read().await;
-        let author_blocklisted = blocklist.contains(msg.author.id.0);
-        let guild_blocklisted = blocklist.contains(guild_id);
-
-        if author_blocklisted || guild_blocklisted {
-            let emb = discordhelpers::build_fail_embed(&msg.author,
-       "This server or user is blocked from executing commands.
-            This may have happened due to abuse, spam, or other reasons.
-            If you feel that this has been done in error, request an unban in the support server.");
-
-            let mut emb_msg = discordhelpers::embed_message(emb);
-            if msg.channel_id.send_message(&ctx.http, |_| &mut emb_msg).await.is_ok() {
-                if author_blocklisted {
-                    warn!("Blocked user {} [{}]", msg.author.tag(), msg.author.id.0);
-                }
-                else {
-                    warn!("Blocked guild {}", guild_id);
+    if COMPILE_COMMANDS.contains(&command_name) {
+        discordhelpers::acknowledge(ctx, msg).await;
+    }
+    true
+}
+
+// Runs the command past every gate, refusing it if one fails. Anything that runs code on
+// behalf of a user outside of the framework (e.g. prompt_stdin) must come through here as
+// well, interactions go through pass_gates themselves since they're answered differently
+async fn admit(ctx: &Context, data: &TypeMap, msg: &Message, command_name: &str) -> bool {
+    let req = Requester::from(msg);
+    match pass_gates(ctx, data, &req, command_name).await {
+        Some(Ok(())) => true,
+        Some(Err(refusal)) => {
+            refuse(ctx, data, msg, refusal).await;
+            false
+        }
+        None => false,
+    }
+}
+
+/// Runs a request past every gate. Requests that go ahead take their share of the rate
+/// limit & cooldowns, refused ones are left for the caller to answer with refusal_embed.
+/// None if the caches the gates need are missing
+pub async fn pass_gates(ctx: &Context, data: &TypeMap, req: &Requester<'_>, command_name: &str) -> Option<Result<(), Refusal>> {
+    let gates = gather_gates(ctx, data, req, command_name).await?;
+    if let Err(refusal) = gates::check(&gates) {
+        return Some(Err(refusal));
+    }
+
+    // only requests that actually go ahead count towards cooldowns & the guild's limit
+    if !gates.owner && COMPILE_COMMANDS.contains(&command_name) {
+        if let Some(id) = req.guild_id {
+            let limit = limiter::guild_rate_limit() * trusted::rate_limit_multiplier(id.0);
+            if limit > 0 {
+                let acquired = get_cache::<GuildRateLimitCache>(data)?.lock().await.try_acquire(id.0, limit);
+                // another request may have taken the last one since we looked
+                if !acquired {
+                    return Some(Err(Refusal::RateLimited));
                 }
             }
-            return false;
+        }
+        if gates.supporter {
+            if let Some(cache) = get_cache::<SupporterCooldownCache>(data) {
+                cache.lock().await.insert(req.author.id.0, std::time::Instant::now());
+            }
         }
     }
+    Some(Ok(()))
+}
 
-    true
+// Looks up everything gates::check decides on. Owners skip every gate, so there's
+// nothing to look up for them, & discord is only asked about admins when it matters
+async fn gather_gates(ctx: &Context, data: &TypeMap, req: &Requester<'_>, command_name: &str) -> Option<Gates> {
+    let compile = COMPILE_COMMANDS.contains(&command_name);
+    if get_cache::<OwnersCache>(data)?.contains(&req.author.id) {
+        return Some(Gates { owner: true, compile, ..Default::default() });
+    }
+
+    // we'll go with 0 if we couldn't grab guild id
+    let guild_id = req.guild_id.map(|g| g.0).unwrap_or_default();
+    let mut gates = Gates { compile, ..Default::default() };
+
+    // written to, expired blocks are pruned as we come across them
+    {
+        let mut blocklist = get_cache::<BlocklistCache>(data)?.write().await;
+        gates.blocked_user = blocklist.active(req.author.id.0);
+        gates.blocked_guild = blocklist.active(guild_id);
+        gates.blocked_channel = blocklist.contains_channel(req.channel_id.0);
+    }
+
+    // compilation is paused during maintenance windows & while the kill switch is on
+    gates.maintenance = get_cache::<ScheduleCache>(data)?.read().await.active().cloned();
+    gates.kill_switch = get_cache::<KillSwitchCache>(data)?.read().await.clone();
+    if guild_id == 0 {
+        return Some(gates);
+    }
+
+    // supporters skip the nospam bucket (see nospam_check), but still get a reduced cooldown
+    if compile {
+        if let Some(percentage) = discordhelpers::supporter_cooldown(ctx, req).await {
+            let cooldown = std::time::Duration::from_millis(NOSPAM_DELAY * 1000 * percentage as u64 / 100);
+            let mut cooldowns = get_cache::<SupporterCooldownCache>(data)?.lock().await;
+            gates.too_fast = match cooldowns.get_mut(&req.author.id.0) {
+                Some(last) => last.elapsed() < cooldown,
+                None => false,
+            };
+            gates.supporter = true;
+        }
+    }
+
+    let (restricted_to, required, min_age) = {
+        let settings = get_cache::<SettingsCache>(data)?.read().await;
+        let guild = settings.guild(guild_id);
+
+        // admins may confine compilation to specific channels
+        let restricted_to = guild
+            .filter(|g| !g.allowed_channels.is_empty() && !g.allowed_channels.contains(&req.channel_id.0))
+            .and_then(|g| g.allowed_channels.iter().next().copied());
+
+        // admins may also require a role to use commands
+        let required = guild.and_then(|guild| {
+            let mut role = None;
+            if compile {
+                role = guild.required_roles.get("compile");
+            }
+            role.or_else(|| guild.required_roles.get("all")).copied()
+        });
+        (restricted_to, required, settings.min_account_age(guild_id))
+    };
+    gates.restricted_to = restricted_to;
+
+    if let Some(role) = required {
+        let has_role = match req.roles {
+            Some(roles) => roles.contains(&RoleId(role)),
+            None => false,
+        };
+
+        // only bother resolving admin status if we need to
+        if !has_role && !discordhelpers::is_guild_admin(ctx, GuildId(guild_id), req.author.id).await {
+            gates.missing_role = Some(role);
+        }
+    }
+
+    // throwaway accounts are usually only days old, admins may keep them from compiling.
+    // The account's age comes straight from its id, so there's no need to ask discord
+    if let Some(days) = min_age.filter(|_| compile) {
+        let age = Utc::now() - req.author.id.created_at();
+        if age < Duration::days(days as i64)
+            && !trusted::is_trusted(guild_id)
+            && !discordhelpers::is_recent_voter(ctx, req.author.id.0).await
+        {
+            gates.too_young = Some(days);
+        }
+    }
+
+    let limit = limiter::guild_rate_limit() * trusted::rate_limit_multiplier(guild_id);
+    if compile && limit > 0 {
+        gates.rate_limited = !get_cache::<GuildRateLimitCache>(data)?.lock().await.would_allow(guild_id, limit);
+    }
+    Some(gates)
+}
+
+// Lets the user know why their command was refused
+async fn refuse(ctx: &Context, data: &TypeMap, msg: &Message, refusal: Refusal) {
+    let blocked = matches!(refusal, Refusal::BlockedUser(_) | Refusal::BlockedGuild(_) | Refusal::BlockedChannel);
+    let emb = refusal_embed(data, &Requester::from(msg), refusal);
+
+    // without embed permissions the blocked would see nothing and keep trying, a reaction still gets through
+    if discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await.is_err() && blocked {
+        discordhelpers::react_fallback(ctx, msg, BLOCKED_EMOJI).await;
+    }
+}
+
+/// What a refused request is answered with, see pass_gates
+pub fn refusal_embed(data: &TypeMap, req: &Requester<'_>, refusal: Refusal) -> CreateEmbed {
+    let guild_id = req.guild_id.map(|g| g.0).unwrap_or_default();
+    let message = match refusal {
+        Refusal::BlockedUser(entry) => return blocked_embed(data, req, "user", entry),
+        Refusal::BlockedGuild(entry) => return blocked_embed(data, req, "guild", entry),
+        Refusal::BlockedChannel => return blocked_embed(data, req, "channel", None),
+        Refusal::Maintenance(maintenance) => format!(
+            "Compiling is down for maintenance until {} UTC: {}",
+            maintenance.ends().format("%Y-%m-%d %H:%M"),
+            maintenance.reason
+        ),
+        Refusal::KillSwitch(reason) => format!("Compiling is temporarily switched off: {}", reason),
+        Refusal::TooFast => String::from("You are sending requests too fast!"),
+        Refusal::RestrictedChannel(channel) => format!(
+            "Compiling is restricted to specific channels in this server, try <#{}> instead.",
+            channel
+        ),
+        Refusal::MissingRole(role) => format!("You need the <@&{}> role to use this command in this server.", role),
+        Refusal::AccountAge(days) => format!(
+            "Sorry, this server only lets accounts that are at least {} day(s) old compile code.",
+            days
+        ),
+        Refusal::RateLimited => {
+            if let Some(log_throttle) = get_cache::<LogThrottleCache>(data) {
+                let limit = limiter::guild_rate_limit() * trusted::rate_limit_multiplier(guild_id);
+                throttle::warn(log_throttle, "Guild hit its rate limit",
+                    &format!("Guild {} hit its rate limit of {} requests per minute", guild_id, limit));
+            }
+            String::from("This server is sending too many requests, please try again in a minute.")
+        }
+    };
+    discordhelpers::build_fail_embed(req.author, &message)
+}
+
+// `kind` is what's blocked, a user, guild or channel
+fn blocked_embed(data: &TypeMap, req: &Requester<'_>, kind: &'static str, entry: Option<BlockEntry>) -> CreateEmbed {
+    let target = match kind {
+        "user" => "Your account is",
+        "guild" => "This server is",
+        _ => "This channel is",
+    };
+    metrics::record(data, |m| m.blocklist_rejection(kind));
+
+    // blocked users tend to keep trying, one line per minute is plenty
+    if let Some(log_throttle) = get_cache::<LogThrottleCache>(data) {
+        match kind {
+            "user" => throttle::warn(log_throttle, "Blocked user", &format!("Blocked user {} [{}]", req.author.tag(), req.author.id.0)),
+            "guild" => throttle::warn(log_throttle, "Blocked guild", &format!("Blocked guild {}", req.guild_id.map(|g| g.0).unwrap_or_default())),
+            _ => throttle::warn(log_throttle, "Blocked channel", &format!("Blocked channel {}", req.channel_id.0)),
+        }
+    }
+    discordhelpers::build_blocked_embed(req.author, target, entry.as_ref())
+}
+
+// Guilds may pick a prefix of their own, our default prefix & mentions keep working alongside it
+#[hook]
+pub async fn dynamic_prefix(ctx: &Context, msg: &Message) -> Option<String> {
+    let guild_id = msg.guild_id?;
+    let data = ctx.data.read().await;
+    let settings = get_cache::<SettingsCache>(&data)?.read().await;
+    settings.prefix(guild_id.0)
+}
+
+// The nospam bucket only applies to those who aren't owners or one of the guild's supporters
+#[hook]
+pub async fn nospam_check(ctx: &Context, msg: &Message) -> bool {
+    let owner = match get_cache::<OwnersCache>(&*ctx.data.read().await) {
+        Some(owners) => owners.contains(&msg.author.id),
+        None => false,
+    };
+    !owner && discordhelpers::supporter_cooldown(ctx, &Requester::from(msg)).await.is_none()
 }
 
 #[hook]
@@ -226,37 +1022,203 @@ pub async fn after(
     command_name: &str,
     command_result: CommandResult,
 ) {
+    if COMPILE_COMMANDS.contains(&command_name) {
+        discordhelpers::clear_acknowledgement(ctx, msg).await;
+    }
+
     if let Err(e) = command_result {
-        let emb = discordhelpers::build_fail_embed(&msg.author, &format!("{}", e));
-        let mut emb_msg = discordhelpers::embed_message(emb);
-        if msg
-            .channel_id
-            .send_message(&ctx.http, |_| &mut emb_msg)
-            .await
-            .is_err()
         {
+            let data = ctx.data.read().await;
+            if let Some(errors) = get_cache::<ErrorLogCache>(&data) {
+                let context = format!("{} by {} [{}]", command_name, msg.author.tag(), msg.author.id.0);
+                errors.lock().await.push(&context, &format!("{}", e));
+            }
+        }
+
+        // an edited command that now fails shouldn't leave its stale results up, see rerun_edited
+        let emb = discordhelpers::build_fail_embed(&msg.author, &format!("{}", e));
+        if discordhelpers::replace_output(ctx, msg, &emb).await.is_none()
+            && discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await.is_err() {
             // missing permissions, just ignore...
         }
     }
 
     let data = ctx.data.read().await;
-    let stats = data.get::<StatsManagerCache>().unwrap().lock().await;
+    metrics::record(&data, |m| {
+        m.command(command_name);
+        // edited commands are timed from their edit, see rerun_edited
+        if COMPILE_COMMANDS.contains(&command_name) {
+            let sent = msg.edited_timestamp.unwrap_or(msg.timestamp);
+            let elapsed = Utc::now() - sent;
+            m.request_latency(command_name, elapsed.num_milliseconds().max(0) as f64 / 1000.0);
+        }
+    });
+
+    let mut stats = cache_or_return!(&data, StatsManagerCache).lock().await;
+    if let Some(guild) = msg.guild_id {
+        stats.guild_command(guild.0, command_name);
+    }
     if stats.should_track() {
         stats.command_executed(command_name).await;
     }
 }
 
+// Compiles messages with a code block in channels where that's been turned on, as if
+// they were sent with ;run. Commands are left to the framework, and code blocks
+// without a language we know of are just conversation
+async fn auto_compile(ctx: &Context, msg: &Message) {
+    let (prefix, guild_prefix, bot_id) = {
+        let data = ctx.data.read().await;
+        let settings = cache_or_return!(&data, SettingsCache).read().await;
+        if !settings.auto_compile(msg.channel_id.0) {
+            return;
+        }
+        let guild_prefix = msg.guild_id.and_then(|g| settings.prefix(g.0));
+
+        let info = cache_or_return!(&data, ConfigCache).read().await;
+        (info.get("BOT_PREFIX").unwrap().clone(), guild_prefix, info.get("BOT_ID").unwrap().clone())
+    };
+    let content = msg.content.trim_start();
+    if content.starts_with(&prefix) || content.starts_with(&format!("<@{}>", bot_id)) || content.starts_with(&format!("<@!{}>", bot_id)) {
+        return;
+    }
+    if let Some(guild_prefix) = guild_prefix {
+        if content.starts_with(&guild_prefix) {
+            return;
+        }
+    }
+
+    let code_block = msg.content.find("```").unwrap();
+    let default_target = {
+        let data = ctx.data.read().await;
+        let settings = cache_or_return!(&data, SettingsCache).read().await;
+        settings.default_language(msg.guild_id.map(|g| g.0).unwrap_or_default(), msg.channel_id.0)
+    };
+    let default_target = match default_target.or_else(|| parser::code_block_language(&msg.content[code_block..])) {
+        Some(target) => String::from(parser::normalize_language(&target)),
+        None => return,
+    };
+
+    // ```json and the like are someone sharing text, not code to run
+    let known = {
+        let data = ctx.data.read().await;
+        let wbox = cache_or_return!(&data, WandboxCache).read().await;
+        wbox.get_compilers(&default_target).is_some() || compile::assembler_for(&default_target).is_some()
+    };
+    if !known {
+        return;
+    }
+
+    if !before(ctx, msg, "run").await {
+        return;
+    }
+
+    // supporters were already held to their own cooldown in before
+    if discordhelpers::supporter_cooldown(ctx, &Requester::from(msg)).await.is_none() {
+        let data = ctx.data.read().await;
+        let mut cooldowns = cache_or_return!(&data, AutoCompileCooldownCache).lock().await;
+        if let Some(last) = cooldowns.get_mut(&msg.author.id.0) {
+            if last.elapsed() < std::time::Duration::from_secs(NOSPAM_DELAY) {
+                let emb = discordhelpers::build_fail_embed(&msg.author, "You are sending requests too fast!");
+                if discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await.is_err() {
+                    // missing permissions, just ignore...
+                }
+                return;
+            }
+        }
+        cooldowns.insert(msg.author.id.0, std::time::Instant::now());
+    }
+
+    // anything before the code block is conversation, not arguments
+    let input = format!("run\n{}", &msg.content[code_block..]);
+    let result = match parser::get_components(&input, &msg.author, Some(default_target)).await {
+        Ok(parse_result) => compile::handle_request(ctx, msg, parse_result, true).await,
+        Err(e) => Err(CommandError::from(format!("{}", e))),
+    };
+    after(ctx, msg, "run", result).await;
+}
+
+// Suggests the closest real command for typos like ;complie, anything that
+// isn't close to a command (emoticons and the like) is silently ignored
+#[hook]
+pub async fn unrecognised_command(ctx: &Context, msg: &Message, unrecognised_command_name: &str) {
+    {
+        let data = ctx.data.read().await;
+        let blocklist = cache_or_return!(&data, BlocklistCache).read().await;
+        let guild_id = msg.guild_id.map(|g| g.0).unwrap_or(0);
+        if blocklist.contains(msg.author.id.0) || blocklist.contains(guild_id) || blocklist.contains_channel(msg.channel_id.0) {
+            return;
+        }
+    }
+
+    let names = crate::GENERAL_GROUP
+        .options
+        .commands
+        .iter()
+        .flat_map(|c| c.options.names.iter().copied());
+    let suggestion = match fuzzy::closest(unrecognised_command_name, names) {
+        Some(s) => s,
+        None => return,
+    };
+
+    let prefix = discordhelpers::command_prefix(ctx, msg.guild_id).await;
+    let reply = format!(
+        "Unknown command `{}`, did you mean `{}{}`?",
+        unrecognised_command_name, prefix, suggestion
+    );
+    if msg.channel_id.say(&ctx.http, reply).await.is_err() {
+        // missing permissions, just ignore...
+    }
+}
+
 #[hook]
 pub async fn dispatch_error(ctx: &Context, msg: &Message, error: DispatchError) {
     if let DispatchError::Ratelimited(_) = error {
         let emb =
             discordhelpers::build_fail_embed(&msg.author, "You are sending requests too fast!");
-        let mut emb_msg = discordhelpers::embed_message(emb);
-        if msg
-            .channel_id
-            .send_message(&ctx.http, |_| &mut emb_msg)
-            .await
-            .is_err()
-        {}
+        if discordhelpers::send_embed(msg.channel_id, &ctx.http, emb).await.is_err() {}
+    }
+}
+
+// commands expect an avatar to be present, bots without one get discord's default
+fn bot_avatar(user: &CurrentUser) -> String {
+    match user.avatar_url() {
+        Some(url) => url,
+        None => {
+            warn!("Bot has no avatar set, falling back to the default avatar");
+            user.default_avatar_url()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(avatar: Value) -> CurrentUser {
+        serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "username": "compiler",
+            "discriminator": "0001",
+            "avatar": avatar,
+            "bot": true,
+            "email": null,
+            "mfa_enabled": false,
+            "verified": true
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn missing_avatar_falls_back_to_default() {
+        let user = user(Value::Null);
+        assert_eq!(bot_avatar(&user), user.default_avatar_url());
+        assert!(bot_avatar(&user).contains("/embed/avatars/"));
+    }
+
+    #[test]
+    fn avatar_is_used_when_set() {
+        let user = user(Value::from("abcdef"));
+        assert!(bot_avatar(&user).contains("/avatars/1/abcdef"));
     }
 }
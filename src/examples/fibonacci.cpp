@@ -0,0 +1,13 @@
+#include <cstdint>
+#include <iostream>
+
+int main() {
+    std::uint64_t a = 0, b = 1;
+    for (int i = 0; i < 20; i++) {
+        std::cout << a << ' ';
+        std::uint64_t next = a + b;
+        a = b;
+        b = next;
+    }
+    std::cout << '\n';
+}
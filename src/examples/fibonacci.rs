@@ -0,0 +1,7 @@
+fn main() {
+    let fibonacci: Vec<u64> = std::iter::successors(Some((0u64, 1u64)), |&(a, b)| Some((b, a + b)))
+        .map(|(a, _)| a)
+        .take(20)
+        .collect();
+    println!("{:?}", fibonacci);
+}
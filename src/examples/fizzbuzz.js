@@ -0,0 +1,3 @@
+for (let i = 1; i <= 15; i++) {
+  console.log((i % 3 === 0 ? 'Fizz' : '') + (i % 5 === 0 ? 'Buzz' : '') || i);
+}
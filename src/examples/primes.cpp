@@ -0,0 +1,16 @@
+#include <iostream>
+#include <vector>
+
+int main() {
+    const int limit = 100;
+    std::vector<bool> composite(limit + 1, false);
+    for (int i = 2; i * i <= limit; i++)
+        if (!composite[i])
+            for (int j = i * i; j <= limit; j += i)
+                composite[j] = true;
+
+    for (int i = 2; i <= limit; i++)
+        if (!composite[i])
+            std::cout << i << ' ';
+    std::cout << '\n';
+}
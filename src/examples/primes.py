@@ -0,0 +1,8 @@
+limit = 100
+composite = set()
+primes = []
+for i in range(2, limit + 1):
+    if i not in composite:
+        primes.append(i)
+        composite.update(range(i * i, limit + 1, i))
+print(*primes)
@@ -0,0 +1,26 @@
+#include <iostream>
+#include <vector>
+
+void quicksort(std::vector<int>& v, int lo, int hi) {
+    if (lo >= hi)
+        return;
+
+    int pivot = v[(lo + hi) / 2];
+    int i = lo, j = hi;
+    while (i <= j) {
+        while (v[i] < pivot) i++;
+        while (v[j] > pivot) j--;
+        if (i <= j)
+            std::swap(v[i++], v[j--]);
+    }
+    quicksort(v, lo, j);
+    quicksort(v, i, hi);
+}
+
+int main() {
+    std::vector<int> v{9, 4, 7, 1, 8, 2, 6, 3, 5};
+    quicksort(v, 0, v.size() - 1);
+    for (int i : v)
+        std::cout << i << ' ';
+    std::cout << '\n';
+}
@@ -0,0 +1,7 @@
+def quicksort(items):
+    if len(items) <= 1:
+        return items
+    pivot, *rest = items
+    return quicksort([x for x in rest if x < pivot]) + [pivot] + quicksort([x for x in rest if x >= pivot])
+
+print(quicksort([9, 4, 7, 1, 8, 2, 6, 3, 5]))
@@ -0,0 +1,17 @@
+fn quicksort<T: PartialOrd + Copy>(items: &[T]) -> Vec<T> {
+    match items.split_first() {
+        None => Vec::new(),
+        Some((&pivot, rest)) => {
+            let smaller: Vec<T> = rest.iter().copied().filter(|&x| x < pivot).collect();
+            let larger: Vec<T> = rest.iter().copied().filter(|&x| x >= pivot).collect();
+            let mut sorted = quicksort(&smaller);
+            sorted.push(pivot);
+            sorted.extend(quicksort(&larger));
+            sorted
+        }
+    }
+}
+
+fn main() {
+    println!("{:?}", quicksort(&[9, 4, 7, 1, 8, 2, 6, 3, 5]));
+}
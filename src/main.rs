@@ -1,33 +1,39 @@
 mod apis;
+#[macro_use]
 mod cache;
 mod commands;
 mod events;
+mod slashcommands;
 mod stats;
 mod utls;
 
 use serenity::{
     client::bridge::gateway::GatewayIntents,
-    framework::{standard::macros::group, StandardFramework},
+    framework::{standard::macros::group, Framework, StandardFramework},
     http::Http,
 };
 
-use std::{collections::HashSet, env, error::Error};
+use std::{collections::HashSet, env, error::Error, sync::Arc};
 
 use crate::apis::dbl::BotsListAPI;
+use crate::utls::constants::{NOSPAM_DELAY, OPTCOMPARE_DELAY, RACE_DELAY};
+use crate::utls::discordhelpers;
 
 #[macro_use]
 extern crate log;
+#[macro_use]
+extern crate lazy_static;
 extern crate pretty_env_logger;
 
 /** Command Registration **/
 use crate::commands::{
     asm::ASM_COMMAND, botinfo::*, compile::*, compilers::*,
     help::*, languages::*, ping::*, block::*, unblock::*,
-    invite::*
+    invite::*, swap::*, setchannellang::*, recenterrors::*, reloadconfig::*, allowchannel::*, random::*, requirerole::*, guildstats::*, autodelete::*, run::*, docs::*, exportstats::*, settheme::*, shard::*, blockchannel::*, top::*, myflags::*, supporters::*, fmtdiff::*, version::*, setbackend::*, backends::*, leaveguild::*, tips::*, compare::*, snippet::*, recentguilds::*, logos::*, features::*, minimal::*, libs::*, schedule::*, announcements::*, preview::*, setpresence::*, session::*, expiry::*, check::*, usagechart::*, autoflags::*, killswitch::*, accountage::*, whichshard::*, setcompilelog::*, example::*, autocompile::*, perms::*, ackreaction::*, optcompare::*, restartshard::*, race::*, config::*
 };
 
 #[group]
-#[commands(botinfo, compile, languages, compilers, ping, help, asm, block, unblock, invite)]
+#[commands(botinfo, compile, languages, compilers, ping, help, asm, block, unblock, invite, swap, setchannellang, recenterrors, reloadconfig, allowchannel, denychannel, random, requirerole, guildstats, autodelete, run, docs, exportstats, settheme, shard, blockchannel, unblockchannel, top, myflags, supporters, fmtdiff, version, setbackend, backends, leaveguild, tips, compare, snippet, recentguilds, logos, features, minimal, libs, schedule, announcements, preview, setpresence, session, expiry, check, usagechart, autoflags, killswitch, accountage, whichshard, setcompilelog, example, autocompile, perms, ackreaction, optcompare, restartshard, race, config)]
 struct General;
 
 /** Spawn bot **/
@@ -70,31 +76,70 @@ async fn main() -> Result<(), Box<dyn Error>> {
             .join(", ")
     );
 
+    // Resolve our shard count up front so the ready handler doesn't need to trust the ready payload
+    let shard_count = match env::var("SHARD_COUNT").unwrap_or_default().parse::<u64>() {
+        Ok(count) if count > 0 => count,
+        _ => match http.get_bot_gateway().await {
+            Ok(gateway) => gateway.shards,
+            Err(why) => {
+                warn!("Could not access gateway info: {:?}", why);
+                warn!("Falling back to autosharding...");
+                0
+            }
+        },
+    };
+
+    // unusable backend urls aren't fatal, we'll talk to the public instances instead
+    apis::endpoints::validate();
+
+    // an unknown presence type isn't fatal, we'll just fall back to playing
+    if let Ok(kind) = env::var("PRESENCE_TYPE") {
+        if !kind.is_empty() && discordhelpers::parse_presence_type(&kind).is_none() {
+            warn!("Unknown PRESENCE_TYPE '{}', expected playing, watching, listening or competing. Falling back to playing", kind);
+        }
+    }
+
     let prefix = env::var("BOT_PREFIX")?;
     let framework = StandardFramework::new()
-        .configure(|c| c.owners(owners).prefix(&prefix))
+        .configure(|c| c.owners(owners.clone()).prefix(&prefix).dynamic_prefix(events::dynamic_prefix).on_mention(Some(bot_id)))
         .before(events::before)
         .after(events::after)
+        .unrecognised_command(events::unrecognised_command)
         .group(&GENERAL_GROUP)
-        .bucket("nospam", |b| b.delay(3).time_span(10).limit(3))
+        .bucket("nospam", |b| b.delay(NOSPAM_DELAY).time_span(10).limit(3).check(events::nospam_check))
+        .await
+        .bucket("optcompare", |b| b.delay(OPTCOMPARE_DELAY).check(events::nospam_check))
+        .await
+        .bucket("race", |b| b.delay(RACE_DELAY).check(events::nospam_check))
         .await
         .on_dispatch_error(events::dispatch_error);
+
+    // shared with the cache so edited commands can be dispatched again, see events::rerun_edited
+    let framework: Arc<Box<dyn Framework + Send + Sync>> = Arc::new(Box::new(framework));
     let mut client = serenity::Client::builder(token)
-        .framework(framework)
+        .framework_arc(framework.clone())
         .event_handler(events::Handler)
         .add_intent(GatewayIntents::GUILDS)
         .add_intent(GatewayIntents::GUILD_MESSAGES)
         .add_intent(GatewayIntents::GUILD_MESSAGE_REACTIONS)
         .await?;
 
-    cache::fill(client.data.clone(), &prefix, &bot_id, client.shard_manager.clone()).await?;
+    cache::fill(client.data.clone(), &prefix, &bot_id, owners, shard_count, client.shard_manager.clone(), framework).await?;
+
+    stats::metrics::spawn(client.data.clone());
 
     let dbl = BotsListAPI::new();
     if dbl.should_spawn() {
         dbl.spawn(client.cache_and_http.http.clone(), client.data.clone());
     }
 
-    if let Err(why) = client.start_autosharded().await {
+    let result = if shard_count > 0 {
+        client.start_shards(shard_count).await
+    } else {
+        client.start_autosharded().await
+    };
+
+    if let Err(why) = result {
         error!("Client error: {:?}", why);
     }
 
@@ -0,0 +1,332 @@
+use std::time::Instant;
+
+use serde_json::Value;
+use serenity::{
+    builder::CreateEmbed,
+    client::Context,
+    framework::standard::CommandError,
+    model::id::{ChannelId, GuildId},
+    model::user::User,
+};
+
+use crate::apis::interactions::{self, Interaction, Reply};
+use crate::cache::{get_cache, ConfigCache, InteractionCooldownCache, RerunCache, StatsManagerCache};
+use crate::commands::{asm, botinfo, compile};
+use crate::events;
+use crate::stats::metrics;
+use crate::utls::constants::{COLOR_OKAY, INTERACTION_COOLDOWN};
+use crate::utls::{discordhelpers, postprocess, syntax};
+use crate::utls::gates::Requester;
+use crate::utls::parser::{self, FlagSource, ParserResult};
+
+/// Entry point for slash command & button interactions, these share their backend
+/// logic with the prefix commands but reply through the interaction instead
+pub async fn handle_interaction(ctx: &Context, raw: Value) {
+    let interaction: Interaction = match serde_json::from_value(raw) {
+        Ok(i) => i,
+        Err(e) => return warn!("Unable to parse interaction: {}", e),
+    };
+
+    let author = match interaction.author() {
+        Some(a) => a.clone(),
+        None => return,
+    };
+
+    let data = ctx.data.read().await;
+    let application_id = {
+        let info = cache_or_return!(&data, ConfigCache).read().await;
+        info.get("BOT_ID").unwrap().parse::<u64>().unwrap()
+    };
+
+    // counted like any prefix command request, see events::before
+    {
+        let stats = cache_or_return!(&data, StatsManagerCache).lock().await;
+        if stats.should_track() {
+            stats.post_request().await;
+        }
+    }
+
+    // prefix commands get this from the 'nospam' bucket, interactions bypass the framework
+    {
+        let mut cooldowns = cache_or_return!(&data, InteractionCooldownCache).lock().await;
+        if let Some(last) = cooldowns.get_mut(&author.id.0) {
+            if last.elapsed().as_secs() < INTERACTION_COOLDOWN {
+                let emb = discordhelpers::build_fail_embed(&author, "You are sending requests too fast!");
+                interactions::respond(&interaction, emb, Reply::Refusal.ephemeral()).await;
+                return;
+            }
+        }
+        cooldowns.insert(author.id.0, Instant::now());
+    }
+
+    // the same gates as prefix commands, see events::admit. Button clicks are gated
+    // like the command they belong to, running one again runs code all the same
+    {
+        let gated_as = if !interaction.is_component() {
+            interaction.command_name()
+        } else if let Some(rerun) = interaction.custom_id().strip_prefix("rerun:") {
+            rerun.split(':').next().unwrap_or_default()
+        } else {
+            "delete"
+        };
+        let req = Requester {
+            id: interaction.id.parse::<u64>().unwrap_or_default(),
+            author: &author,
+            guild_id: guild_id(&interaction),
+            channel_id: ChannelId(interaction.channel_id()),
+            roles: interaction.member.as_ref().map(|m| m.roles.as_slice()),
+        };
+        match events::pass_gates(ctx, &data, &req, gated_as).await {
+            Some(Ok(())) => (),
+            Some(Err(refusal)) => {
+                let emb = events::refusal_embed(&data, &req, refusal);
+                interactions::respond(&interaction, emb, Reply::Refusal.ephemeral()).await;
+                return;
+            }
+            None => return,
+        }
+    }
+
+    // button clicks aren't commands of their own, their results count towards the original
+    if interaction.is_component() {
+        handle_component(ctx, &interaction, &author, application_id).await;
+        debug!("Interaction executed");
+        return;
+    }
+
+    let started = Instant::now();
+    let command_name = String::from(interaction.command_name());
+    match command_name.as_str() {
+        "ping" => {
+            let mut emb = CreateEmbed::default();
+            emb.color(COLOR_OKAY);
+            emb.description("Pong!");
+            interactions::respond(&interaction, emb, Reply::Result.ephemeral()).await;
+        }
+        "botinfo" => {
+            let emb = botinfo::build_botinfo_embed(ctx, guild_id(&interaction)).await;
+            interactions::respond(&interaction, emb, Reply::Result.ephemeral()).await;
+        }
+        "run" | "compile" | "asm" => {
+            // backend requests can easily exceed the 3 second response window
+            interactions::defer(&interaction).await;
+
+            let key = interaction.id.parse::<u64>().unwrap_or_default();
+            let result = match command_name.as_str() {
+                "asm" => assembly(ctx, &interaction)
+                    .await
+                    .map(|r| (discordhelpers::build_asm_embed(&author, &r), None)),
+                _ => match get_components(&interaction) {
+                    Ok(parse_result) => compilation(ctx, &interaction, &author, parse_result.clone(), command_name == "run")
+                        .await
+                        .map(|emb| (emb, Some(parse_result))),
+                    Err(e) => Err(e),
+                },
+            };
+
+            match result {
+                Ok((mut emb, rerun)) => {
+                    let theme = discordhelpers::theme_color(ctx, guild_id(&interaction)).await;
+                    discordhelpers::apply_theme(&mut emb, theme);
+
+                    // compilations can be ran again from their buttons, see handle_component
+                    if let Some(parse_result) = rerun {
+                        let data = ctx.data.read().await;
+                        // results still go out without it, there's just no running them again
+                        if let Some(reruns) = get_cache::<RerunCache>(&data) {
+                            reruns.lock().await.insert(key, (author.id.0, parse_result));
+                        }
+                    }
+                    let rerunnable = command_name != "asm";
+                    let buttons = result_buttons(&command_name, key, author.id.0, rerunnable);
+                    interactions::complete(application_id, &interaction, emb, Reply::Result.ephemeral(), buttons).await
+                }
+                Err(e) => {
+                    let emb = discordhelpers::build_fail_embed(&author, &format!("{}", e));
+                    interactions::complete(application_id, &interaction, emb, Reply::Failure.ephemeral(), Vec::new()).await;
+                }
+            }
+        }
+        _ => return,
+    }
+
+    metrics::record(&data, |m| {
+        m.command(&command_name);
+        if command_name != "ping" && command_name != "botinfo" {
+            m.request_latency(&command_name, started.elapsed().as_secs_f64());
+        }
+    });
+
+    let mut stats = cache_or_return!(&data, StatsManagerCache).lock().await;
+    if interaction.guild_id.is_some() {
+        stats.guild_command(interaction.guild_id(), &command_name);
+    }
+    if stats.should_track() {
+        stats.command_executed(&command_name).await;
+    }
+    debug!("Interaction executed");
+}
+
+fn guild_id(interaction: &Interaction) -> Option<GuildId> {
+    interaction.guild_id.as_ref().map(|_| GuildId(interaction.guild_id()))
+}
+
+// A "Run again" button for compilations & a "Delete" button only their author may use.
+// Custom ids are rerun:<command>:<key into RerunCache> and delete:<author>
+fn result_buttons(command_name: &str, key: u64, author: u64, rerunnable: bool) -> Vec<Value> {
+    let mut buttons = Vec::new();
+    if rerunnable {
+        buttons.push(interactions::button(&format!("rerun:{}:{}", command_name, key), "Run again", false));
+    }
+    buttons.push(interactions::button(&format!("delete:{}", author), "Delete", true));
+    vec![interactions::action_row(buttons)]
+}
+
+async fn handle_component(ctx: &Context, interaction: &Interaction, author: &User, application_id: u64) {
+    let parts: Vec<&str> = interaction.custom_id().split(':').collect();
+    match parts.as_slice() {
+        ["delete", owner] => {
+            if *owner != author.id.0.to_string() {
+                let emb = discordhelpers::build_fail_embed(author, "Only the person who ran this can delete it");
+                interactions::respond(interaction, emb, Reply::Refusal.ephemeral()).await;
+                return;
+            }
+            interactions::defer_update(interaction).await;
+            interactions::delete_response(application_id, interaction).await;
+        }
+        ["rerun", command_name, key] => {
+            let key = key.parse::<u64>().unwrap_or_default();
+            let request = {
+                let data = ctx.data.read().await;
+                let mut reruns = cache_or_return!(&data, RerunCache).lock().await;
+                reruns.get_mut(&key).cloned()
+            };
+            let parse_result = match request {
+                Some((owner, _)) if owner != author.id.0 => {
+                    let emb = discordhelpers::build_fail_embed(author, "Only the person who ran this can run it again");
+                    interactions::respond(interaction, emb, Reply::Refusal.ephemeral()).await;
+                    return;
+                }
+                Some((_, parse_result)) => parse_result,
+                None => {
+                    let emb = discordhelpers::build_fail_embed(author, "This request is too old to run again, please use the command instead");
+                    interactions::respond(interaction, emb, Reply::Refusal.ephemeral()).await;
+                    return;
+                }
+            };
+
+            interactions::defer_update(interaction).await;
+            match compilation(ctx, interaction, author, parse_result, *command_name == "run").await {
+                Ok(mut emb) => {
+                    let theme = discordhelpers::theme_color(ctx, guild_id(interaction)).await;
+                    discordhelpers::apply_theme(&mut emb, theme);
+                    let buttons = result_buttons(command_name, key, author.id.0, true);
+                    interactions::edit_response(application_id, interaction, emb, buttons).await;
+                }
+                Err(e) => {
+                    let emb = discordhelpers::build_fail_embed(author, &format!("{}", e));
+                    interactions::followup(application_id, interaction, emb, Reply::Failure.ephemeral()).await;
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Shared by /run & /compile, just like ;run & ;compile only the former shows what the program did
+async fn compilation(ctx: &Context, interaction: &Interaction, author: &User, mut parse_result: ParserResult, execute_requested: bool) -> Result<CreateEmbed, CommandError> {
+    let (language, (mut result, truncated)) = match compile::assembler_for(&parse_result.target) {
+        Some(assembler) => {
+            if !compile::execution_allowed(ctx, "assembly").await {
+                return Err(CommandError::from("Running assembly is disabled"));
+            }
+            let dispatched = compile::dispatch_assembly(ctx, &parse_result, assembler).await?;
+            (String::from("assembly"), dispatched)
+        }
+        None => {
+            let builder = compile::build_request(ctx, &mut parse_result, author.id.0).await?;
+            // the backend mustn't get to run languages that may only be compiled,
+            // & /compile stops before there's a program wherever the toolchain can
+            if !compile::execution_allowed(ctx, &builder.lang).await {
+                parse_result.options.push(String::from(compile::compile_only_flag(&builder.lang)?));
+            } else if !execute_requested {
+                if let Some(flag) = syntax::compile_only_flag(&builder.lang) {
+                    parse_result.options.push(String::from(flag));
+                }
+            }
+            let dispatched = compile::dispatch(ctx, &builder, &parse_result).await?;
+            (builder.lang, dispatched)
+        }
+    };
+    postprocess::apply(&language, &mut result);
+
+    let allowed = compile::execution_allowed(ctx, &language).await;
+    let execute = execute_requested && allowed;
+    let success = if execute { result.succeeded() } else { result.compiled() };
+    let alert = {
+        let data = ctx.data.read().await;
+        let mut stats = data.get::<StatsManagerCache>().unwrap().lock().await;
+        let alert = match interaction.guild_id {
+            Some(_) => stats.guild_compilation(interaction.guild_id(), &language, !success),
+            None => None,
+        };
+        if stats.should_track() {
+            stats.compilation(&language, !success).await;
+        }
+        metrics::record(&data, |m| m.compilation(&language, !success));
+        alert
+    };
+    if let Some(alert) = alert {
+        discordhelpers::report_failure_rate(ctx, interaction.guild_id(), &alert).await;
+    }
+
+    let mut emb = discordhelpers::build_compilation_embed(author, &result, execute, parse_result.flags_source);
+    if truncated {
+        discordhelpers::add_truncation_note(&mut emb, &result);
+    }
+    if execute_requested && !allowed {
+        discordhelpers::add_execution_disabled_note(&mut emb, &language);
+    }
+    if discordhelpers::logos_enabled(ctx, guild_id(interaction)).await {
+        discordhelpers::add_language_logo(&mut emb, &language);
+    }
+    Ok(emb)
+}
+
+async fn assembly(ctx: &Context, interaction: &Interaction) -> Result<godbolt::CompilationResult, CommandError> {
+    let parse_result = get_components(interaction)?;
+    let (response, _) = asm::dispatch_request(ctx, &parse_result, false).await?;
+    Ok(response)
+}
+
+// The slash command equivalent of parser::get_components, our options
+// arrive already separated so there's no need to parse anything
+fn get_components(interaction: &Interaction) -> Result<ParserResult, CommandError> {
+    let target = interaction.option("language").unwrap_or_default().trim().to_lowercase();
+    if target.is_empty() {
+        return Err(CommandError::from("You must provide a valid language or compiler!"));
+    }
+    let target = String::from(parser::normalize_language(&target));
+
+    let code = interaction.option("code").unwrap_or_default();
+    if code.trim().is_empty() {
+        return Err(CommandError::from("You must provide some code to compile"));
+    }
+
+    let options: Vec<String> = interaction
+        .option("options")
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(String::from)
+        .collect();
+    let flags_source = if options.is_empty() { FlagSource::None } else { FlagSource::Inline };
+
+    Ok(ParserResult {
+        stdin: interaction.option("stdin").unwrap_or_default(),
+        target,
+        code,
+        options,
+        flags_source,
+        ..Default::default()
+    })
+}
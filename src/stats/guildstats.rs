@@ -0,0 +1,192 @@
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use serde::*;
+
+use crate::utls::constants::{
+    DEFAULT_FAILURE_RATE_MIN_SAMPLES, DEFAULT_FAILURE_RATE_THRESHOLD, DEFAULT_FAILURE_RATE_WINDOW,
+    GUILD_STATS_DAYS,
+};
+
+/// Compilations made on a single (UTC) day
+#[derive(Serialize, Deserialize)]
+pub struct DailyUsage {
+    pub date: String,
+    pub compilations: u64,
+    pub languages: HashMap<String, u64>,
+}
+
+// Compile outcomes since the window started, the whole thing starts over
+// once FAILURE_RATE_WINDOW has passed
+#[derive(Default)]
+struct FailureWindow {
+    started: Option<Instant>,
+    compilations: u32,
+    failures: u32,
+    flagged: bool,
+}
+
+/// A guild whose compilations have been failing far more often than they should
+pub struct FailureRateAlert {
+    pub compilations: u32,
+    pub failures: u32,
+    pub window: Duration,
+}
+
+impl FailureRateAlert {
+    pub fn percentage(&self) -> u32 {
+        self.failures * 100 / self.compilations.max(1)
+    }
+}
+
+/// Usage counters for a single guild, StatsManager flushes these to guildstats.json
+/// every so often so they survive restarts. The failure window is never persisted.
+///
+/// Retention is bounded so memory stays predictable: daily buckets are kept for
+/// GUILD_STATS_DAYS days from their date, and StatsManager only keeps the
+/// GUILD_STATS_SIZE most recently active guilds around.
+#[derive(Default, Serialize, Deserialize)]
+pub struct GuildStats {
+    #[serde(default)]
+    pub compilations: u64,
+    #[serde(default)]
+    commands: HashMap<String, u64>,
+    #[serde(default)]
+    languages: HashMap<String, u64>,
+    #[serde(default)]
+    daily: VecDeque<DailyUsage>,
+    #[serde(skip)]
+    failure_window: FailureWindow,
+}
+
+impl GuildStats {
+    pub fn command_executed(&mut self, command: &str) {
+        *self.commands.entry(String::from(command)).or_insert(0) += 1;
+    }
+
+    /// Records a compilation, returning an alert the first time the guild's failure
+    /// rate crosses FAILURE_RATE_THRESHOLD within the current window. The minimum
+    /// number of compilations needed to judge the rate is scaled by `sample_multiplier`
+    pub fn compilation(&mut self, language: &str, fail: bool, sample_multiplier: u32) -> Option<FailureRateAlert> {
+        self.compilations += 1;
+        *self.languages.entry(String::from(language)).or_insert(0) += 1;
+
+        self.prune_daily();
+        let today = Utc::now().format("%Y-%m-%d").to_string();
+        if self.daily.back().map(|d| d.date != today).unwrap_or(true) {
+            self.daily.push_back(DailyUsage {
+                date: today,
+                compilations: 0,
+                languages: HashMap::new(),
+            });
+        }
+
+        let bucket = self.daily.back_mut().unwrap();
+        bucket.compilations += 1;
+        *bucket.languages.entry(String::from(language)).or_insert(0) += 1;
+
+        self.track_failure_rate(fail, sample_multiplier)
+    }
+
+    /// Drops the daily buckets older than GUILD_STATS_DAYS. Quiet days have no bucket,
+    /// so this goes by their dates rather than how many there are
+    pub fn prune_daily(&mut self) {
+        let oldest = (Utc::now() - chrono::Duration::days(GUILD_STATS_DAYS as i64 - 1))
+            .format("%Y-%m-%d")
+            .to_string();
+        // the dates are zero padded, so they sort the same as text
+        self.daily.retain(|d| d.date >= oldest);
+    }
+
+    // abuse (fork bombs, deliberate timeouts) or a broken setup tends to show
+    // up as a guild whose compilations almost never succeed
+    fn track_failure_rate(&mut self, fail: bool, sample_multiplier: u32) -> Option<FailureRateAlert> {
+        let window = failure_rate_window();
+        let current = &mut self.failure_window;
+        if current.started.map(|s| s.elapsed() >= window).unwrap_or(true) {
+            *current = FailureWindow {
+                started: Some(Instant::now()),
+                ..Default::default()
+            };
+        }
+
+        current.compilations += 1;
+        if fail {
+            current.failures += 1;
+        }
+
+        if current.flagged || current.compilations < failure_rate_min_samples() * sample_multiplier {
+            return None;
+        }
+
+        if current.failures * 100 < current.compilations * failure_rate_threshold() {
+            return None;
+        }
+
+        current.flagged = true;
+        Some(FailureRateAlert {
+            compilations: current.compilations,
+            failures: current.failures,
+            window,
+        })
+    }
+
+    pub fn top_commands(&self, amount: usize) -> Vec<(&str, u64)> {
+        top(&self.commands, amount)
+    }
+
+    pub fn top_languages(&self, amount: usize) -> Vec<(&str, u64)> {
+        top(&self.languages, amount)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// One row per language per day, oldest first
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("date,language,compilations\n");
+        for day in &self.daily {
+            let mut languages: Vec<(&String, &u64)> = day.languages.iter().collect();
+            languages.sort();
+            for (language, count) in languages {
+                csv.push_str(&format!("{},{},{}\n", day.date, language, count));
+            }
+        }
+        csv
+    }
+}
+
+fn failure_rate_threshold() -> u32 {
+    env::var("FAILURE_RATE_THRESHOLD")
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .filter(|v| *v > 0 && *v <= 100)
+        .unwrap_or(DEFAULT_FAILURE_RATE_THRESHOLD)
+}
+
+fn failure_rate_min_samples() -> u32 {
+    env::var("FAILURE_RATE_MIN_SAMPLES")
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_FAILURE_RATE_MIN_SAMPLES)
+}
+
+fn failure_rate_window() -> Duration {
+    let minutes = env::var("FAILURE_RATE_WINDOW")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_FAILURE_RATE_WINDOW);
+    Duration::from_secs(minutes * 60)
+}
+
+fn top(map: &HashMap<String, u64>, amount: usize) -> Vec<(&str, u64)> {
+    let mut entries: Vec<(&str, u64)> = map.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    entries.truncate(amount);
+    entries
+}
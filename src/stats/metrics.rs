@@ -0,0 +1,180 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::RwLock;
+
+use serenity::prelude::TypeMap;
+use warp::{http::header::CONTENT_TYPE, Filter};
+
+use crate::cache::MetricsCache;
+
+// Upper bounds, in seconds, of the request latency histogram buckets
+static LATENCY_BUCKETS: [f64; 9] = [0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0];
+
+#[derive(Default)]
+struct Histogram {
+    /// Observations per bucket in LATENCY_BUCKETS, not cumulative
+    buckets: [u64; 9],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        if let Some(i) = LATENCY_BUCKETS.iter().position(|b| seconds <= *b) {
+            self.buckets[i] += 1;
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+/// Counters kept for our Prometheus endpoint. Unlike the StatsManager these are
+/// recorded whether or not a stats api is configured, and are lost on restart.
+/// BTreeMaps keep the exported series in a stable order
+#[derive(Default)]
+pub struct Metrics {
+    shard_guilds: BTreeMap<u64, u64>,
+    commands: BTreeMap<String, u64>,
+    /// Language -> (successes, failures)
+    compilations: BTreeMap<String, (u64, u64)>,
+    latency: BTreeMap<String, Histogram>,
+    blocklist_rejections: HashMap<&'static str, u64>,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        Metrics::default()
+    }
+
+    pub fn shard_ready(&mut self, shard_id: u64, guilds: u64) {
+        self.shard_guilds.insert(shard_id, guilds);
+    }
+
+    pub fn guild_joined(&mut self, shard_id: u64) {
+        *self.shard_guilds.entry(shard_id).or_default() += 1;
+    }
+
+    pub fn guild_left(&mut self, shard_id: u64) {
+        let guilds = self.shard_guilds.entry(shard_id).or_default();
+        *guilds = guilds.saturating_sub(1);
+    }
+
+    pub fn command(&mut self, command: &str) {
+        *self.commands.entry(String::from(command)).or_default() += 1;
+    }
+
+    pub fn compilation(&mut self, language: &str, fail: bool) {
+        let entry = self.compilations.entry(language.to_lowercase()).or_default();
+        if fail {
+            entry.1 += 1;
+        } else {
+            entry.0 += 1;
+        }
+    }
+
+    /// How long a compile command took from being sent to being answered
+    pub fn request_latency(&mut self, command: &str, seconds: f64) {
+        self.latency.entry(String::from(command)).or_default().observe(seconds);
+    }
+
+    /// `target` is what was blocked, a user, guild or channel
+    pub fn blocklist_rejection(&mut self, target: &'static str) {
+        *self.blocklist_rejections.entry(target).or_default() += 1;
+    }
+
+    /// Everything in Prometheus' text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        header(&mut out, "compiler_bot_guilds", "gauge", "Guilds held by each shard");
+        for (shard, guilds) in &self.shard_guilds {
+            let _ = writeln!(out, "compiler_bot_guilds{{shard=\"{}\"}} {}", shard, guilds);
+        }
+
+        header(&mut out, "compiler_bot_commands_total", "counter", "Commands executed, by name");
+        for (command, count) in &self.commands {
+            let _ = writeln!(out, "compiler_bot_commands_total{{command=\"{}\"}} {}", escape(command), count);
+        }
+
+        header(&mut out, "compiler_bot_compilations_total", "counter", "Compilations, by language & result");
+        for (language, (successes, failures)) in &self.compilations {
+            let language = escape(language);
+            let _ = writeln!(out, "compiler_bot_compilations_total{{language=\"{}\",result=\"success\"}} {}", language, successes);
+            let _ = writeln!(out, "compiler_bot_compilations_total{{language=\"{}\",result=\"failure\"}} {}", language, failures);
+        }
+
+        header(&mut out, "compiler_bot_request_duration_seconds", "histogram", "Time taken to answer compile commands");
+        for (command, histogram) in &self.latency {
+            let command = escape(command);
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets.iter()) {
+                cumulative += count;
+                let _ = writeln!(out, "compiler_bot_request_duration_seconds_bucket{{command=\"{}\",le=\"{}\"}} {}", command, bound, cumulative);
+            }
+            let _ = writeln!(out, "compiler_bot_request_duration_seconds_bucket{{command=\"{}\",le=\"+Inf\"}} {}", command, histogram.count);
+            let _ = writeln!(out, "compiler_bot_request_duration_seconds_sum{{command=\"{}\"}} {}", command, histogram.sum);
+            let _ = writeln!(out, "compiler_bot_request_duration_seconds_count{{command=\"{}\"}} {}", command, histogram.count);
+        }
+
+        header(&mut out, "compiler_bot_blocklist_rejections_total", "counter", "Commands refused because of the blocklist");
+        for target in &["user", "guild", "channel"] {
+            let count = self.blocklist_rejections.get(target).copied().unwrap_or_default();
+            let _ = writeln!(out, "compiler_bot_blocklist_rejections_total{{target=\"{}\"}} {}", target, count);
+        }
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+// label values are user influenced (e.g. languages), so quotes & the like must not break the format
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Records into the shared metrics, if they've been set up
+pub fn record<F: FnOnce(&mut Metrics)>(data: &TypeMap, f: F) {
+    if let Some(metrics) = data.get::<MetricsCache>() {
+        f(&mut metrics.lock().unwrap());
+    }
+}
+
+/// Port our metrics are served on, zero (the default) leaves the endpoint off
+pub fn metrics_port() -> u16 {
+    env::var("METRICS_PORT")
+        .ok()
+        .and_then(|p| p.parse::<u16>().ok())
+        .unwrap_or_default()
+}
+
+/// Serves GET /metrics for Prometheus to scrape, if METRICS_PORT is set
+pub fn spawn(data: Arc<RwLock<TypeMap>>) {
+    let port = metrics_port();
+    if port == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let metrics: Arc<Mutex<Metrics>> = match data.read().await.get::<MetricsCache>() {
+            Some(m) => m.clone(),
+            None => return warn!("Metrics cache is missing, not serving metrics"),
+        };
+
+        let route = warp::get()
+            .and(warp::path!("metrics"))
+            .map(move || {
+                let body = metrics.lock().unwrap().render();
+                warp::reply::with_header(body, CONTENT_TYPE, "text/plain; version=0.0.4")
+            });
+
+        info!("Serving metrics on port {}", port);
+        warp::serve(route).run(([0, 0, 0, 0], port)).await;
+    });
+}
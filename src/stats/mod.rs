@@ -1,2 +1,4 @@
+pub mod guildstats;
+pub mod metrics;
 pub mod statsmanager;
 pub mod structures;
@@ -1,8 +1,32 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 use std::env;
 
+use lru_cache::LruCache;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::stats::guildstats::{FailureRateAlert, GuildStats};
 use crate::stats::structures::*;
+use crate::utls::constants::{DEFAULT_GUILD_STATS_FLUSH_INTERVAL, DEFAULT_SHARD_GUILD_WARNING, GUILD_STATS_SIZE};
+use crate::utls::monitor::HealthMonitor;
+use crate::utls::persistence::{self, Migration};
+use crate::utls::throttle::{self, LogThrottle};
+use crate::utls::trusted;
+
+// Upgrades older guildstats.json formats, see persistence::Migration
+static GUILD_STATS_MIGRATIONS: [Migration; 1] = [wrap_guild_list];
+
+// version 1 was the bare list of guilds, which had nowhere to put a version
+fn wrap_guild_list(value: Value) -> Value {
+    json!({ "guilds": value })
+}
+
+#[derive(Deserialize)]
+struct SavedGuildStats {
+    guilds: Vec<(u64, GuildStats)>,
+}
 
 pub struct StatsManager {
     client: Arc<reqwest::Client>,
@@ -10,23 +34,33 @@ pub struct StatsManager {
     pass: String,
     servers: u64,
     shards: u64,
-    boot_count: Vec<u64>,
+    /// (shard id, guilds) as reported by each shard's ready event
+    boot_count: Vec<(u64, u64)>,
+    guilds: LruCache<u64, GuildStats>,
+    /// Guild counters changed since they were last flushed to disk
+    guilds_dirty: bool,
+    monitor: Arc<Mutex<HealthMonitor>>,
+    throttle: Arc<Mutex<LogThrottle>>,
 }
 
 impl StatsManager {
-    pub fn new() -> StatsManager {
+    pub fn new(monitor: Arc<Mutex<HealthMonitor>>, throttle: Arc<Mutex<LogThrottle>>) -> StatsManager {
         StatsManager {
             client: Arc::new(reqwest::Client::new()),
             url: env::var("STATS_API_LINK").unwrap_or_default(),
             pass: env::var("STATS_API_KEY").unwrap_or_default(),
             servers: 0,
             shards: 0,
-            boot_count: Vec::new()
+            boot_count: Vec::new(),
+            guilds: load_guild_stats(),
+            guilds_dirty: false,
+            monitor,
+            throttle,
         }
     }
 
     pub fn should_track(&self) -> bool {
-        !self.url.is_empty() && !self.pass.is_empty()
+        dry_run() || (!self.url.is_empty() && !self.pass.is_empty())
     }
 
     pub async fn compilation(&self, language: &str, fail: bool) {
@@ -62,6 +96,85 @@ impl StatsManager {
         self.send_request::<LegacyRequest>(&mut legacy).await;
     }
 
+    /// The guild counters as JSON, None if nothing changed since the last call.
+    /// Written out by the caller so the lock isn't held while we hit the disk
+    pub fn take_guild_stats_snapshot(&mut self) -> Option<String> {
+        if !self.guilds_dirty {
+            return None;
+        }
+
+        // least recently active first, so loading them back in keeps the same order
+        let guilds: Vec<(&u64, &GuildStats)> = self.guilds.iter().collect();
+        match persistence::versioned_json(&json!({ "guilds": guilds }), &GUILD_STATS_MIGRATIONS) {
+            Ok(json) => {
+                self.guilds_dirty = false;
+                Some(json)
+            }
+            Err(e) => {
+                error!("Unable to serialize guild stats: {}", e);
+                None
+            }
+        }
+    }
+
+    pub fn guild_command(&mut self, guild_id: u64, command: &str) {
+        self.guild_entry(guild_id).command_executed(command);
+    }
+
+    pub fn guild_compilation(&mut self, guild_id: u64, language: &str, fail: bool) -> Option<FailureRateAlert> {
+        self.guild_entry(guild_id).compilation(language, fail, trusted::sample_multiplier(guild_id))
+    }
+
+    /// The guild's stats, without days that have aged out since it last compiled
+    pub fn guild_stats(&mut self, guild_id: u64) -> Option<&GuildStats> {
+        self.guilds.get_mut(&guild_id).map(|s| {
+            s.prune_daily();
+            &*s
+        })
+    }
+
+    /// Guilds with the most compilations, as (guild id, compilations)
+    pub fn top_guilds(&self, amount: usize) -> Vec<(u64, u64)> {
+        let mut guilds: Vec<(u64, u64)> = self
+            .guilds
+            .iter()
+            .filter(|(_, stats)| stats.compilations > 0)
+            .map(|(id, stats)| (*id, stats.compilations))
+            .collect();
+        guilds.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        guilds.truncate(amount);
+        guilds
+    }
+
+    /// Most used commands across every guild we hold counters for, as (command, uses)
+    pub fn command_ranking(&self, amount: usize) -> Vec<(String, u64)> {
+        let mut totals: HashMap<String, u64> = HashMap::new();
+        for (_, stats) in self.guilds.iter() {
+            for (command, uses) in stats.top_commands(usize::MAX) {
+                *totals.entry(String::from(command)).or_insert(0) += uses;
+            }
+        }
+
+        let mut ranking: Vec<(String, u64)> = totals.into_iter().collect();
+        ranking.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranking.truncate(amount);
+        ranking
+    }
+
+    /// Amount of guilds we're currently holding usage counters for
+    pub fn tracked_guilds(&self) -> usize {
+        self.guilds.len()
+    }
+
+    // least recently active guilds are evicted once we hit GUILD_STATS_SIZE
+    fn guild_entry(&mut self, guild_id: u64) -> &mut GuildStats {
+        self.guilds_dirty = true;
+        if !self.guilds.contains_key(&guild_id) {
+            self.guilds.insert(guild_id, GuildStats::default());
+        }
+        self.guilds.get_mut(&guild_id).unwrap()
+    }
+
     pub fn server_count(&self) -> u64 {
         self.servers
     }
@@ -70,20 +183,94 @@ impl StatsManager {
         self.shards
     }
 
-    pub fn add_shard(& mut self, server_count : u64) {
+    pub fn add_shard(& mut self, shard_id : u64, server_count : u64) {
         self.shards += 1;
-        self.boot_count.push(server_count);
+        self.boot_count.push((shard_id, server_count));
+
+        let threshold = shard_guild_threshold();
+        if threshold > 0 && server_count > threshold {
+            warn!("Shard {} is holding {} guilds, over the threshold of {}. Time to re-shard", shard_id, server_count, threshold);
+        }
     }
 
     pub fn get_boot_vec_sum(&self) -> u64 {
-        self.boot_count.iter().sum()
+        self.boot_count.iter().map(|(_, count)| count).sum()
+    }
+
+    /// The shard holding the most guilds, as (shard id, guilds)
+    pub fn largest_shard(&self) -> Option<(u64, u64)> {
+        self.boot_count.iter().max_by_key(|(_, count)| *count).copied()
     }
 
     async fn send_request<T: Sendable + std::marker::Sync>(&self, sendable: &mut T) {
+        // logged before the key is filled in so it never ends up in our logs
+        if dry_run() {
+            let payload = serde_json::to_string(sendable).unwrap_or_default();
+            info!("[dry run] POST {}/{}: {}", self.url, sendable.endpoint(), payload);
+            return;
+        }
+
         sendable.set_key(&self.pass);
-        match sendable.send(self.client.clone(), &self.url).await {
-            Ok(_) => (),
-            Err(e) => warn!("Request failed to {}: {}", sendable.endpoint(), e),
+        let result = sendable.send(self.client.clone(), &self.url).await;
+        let mut monitor = self.monitor.lock().unwrap();
+        match result {
+            Ok(_) => monitor.success("stats"),
+            Err(e) => {
+                throttle::warn(&self.throttle, "Failed to post to the stats api", &format!("Request failed to {}: {}", sendable.endpoint(), e));
+                monitor.failure("stats", &format!("{}: {}", sendable.endpoint(), e));
+            }
         }
     }
 }
+
+/// Minutes between flushes of the guild counters to guildstats.json. Zero disables them,
+/// counters are then lost on restart like they used to be
+pub fn guild_stats_flush_interval() -> u64 {
+    env::var("GUILD_STATS_FLUSH_INTERVAL")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_GUILD_STATS_FLUSH_INTERVAL)
+}
+
+pub fn write_guild_stats(json: &str) {
+    if let Err(e) = persistence::write_atomic("guildstats.json", json) {
+        error!("Unable to write guildstats.json: {}", e);
+    }
+}
+
+// Counters are nice to have, a missing or broken file means starting over rather than refusing to boot
+fn load_guild_stats() -> LruCache<u64, GuildStats> {
+    let mut guilds = LruCache::new(GUILD_STATS_SIZE);
+    if guild_stats_flush_interval() == 0 {
+        return guilds;
+    }
+
+    match persistence::load_versioned::<SavedGuildStats>("guildstats.json", &GUILD_STATS_MIGRATIONS) {
+        Ok(Some(saved)) => {
+            for (guild_id, stats) in saved.guilds {
+                guilds.insert(guild_id, stats);
+            }
+            info!("Loaded usage counters for {} guilds", guilds.len());
+        }
+        Ok(None) => (),
+        Err(e) => warn!("Unable to load guildstats.json, guild counters start over: {}", e),
+    }
+    guilds
+}
+
+/// Guilds a single shard may hold before we warn that it's time to re-shard. Zero disables the warning
+pub fn shard_guild_threshold() -> u64 {
+    env::var("SHARD_GUILD_WARNING")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_SHARD_GUILD_WARNING)
+}
+
+/// In a dry run nothing is posted to the stats service or top.gg, we only log what
+/// would have been sent. Meant for running the bot locally
+pub fn dry_run() -> bool {
+    match env::var("DRY_RUN") {
+        Ok(v) => v == "1" || v.eq_ignore_ascii_case("true"),
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,56 @@
+// Programs whose only purpose is exhausting the backend's resources. These
+// waste a backend slot until the timeout even when they can't do any harm,
+// so they're refused before being sent anywhere. The list is kept narrow on
+// purpose, each pattern only matches the textbook form of the abuse and only
+// for languages where it means something, legitimate code should never trip it.
+//
+// (languages, pattern, description)
+static PATTERNS: [(&[&str], &str, &str); 5] = [
+    (
+        &["bash script", "bash", "shell", "sh"],
+        r":\(\)\s*\{\s*:\s*\|\s*:\s*&\s*\}\s*;\s*:",
+        "shell fork bomb",
+    ),
+    (
+        &["c", "c++", "cpp"],
+        r"(while\s*\(\s*(1|true)\s*\)|for\s*\(\s*;\s*;\s*\))\s*\{?\s*fork\s*\(\s*\)",
+        "fork loop",
+    ),
+    (
+        &["c", "c++", "cpp"],
+        r"(while\s*\(\s*(1|true)\s*\)|for\s*\(\s*;\s*;\s*\))\s*(\{\s*(malloc|calloc)\s*\([^;{}]*\)\s*;\s*\}|(malloc|calloc)\s*\([^;{}]*\)\s*;)",
+        "allocation loop",
+    ),
+    (
+        &["python"],
+        r"while\s+(True|1)\s*:\s*os\.fork\s*\(\s*\)",
+        "fork loop",
+    ),
+    (
+        &["perl"],
+        r"fork\s+while\s+fork",
+        "fork bomb",
+    ),
+];
+
+lazy_static! {
+    // PATTERNS' patterns, in the same order
+    static ref REGEXES: Vec<regex::Regex> = PATTERNS.iter().map(|(_, pattern, _)| regex::Regex::new(pattern).unwrap()).collect();
+}
+
+/// Describes the abuse pattern found in the code if it matches one meant for its language
+pub fn find_abuse(language: &str, code: &str) -> Option<&'static str> {
+    let language = language.to_lowercase();
+    for ((languages, _, description), re) in PATTERNS.iter().zip(REGEXES.iter()) {
+        if !languages.contains(&language.as_str()) {
+            continue;
+        }
+
+        if let Some(found) = re.find(code) {
+            // logged so the patterns can be tuned if they ever misfire
+            warn!("Refused {} code matching '{}': {}", language, description, found.as_str());
+            return Some(description);
+        }
+    }
+    None
+}
@@ -1,57 +1,172 @@
-use std::fs;
+use std::collections::HashMap;
 
+use chrono::{DateTime, Duration, TimeZone, Utc};
 use serde::*;
+use serde_json::{json, Value};
+
+use crate::utls::persistence::{self, Migration};
+
+// Upgrades older blocklist.json formats, see persistence::Migration
+static MIGRATIONS: [Migration; 1] = [list_to_entries];
+
+// version 1 only had a list of blocked ids, those become permanent blocks without a reason
+fn list_to_entries(mut value: Value) -> Value {
+    if let Some(map) = value.as_object_mut() {
+        let list = map.remove("list").unwrap_or_else(|| json!([]));
+        let mut entries = serde_json::Map::new();
+        for id in list.as_array().into_iter().flatten() {
+            if let Some(id) = id.as_str() {
+                entries.insert(String::from(id), json!({}));
+            }
+        }
+        map.insert(String::from("entries"), Value::Object(entries));
+    }
+    value
+}
+
+/// A blocked user or guild
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BlockEntry {
+    /// Shown to the user when their commands are refused
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Unix timestamp the block lifts at, permanent blocks have none
+    #[serde(default)]
+    pub expires: Option<i64>,
+}
+
+impl BlockEntry {
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.expires.map(|e| Utc.timestamp(e, 0))
+    }
+
+    fn expired(&self, now: DateTime<Utc>) -> bool {
+        match self.expires {
+            Some(e) => e <= now.timestamp(),
+            None => false,
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize, Default)]
 pub struct Blocklist {
-    list : Vec<String>
+    // users & guilds
+    #[serde(default)]
+    entries : HashMap<u64, BlockEntry>,
+    #[serde(default)]
+    channels : Vec<String>
 }
 
 impl Blocklist {
     pub fn new() -> Blocklist {
-        let path = std::path::Path::new("blocklist.json");
-        if !path.exists() {
-            return Blocklist::create_blocklist();
+        let loaded = persistence::load_versioned("blocklist.json", &MIGRATIONS)
+            .expect("Unable to load blocklist.json");
+        match loaded {
+            Some(list) => list,
+            None => Blocklist::create_blocklist(),
         }
+    }
 
-        let json = fs::read_to_string(path)
-            .expect("Unable to read blocklist.json");
+    /// Whether the snowflake is blocked right now, for when we can't prune, see active
+    pub fn contains(&self, snowflake : u64) -> bool {
+        match self.entries.get(&snowflake) {
+            Some(entry) => !entry.expired(Utc::now()),
+            None => false,
+        }
+    }
 
-        let list : Blocklist = serde_json::from_str(&json)
-            .expect("Unable to deserialize blocklist.json");
-        list
+    /// The block the snowflake is under, if any. Blocks that have run out are removed along the way
+    pub fn active(&mut self, snowflake : u64) -> Option<BlockEntry> {
+        let entry = self.entries.get(&snowflake)?;
+        if entry.expired(Utc::now()) {
+            self.entries.remove(&snowflake);
+            self.write();
+            return None;
+        }
+        Some(entry.clone())
     }
 
-    pub fn contains(&self, snowflake : u64) -> bool {
-        self.list.contains(&snowflake.to_string())
+    pub fn insert_permanent(&mut self, snowflake : u64, reason : Option<String>) {
+        self.entries.insert(snowflake, BlockEntry {
+            reason,
+            expires: None,
+        });
+        self.write();
     }
 
-    pub fn block(&mut self, snowflake : u64) {
-        let snowflake = snowflake.to_string();
-        self.list.push(snowflake);
+    pub fn insert_temp(&mut self, snowflake : u64, duration : Duration, reason : Option<String>) {
+        let expires = Utc::now() + duration;
+        self.entries.insert(snowflake, BlockEntry {
+            reason,
+            expires: Some(expires.timestamp()),
+        });
         self.write();
     }
 
     pub fn unblock(&mut self, snowflake : u64) {
+        self.entries.remove(&snowflake);
+        self.write();
+    }
+
+    pub fn contains_channel(&self, snowflake : u64) -> bool {
+        self.channels.contains(&snowflake.to_string())
+    }
+
+    pub fn block_channel(&mut self, snowflake : u64) {
         let snowflake = snowflake.to_string();
-        self.list.retain(|x| *x != snowflake);
+        self.channels.push(snowflake);
         self.write();
     }
 
-    pub fn write(&self) {
-        let json = serde_json::to_string(self)
-            .expect("Unable to serialize blocklist.json");
+    pub fn unblock_channel(&mut self, snowflake : u64) {
+        let snowflake = snowflake.to_string();
+        self.channels.retain(|x| *x != snowflake);
+        self.write();
+    }
 
-        fs::write("blocklist.json", json)
+    pub fn write(&self) {
+        persistence::write_versioned("blocklist.json", self, &MIGRATIONS)
             .expect("Unable to create blocklist.json!");
     }
 
     fn create_blocklist() -> Blocklist {
         let list = Blocklist {
-            list : Default::default()
+            entries : Default::default(),
+            channels : Default::default()
         };
         list.write();
         list
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_becomes_entries() {
+        let value = persistence::migrate(json!({ "list": ["1"] }), &MIGRATIONS).unwrap();
+        assert_eq!(value, json!({ "entries": { "1": {} } }));
+
+        let list: Blocklist = serde_json::from_value(value).unwrap();
+        assert!(list.contains(1));
+        assert!(list.entries[&1].reason.is_none());
+        assert!(list.entries[&1].expires.is_none());
+    }
+
+    #[test]
+    fn empty_list_becomes_no_entries() {
+        let value = list_to_entries(json!({}));
+        assert_eq!(value, json!({ "entries": {} }));
+    }
+
+    #[test]
+    fn newer_blocklist_is_refused() {
+        assert!(persistence::migrate(json!({ "entries": {}, "version": 3 }), &MIGRATIONS).is_err());
+    }
+
+    #[test]
+    fn version_zero_blocklist_is_refused() {
+        assert!(persistence::migrate(json!({ "entries": {}, "version": 0 }), &MIGRATIONS).is_err());
+    }
+}
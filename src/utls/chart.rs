@@ -0,0 +1,43 @@
+use std::error::Error;
+use std::path::Path;
+
+use plotters::prelude::*;
+
+use crate::utls::constants::{CHART_HEIGHT, CHART_WIDTH};
+
+/// Renders (label, value) pairs as a bar chart, saved as a png at `path`.
+/// This is cpu bound, so it's best ran through spawn_blocking
+pub fn render_bar_chart(path: &Path, title: &str, bars: &[(String, u64)]) -> Result<(), Box<dyn Error>> {
+    let root = BitMapBackend::new(path, (CHART_WIDTH, CHART_HEIGHT)).into_drawing_area();
+    root.fill(&WHITE)?;
+
+    // leave some headroom above the tallest bar
+    let max = bars.iter().map(|(_, value)| *value).max().unwrap_or(0);
+    let mut chart = ChartBuilder::on(&root)
+        .caption(title, ("sans-serif", 28))
+        .margin(15)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d((0..bars.len()).into_segmented(), 0..max + max / 10 + 1)?;
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .x_labels(bars.len())
+        .x_label_formatter(&|segment| match segment {
+            SegmentValue::CenterOf(i) => bars.get(*i).map(|(label, _)| label.clone()).unwrap_or_default(),
+            _ => String::new(),
+        })
+        .y_desc("Uses")
+        .draw()?;
+
+    chart.draw_series(
+        Histogram::vertical(&chart)
+            .style(RGBColor(0x58, 0x65, 0xf2).filled())
+            .margin(6)
+            .data(bars.iter().enumerate().map(|(i, (_, value))| (i, *value))),
+    )?;
+
+    root.present()?;
+    Ok(())
+}
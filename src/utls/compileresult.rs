@@ -0,0 +1,339 @@
+use std::env;
+use std::time::Duration;
+
+use crate::apis::execution::ExecutionResult;
+use crate::apis::stream::StreamOutput;
+use crate::utls::constants::{DEFAULT_COLLAPSE_THRESHOLD, TIMEOUT_SIGNALS};
+
+/// Backend agnostic result of a compilation, every backend's response gets
+/// mapped into this so embeds, caches & stats only have one shape to handle
+#[derive(Default)]
+pub struct CompileResult {
+    pub backend: &'static str,
+    pub compiler: String,
+    pub compiler_output: String,
+    pub stdout: String,
+    pub stderr: String,
+    /// None if the program never ran (or the backend didn't tell us)
+    pub exit_code: Option<i32>,
+    pub signal: Option<String>,
+    /// The whole request from our end, see build_time_ms & run_time_ms for the backend's own timings
+    pub compile_time_ms: u64,
+    pub build_time_ms: Option<u64>,
+    pub run_time_ms: Option<u64>,
+    pub url: Option<String>,
+    /// Libraries the program was built with, as the user named them
+    pub libraries: Vec<String>,
+    /// Flags we added for the headers the code uses, see utls::flagpresets
+    pub auto_flags: Vec<String>,
+    /// The runtime picked with --runtime & the compiler it resolved to
+    pub runtime: Option<String>,
+    /// Size of the input read from --stdin-file, in bytes
+    pub stdin_bytes: Option<usize>,
+    /// The user asked for --timing
+    pub timing: bool,
+    /// The user asked for --spoiler
+    pub spoiler: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    Success,
+    CompileError,
+    RuntimeError,
+    /// Killed by the backend for running too long, see TIMEOUT_SIGNALS
+    TimedOut,
+}
+
+impl CompileResult {
+    pub fn from_stream(res: &StreamOutput, compiler: &str, elapsed: Duration) -> CompileResult {
+        CompileResult {
+            backend: "wandbox",
+            compiler: String::from(compiler),
+            compiler_output: res.compiler_output.clone(),
+            stdout: res.stdout.clone(),
+            stderr: res.stderr.clone(),
+            exit_code: res.status.parse::<i32>().ok(),
+            signal: non_empty(res.signal.clone()),
+            compile_time_ms: elapsed.as_millis() as u64,
+            build_time_ms: None,
+            run_time_ms: None,
+            url: None,
+            libraries: Vec::new(),
+            auto_flags: Vec::new(),
+            runtime: None,
+            stdin_bytes: None,
+            timing: false,
+            spoiler: false,
+        }
+    }
+
+    pub fn from_godbolt(res: ExecutionResult, compiler: &str, elapsed: Duration) -> CompileResult {
+        let exit_code = if res.did_execute { Some(res.code) } else { None };
+        CompileResult {
+            backend: "godbolt",
+            compiler: String::from(compiler),
+            compiler_output: String::new(),
+            stdout: res.stdout_text(),
+            stderr: res.stderr_text(),
+            exit_code,
+            signal: None,
+            compile_time_ms: elapsed.as_millis() as u64,
+            build_time_ms: res.build_result.as_ref().and_then(|b| b.exec_time_ms()),
+            run_time_ms: if res.did_execute { res.exec_time_ms() } else { None },
+            url: None,
+            libraries: Vec::new(),
+            auto_flags: Vec::new(),
+            runtime: None,
+            stdin_bytes: None,
+            timing: false,
+            spoiler: false,
+        }
+    }
+
+    /// Whatever the backend told us about how long building & running took.
+    /// Wandbox doesn't time either, so we can only give the total it took us
+    pub fn timing_summary(&self) -> String {
+        match (self.build_time_ms, self.run_time_ms) {
+            (Some(build), Some(run)) => format!("compiled in {}ms, ran in {}ms", build, run),
+            (Some(build), None) => format!("compiled in {}ms", build),
+            (None, Some(run)) => format!("ran in {}ms", run),
+            (None, None) => format!("took {}ms in total", self.compile_time_ms),
+        }
+    }
+
+    /// Whether the program ran to completion & exited cleanly
+    pub fn succeeded(&self) -> bool {
+        self.signal.is_none() && self.exit_code == Some(0)
+    }
+
+    /// Whether the backend killed the program because it ran for too long
+    pub fn timed_out(&self) -> bool {
+        match &self.signal {
+            Some(signal) => TIMEOUT_SIGNALS.contains(&signal.as_str()),
+            None => false,
+        }
+    }
+
+    /// Whether the build itself worked, regardless of what the program did. A
+    /// failing status with no program output and something from the compiler
+    /// means we never got as far as running anything
+    pub fn compiled(&self) -> bool {
+        self.succeeded()
+            || self.signal.is_some()
+            || !self.stdout.is_empty()
+            || !self.stderr.is_empty()
+            || self.compiler_output.is_empty()
+    }
+
+    /// Tells a build that failed, where the program never ran, apart from a program that
+    /// built fine but failed while running, or never got to finish
+    pub fn outcome(&self) -> Outcome {
+        if self.succeeded() {
+            Outcome::Success
+        } else if self.timed_out() {
+            Outcome::TimedOut
+        } else if !self.compiled() {
+            Outcome::CompileError
+        } else {
+            Outcome::RuntimeError
+        }
+    }
+
+    /// Collapses repeated lines in the program's output, see collapse_repeats
+    pub fn collapse_output(&mut self, threshold: usize) {
+        self.stdout = collapse_repeats(&self.stdout, threshold);
+        self.stderr = collapse_repeats(&self.stderr, threshold);
+    }
+
+    pub fn status(&self) -> String {
+        match (self.exit_code, &self.signal) {
+            (_, Some(signal)) => format!("signal '{}'", signal),
+            (Some(code), None) => format!("exit code {}", code),
+            (None, None) => String::from("unknown"),
+        }
+    }
+}
+
+fn non_empty(s: String) -> Option<String> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(s)
+    }
+}
+
+/// How many identical lines in a row it takes for them to be collapsed. Zero disables it
+pub fn collapse_threshold() -> usize {
+    env::var("OUTPUT_COLLAPSE_THRESHOLD")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_COLLAPSE_THRESHOLD)
+}
+
+// Programs stuck printing the same thing over and over would otherwise eat up
+// our output limits, so runs of exactly identical consecutive lines become a
+// single "<line> (repeated N times)". Anything that differs is left alone
+pub fn collapse_repeats(output: &str, threshold: usize) -> String {
+    if threshold < 2 {
+        return String::from(output);
+    }
+
+    let mut collapsed = Vec::new();
+    let mut lines = output.split('\n').peekable();
+    while let Some(line) = lines.next() {
+        let mut count = 1;
+        while lines.peek() == Some(&line) {
+            lines.next();
+            count += 1;
+        }
+
+        if count >= threshold {
+            collapsed.push(format!("{} (repeated {} times)", line, count));
+        } else {
+            collapsed.extend(std::iter::repeat(String::from(line)).take(count));
+        }
+    }
+    collapsed.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(compiler_output: &str, stdout: &str, stderr: &str, exit_code: Option<i32>, signal: Option<&str>) -> CompileResult {
+        let mut result = CompileResult::from_godbolt(ExecutionResult::default(), "test", Duration::from_millis(0));
+        result.compiler_output = String::from(compiler_output);
+        result.stdout = String::from(stdout);
+        result.stderr = String::from(stderr);
+        result.exit_code = exit_code;
+        result.signal = signal.map(String::from);
+        result
+    }
+
+    #[test]
+    fn stream_mapping() {
+        let mut output = StreamOutput::default();
+        output.compiler_output = String::from("prog.cc:1:1: warning: something");
+        output.stdout = String::from("hello");
+        output.stderr = String::from("oops");
+        output.status = String::from("3");
+        output.signal = String::new();
+
+        let res = CompileResult::from_stream(&output, "gcc-head", Duration::from_millis(1500));
+        assert_eq!(res.backend, "wandbox");
+        assert_eq!(res.compiler, "gcc-head");
+        assert_eq!(res.compiler_output, "prog.cc:1:1: warning: something");
+        assert_eq!(res.stdout, "hello");
+        assert_eq!(res.stderr, "oops");
+        assert_eq!(res.exit_code, Some(3));
+        assert_eq!(res.signal, None);
+        assert_eq!(res.compile_time_ms, 1500);
+        assert_eq!(res.run_time_ms, None);
+    }
+
+    #[test]
+    fn stream_mapping_with_signal() {
+        let mut output = StreamOutput::default();
+        output.signal = String::from("Killed");
+
+        let res = CompileResult::from_stream(&output, "gcc-head", Duration::from_millis(0));
+        assert_eq!(res.exit_code, None);
+        assert_eq!(res.signal.as_deref(), Some("Killed"));
+    }
+
+    #[test]
+    fn godbolt_mapping() {
+        let exec: ExecutionResult = serde_json::from_value(serde_json::json!({
+            "code": 0,
+            "didExecute": true,
+            "stdout": [{ "text": "one" }, { "text": "two" }],
+            "stderr": [{ "text": "warn" }],
+            "buildResult": { "code": 0, "stderr": [], "execTime": "120" },
+            "execTime": 7
+        }))
+        .unwrap();
+
+        let res = CompileResult::from_godbolt(exec, "g112", Duration::from_millis(900));
+        assert_eq!(res.backend, "godbolt");
+        assert_eq!(res.compiler, "g112");
+        assert_eq!(res.stdout, "one\ntwo");
+        assert_eq!(res.stderr, "warn");
+        assert_eq!(res.exit_code, Some(0));
+        assert_eq!(res.build_time_ms, Some(120));
+        assert_eq!(res.run_time_ms, Some(7));
+        assert_eq!(res.compile_time_ms, 900);
+    }
+
+    #[test]
+    fn godbolt_mapping_without_execution() {
+        let exec: ExecutionResult = serde_json::from_value(serde_json::json!({
+            "code": -1,
+            "didExecute": false,
+            "buildResult": { "code": 1, "stderr": [{ "text": "undefined reference to `foo'" }] },
+            "execTime": 3
+        }))
+        .unwrap();
+
+        let res = CompileResult::from_godbolt(exec, "g112", Duration::from_millis(0));
+        assert_eq!(res.exit_code, None);
+        assert_eq!(res.run_time_ms, None);
+        assert_eq!(res.build_time_ms, None);
+        assert!(res.stdout.is_empty());
+    }
+
+    #[test]
+    fn cpp_compile_error() {
+        let res = result("prog.cc:3:5: error: 'foo' was not declared in this scope", "", "", Some(1), None);
+        assert_eq!(res.outcome(), Outcome::CompileError);
+    }
+
+    #[test]
+    fn cpp_segfault() {
+        let res = result("", "", "", None, Some("Segmentation fault"));
+        assert_eq!(res.outcome(), Outcome::RuntimeError);
+    }
+
+    #[test]
+    fn c_warnings_still_succeed() {
+        let res = result("prog.c:4:9: warning: unused variable 'x'", "hello\n", "", Some(0), None);
+        assert_eq!(res.outcome(), Outcome::Success);
+    }
+
+    #[test]
+    fn rust_compile_error() {
+        let res = result("error[E0425]: cannot find value `y` in this scope", "", "", Some(1), None);
+        assert_eq!(res.outcome(), Outcome::CompileError);
+    }
+
+    #[test]
+    fn rust_panic() {
+        let res = result("", "", "thread 'main' panicked at 'oops', prog.rs:2:5", Some(101), None);
+        assert_eq!(res.outcome(), Outcome::RuntimeError);
+    }
+
+    #[test]
+    fn python_exception() {
+        let res = result("", "", "Traceback (most recent call last):\nZeroDivisionError: division by zero", Some(1), None);
+        assert_eq!(res.outcome(), Outcome::RuntimeError);
+    }
+
+    #[test]
+    fn python_success() {
+        let res = result("", "42\n", "", Some(0), None);
+        assert_eq!(res.outcome(), Outcome::Success);
+    }
+
+    #[test]
+    fn signal_with_clean_output_is_runtime_error() {
+        let res = result("", "partial output", "", Some(0), Some("Aborted"));
+        assert_eq!(res.outcome(), Outcome::RuntimeError);
+    }
+
+    #[test]
+    fn java_backend_timeout() {
+        let res = result("", "0\n1\n2\n", "", None, Some("Killed"));
+        assert_eq!(res.outcome(), Outcome::TimedOut);
+        assert!(res.compiled());
+    }
+}
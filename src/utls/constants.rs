@@ -2,11 +2,161 @@
 pub static COLOR_OKAY: i32 = 0x5dbcd2;
 //pub static COLOR_FAIL : i32 = 0x660404;
 pub static COLOR_FAIL: i32 = 0xff7761;
+pub static COLOR_RUNTIME_ERROR: i32 = 0xf0b132;
+
+// Signals the backends kill programs with once their time is up
+pub static TIMEOUT_SIGNALS: [&str; 4] = ["Killed", "SIGKILL", "CPU time limit exceeded", "SIGXCPU"];
 pub static ICON_FAIL: &str = "https://i.imgur.com/LxxYrFj.png";
 pub static ICON_VOTE: &str = "https://i.imgur.com/VXbdwSQ.png";
 pub static ICON_HELP: &str = "https://i.imgur.com/TNzxfMB.png";
 pub static ICON_INVITE: &str = "https://i.imgur.com/CZFt69d.png";
 pub static COMPILER_EXPLORER_ICON: &str = "https://i.imgur.com/GIgATFr.png";
 pub static MAX_OUTPUT_LEN: usize = 250;
+pub static LAST_SOURCE_TTL: u64 = 300;
+pub static THREAD_LINE_THRESHOLD: usize = 15;
+pub static STREAM_EDIT_INTERVAL: u64 = 2;
+pub static ERROR_LOG_SIZE: usize = 50;
+pub static GUILD_LOG_SIZE: usize = 100;
+// the fewest members a partial member list needs before its bot ratio means anything
+pub static MIN_RATIO_SAMPLE: usize = 100;
+pub static VOTER_CACHE_SIZE: usize = 5000;
+// top.gg lets users vote again every 12 hours, the reward lasts until then
+pub static VOTE_REWARD_DURATION: u64 = 12 * 60 * 60;
+pub static DEFAULT_VOTER_COOLDOWN: u8 = 50;
+pub static CHART_WIDTH: u32 = 900;
+pub static CHART_HEIGHT: u32 = 500;
+pub static CHART_COMMANDS: usize = 15;
+pub static EXPIRY_CHECK_INTERVAL: u64 = 15;
+pub static MAX_MIN_ACCOUNT_AGE: u64 = 365;
+pub static MAX_OUTPUT_EXPIRY: u64 = 24 * 60;
+pub static LOG_THROTTLE_WINDOW: u64 = 60;
+pub static SESSIONS_PER_PAGE: usize = 15;
+pub static DEFAULT_RECENT_GUILDS: usize = 10;
+pub static INTERACTION_COOLDOWN: u64 = 3;
+pub static NOSPAM_DELAY: u64 = 3;
+pub static DEFAULT_SUPPORTER_COOLDOWN: u8 = 50;
+pub static DEFAULT_TRUSTED_COOLDOWN: u8 = 25;
+// discord refuses to identify shards holding more than 2500 guilds
+pub static DEFAULT_SHARD_GUILD_WARNING: u64 = 2000;
+pub static DEFAULT_TRUSTED_SAMPLE_MULTIPLIER: u32 = 3;
+pub static DEFAULT_TRUSTED_RATE_LIMIT_MULTIPLIER: usize = 3;
+pub static DEFAULT_GUILD_RATE_LIMIT: usize = 60;
+pub static GUILD_RATE_LIMIT_SIZE: usize = 1000;
+pub static DEFAULT_MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+pub static DEFAULT_MAX_CODE_BLOCKS: usize = 1;
+pub static DEFAULT_NO_OUTPUT_MESSAGE: &str = "Program ran successfully with no output, exit code 0";
+// header blocks tagged ```h without a filename marker are #included by this name
+pub static DEFAULT_HEADER_NAME: &str = "solution.h";
+pub static DEFAULT_COLLAPSE_THRESHOLD: usize = 3;
+pub static MAX_COMPARE_LANGUAGES: usize = 3;
+pub static DEFAULT_OPT_LEVELS: [&str; 4] = ["-O0", "-O1", "-O2", "-O3"];
+pub static MAX_OPT_LEVELS: usize = 4;
+// ;optcompare sends a request per level, so it's held to a longer cooldown than nospam
+pub static OPTCOMPARE_DELAY: u64 = 15;
+pub static MAX_RACE_LANGUAGES: usize = 4;
+// every language in a ;race is its own full compilation
+pub static RACE_DELAY: u64 = 30;
+pub static RACE_MEDALS: [&str; 3] = ["🥇", "🥈", "🥉"];
+pub static MAX_SNIPPETS: usize = 25;
+pub static MAX_SNIPPET_LEN: usize = 2000;
+pub static MAX_SNIPPET_NAME_LEN: usize = 32;
+pub static MAX_PREFIX_LEN: usize = 8;
+pub static PRESENCE_INTERVAL: u64 = 900;
+pub static PRESENCE_JITTER: u64 = 30000;
+pub static CACHE_STATS_INTERVAL: u64 = 60;
+pub static DEFAULT_BACKUP_INTERVAL: u64 = 360;
+pub static DEFAULT_BACKUP_RETENTION: usize = 10;
+pub static GUILD_STATS_SIZE: usize = 2500;
+pub static GUILD_STATS_DAYS: usize = 30;
+pub static DEFAULT_GUILD_STATS_FLUSH_INTERVAL: u64 = 5;
+pub static DEFAULT_FAILURE_RATE_THRESHOLD: u32 = 75;
+pub static DEFAULT_FAILURE_RATE_MIN_SAMPLES: u32 = 20;
+pub static DEFAULT_FAILURE_RATE_WINDOW: u64 = 60;
+pub static DEFAULT_MAX_CONCURRENT_COMPILES: usize = 10;
+pub static COMPILE_QUEUE_TIMEOUT: u64 = 10;
+pub static PERMLINK_TIMEOUT: u64 = 5;
+pub static QUEUE_NOTICE_DELAY: u64 = 2;
+pub static DEFAULT_ALERT_THRESHOLD: u32 = 5;
+pub static DEFAULT_ALERT_WINDOW: u64 = 30;
+pub static ALERT_CHECK_INTERVAL: u64 = 60;
+pub static RATELIMIT_PROBE_INTERVAL: u64 = 30;
+pub static SCHEDULE_CHECK_INTERVAL: u64 = 30;
+pub static DEFAULT_RATELIMIT_THRESHOLD: u64 = 5000;
+pub static OUTPUT_RECORD_SIZE: usize = 500;
+pub static REPORT_EMOJI: &str = "🚩";
+pub static RERUN_EMOJI: &str = "➕";
+pub static EXPAND_EMOJI: &str = "🔍";
+pub static DM_EMOJI: &str = "📬";
+pub static BLOCKED_EMOJI: &str = "🚫";
+pub static ACK_EMOJI: &str = "⏳";
+pub static RERUN_CACHE_SIZE: usize = 250;
+pub static EXPAND_CACHE_SIZE: usize = 250;
+pub static COMMAND_CONTENT_CACHE_SIZE: usize = 250;
+pub static SUPPORTER_LOOKUP_CACHE_SIZE: usize = 250;
+// seconds after sending a command that editing it still runs it again
+pub static EDIT_RERUN_WINDOW: i64 = 60;
+pub static RUNTIME_VERSION_CACHE_SIZE: usize = 100;
+pub static STDIN_PROMPT_TIMEOUT: u64 = 60;
+// checked against the attachment's reported size before we download anything
+pub static MAX_STDIN_FILE_BYTES: u64 = 256 * 1024;
+pub static MAX_SOURCE_FILE_BYTES: u64 = 256 * 1024;
+pub static CONFIRM_EMOJI: &str = "✅";
+pub static CONFIRM_TIMEOUT: u64 = 30;
+pub static SUGGESTION_MAX_DISTANCE: usize = 2;
+pub static SUGGESTION_MIN_SIMILARITY: f64 = 0.6;
+pub static DIFF_CONTEXT_LINES: usize = 3;
+pub static MAX_DIFF_LEN: usize = 1900;
+
+// Backends compilations can be routed to, wandbox is the default since it
+// supports the most languages and the only one that takes stdin
+pub static BACKENDS: [(&str, &str); 2] = [
+    ("wandbox", "Supports the most languages, along with stdin & runtime options"),
+    ("godbolt", "Compiler explorer, offers a far wider choice of compilers and versions"),
+];
+pub static DEFAULT_BACKEND: &str = "wandbox";
+pub static DEFAULT_GODBOLT_URL: &str = "https://godbolt.org";
+pub static DEFAULT_WANDBOX_URL: &str = "https://wandbox.org";
+
+pub static ONBOARDING_TIP: &str = "Put flags like `-O2` before your code block, or use `< link` to compile code from a paste";
+
+pub static URL_ALLOW_LIST : [&str; 3] = ["pastebin.com", "gist.githubusercontent.com", "hastebin.com"];
+
+// File extension -> language, for source given as an attachment or a url without a language.
+// .txt is left out on purpose, those are taken as input (see --stdin-file)
+pub static SOURCE_EXTENSIONS : [(&str, &str); 24] = [
+    ("c", "c"),
+    ("cpp", "c++"),
+    ("cc", "c++"),
+    ("cxx", "c++"),
+    ("rs", "rust"),
+    ("py", "python"),
+    ("js", "javascript"),
+    ("ts", "typescript"),
+    ("go", "go"),
+    ("java", "java"),
+    ("cs", "csharp"),
+    ("kt", "kotlin"),
+    ("rb", "ruby"),
+    ("php", "php"),
+    ("lua", "lua"),
+    ("hs", "haskell"),
+    ("swift", "swift"),
+    ("scala", "scala"),
+    ("sh", "bash"),
+    ("pl", "perl"),
+    ("d", "d"),
+    ("nim", "nim"),
+    ("cr", "crystal"),
+    ("zig", "zig"),
+];
+
+// Raw assembly 'languages' for ;run & ;compile, these are assembled, linked & ran on godbolt
+// by the first godbolt assembler whose name contains the second value
+pub static ASSEMBLERS : [(&str, &str); 4] = [("asm", "nasm"), ("assembly", "nasm"), ("nasm", "nasm"), ("gas", "gnu as")];
+
+// Commands which result in a compilation, these are subject to channel restrictions.
+// Hooks see subcommands under their own name, which is why ;snippet run is covered by run
+pub static COMPILE_COMMANDS : [&str; 11] = ["compile", "run", "check", "asm", "swap", "random", "example", "optcompare", "race", "compare", "snippet"];
 
-pub static URL_ALLOW_LIST : [&str; 3] = ["pastebin.com", "gist.githubusercontent.com", "hastebin.com"];
\ No newline at end of file
+// Command categories roles can be required for, 'compile' covers COMPILE_COMMANDS
+pub static ROLE_CATEGORIES : [&str; 2] = ["compile", "all"];
@@ -0,0 +1,144 @@
+use regex::Regex;
+
+// Diagnostic headers of each toolchain family we understand, as (family, pattern).
+// The pattern's 'kind' group is what tells warnings & errors apart, every line
+// that doesn't start a diagnostic belongs to the one before it (notes, source excerpts)
+static FAMILIES: [(&str, &str); 2] = [
+    ("gcc", r"^[^\s:][^:]*:\d+(?::\d+)?: (?P<kind>fatal error|error|warning): "),
+    ("rustc", r"^(?P<kind>error|warning)(?:\[\w+\])?: "),
+];
+
+// rustc closes with summaries that look like diagnostics, but only count the real ones
+static SUMMARY: &str = r"^(?:warning: .*\d+ warnings? emitted|error: aborting due to|error: could not compile)";
+
+lazy_static! {
+    // FAMILIES' patterns, in the same order
+    static ref HEADERS: Vec<Regex> = FAMILIES.iter().map(|(_, pattern)| Regex::new(pattern).unwrap()).collect();
+    static ref SUMMARY_REGEX: Regex = Regex::new(SUMMARY).unwrap();
+}
+
+/// Compiler output split up into its warnings & errors, each entry is one whole diagnostic
+#[derive(Default)]
+pub struct Diagnostics {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Splits the output with the first toolchain family that recognizes it. Output
+/// none of them understand is None, so callers can show it as it is
+pub fn classify(output: &str) -> Option<Diagnostics> {
+    HEADERS.iter().find_map(|header| classify_with(output, header, &SUMMARY_REGEX))
+}
+
+fn classify_with(output: &str, header: &Regex, summary: &Regex) -> Option<Diagnostics> {
+    let mut diagnostics = Diagnostics::default();
+
+    // lines before a diagnostic (e.g. gcc's "In function 'int main()':") introduce it
+    let mut pending: Vec<&str> = Vec::new();
+    let mut current: Option<(bool, Vec<&str>)> = None;
+    for line in output.lines() {
+        if summary.is_match(line) {
+            continue;
+        }
+
+        let kind = header.captures(line).map(|c| c.name("kind").unwrap().as_str() != "warning");
+        match kind {
+            Some(error) => {
+                if let Some((was_error, lines)) = current.take() {
+                    push(&mut diagnostics, was_error, &lines);
+                }
+                let mut lines = std::mem::take(&mut pending);
+                lines.push(line);
+                current = Some((error, lines));
+            }
+            None => match current.as_mut() {
+                // gcc's context lines start at the very beginning of a line, like a new diagnostic would
+                Some(_) if line.ends_with("':") || line.ends_with("\":") => pending.push(line),
+                Some((_, lines)) => lines.push(line),
+                None => pending.push(line),
+            },
+        }
+    }
+
+    let (error, lines) = current?;
+    push(&mut diagnostics, error, &lines);
+
+    // anything left over trails the last diagnostic (e.g. "compilation terminated.")
+    if !pending.is_empty() {
+        let last = if error { diagnostics.errors.last_mut() } else { diagnostics.warnings.last_mut() };
+        if let Some(last) = last {
+            last.push('\n');
+            last.push_str(&pending.join("\n"));
+        }
+    }
+    Some(diagnostics)
+}
+
+fn push(diagnostics: &mut Diagnostics, error: bool, lines: &[&str]) {
+    let text = lines.join("\n");
+    if error {
+        diagnostics.errors.push(text);
+    } else {
+        diagnostics.warnings.push(text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gcc() {
+        let output = "prog.cc: In function 'int main()':\n\
+            prog.cc:4:9: warning: unused variable 'x' [-Wunused-variable]\n\
+            \x20   4 |     int x;\n\
+            prog.cc:5:5: error: 'foo' was not declared in this scope\n\
+            \x20   5 |     foo();";
+        let diagnostics = classify(output).unwrap();
+        assert_eq!(diagnostics.warnings.len(), 1);
+        assert_eq!(diagnostics.errors.len(), 1);
+        assert!(diagnostics.warnings[0].starts_with("prog.cc: In function 'int main()':"));
+        assert!(diagnostics.errors[0].ends_with("foo();"));
+    }
+
+    #[test]
+    fn gcc_fatal_error() {
+        let output = "prog.c:1:10: fatal error: missing.h: No such file or directory\ncompilation terminated.";
+        let diagnostics = classify(output).unwrap();
+        assert_eq!(diagnostics.errors, vec![output]);
+        assert!(diagnostics.warnings.is_empty());
+    }
+
+    #[test]
+    fn clang() {
+        let output = "prog.cc:3:5: error: use of undeclared identifier 'y'\n\
+            \x20   y = 1;\n\
+            \x20   ^\n\
+            prog.cc:2:9: warning: unused variable 'x' [-Wunused-variable]\n\
+            1 warning and 1 error generated.";
+        let diagnostics = classify(output).unwrap();
+        assert_eq!(diagnostics.errors.len(), 1);
+        assert_eq!(diagnostics.warnings.len(), 1);
+        assert!(diagnostics.errors[0].contains("y = 1;"));
+    }
+
+    #[test]
+    fn rustc() {
+        let output = "warning: unused variable: `x`\n\
+            \x20--> prog.rs:2:9\n\
+            error[E0425]: cannot find value `y` in this scope\n\
+            \x20--> prog.rs:3:5\n\
+            error: aborting due to previous error; 1 warning emitted";
+        let diagnostics = classify(output).unwrap();
+        assert_eq!(diagnostics.warnings.len(), 1);
+        assert_eq!(diagnostics.errors.len(), 1);
+        assert!(diagnostics.errors[0].starts_with("error[E0425]"));
+        assert!(!diagnostics.errors[0].contains("aborting"));
+    }
+
+    #[test]
+    fn unrecognized() {
+        assert!(classify("Traceback (most recent call last):\n  File \"prog.py\", line 1").is_none());
+        assert!(classify("").is_none());
+    }
+}
@@ -0,0 +1,93 @@
+// Small line based diff for showing users what would change in their code,
+// inputs are discord messages so the quadratic lcs table stays tiny
+enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+fn edits<'a>(before: &[&'a str], after: &[&'a str]) -> Vec<Line<'a>> {
+    // lcs[i][j] is the longest common subsequence of before[i..] and after[j..]
+    let mut lcs = vec![vec![0usize; after.len() + 1]; before.len() + 1];
+    for i in (0..before.len()).rev() {
+        for j in (0..after.len()).rev() {
+            lcs[i][j] = if before[i] == after[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < before.len() && j < after.len() {
+        if before[i] == after[j] {
+            lines.push(Line::Same(before[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(Line::Removed(before[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Added(after[j]));
+            j += 1;
+        }
+    }
+    lines.extend(before[i..].iter().map(|l| Line::Removed(l)));
+    lines.extend(after[j..].iter().map(|l| Line::Added(l)));
+    lines
+}
+
+fn old_lines(lines: &[Line]) -> usize {
+    lines.iter().filter(|l| !matches!(l, Line::Added(_))).count()
+}
+
+fn new_lines(lines: &[Line]) -> usize {
+    lines.iter().filter(|l| !matches!(l, Line::Removed(_))).count()
+}
+
+/// Unified diff between two texts with the given lines of context, None if their lines are identical
+pub fn unified(before: &str, after: &str, context: usize) -> Option<String> {
+    let before: Vec<&str> = before.lines().collect();
+    let after: Vec<&str> = after.lines().collect();
+    let lines = edits(&before, &after);
+
+    // changes whose context overlaps end up in the same hunk
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (i, _) in lines.iter().enumerate().filter(|(_, l)| !matches!(l, Line::Same(_))) {
+        let start = i.saturating_sub(context);
+        let end = (i + context + 1).min(lines.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    if hunks.is_empty() {
+        return None;
+    }
+
+    let mut output = String::new();
+    for (start, end) in hunks {
+        let hunk = &lines[start..end];
+        output.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_lines(&lines[..start]) + 1,
+            old_lines(hunk),
+            new_lines(&lines[..start]) + 1,
+            new_lines(hunk)
+        ));
+        for line in hunk {
+            let (prefix, text) = match line {
+                Line::Same(l) => (' ', l),
+                Line::Removed(l) => ('-', l),
+                Line::Added(l) => ('+', l),
+            };
+            output.push(prefix);
+            output.push_str(text);
+            output.push('\n');
+        }
+    }
+    Some(output)
+}
@@ -1,19 +1,42 @@
+use std::env;
 use std::str;
 use std::sync::Arc;
+use std::time::Duration;
+
+use rand::Rng;
 
 use serenity::{
     builder::{CreateEmbed, CreateMessage},
+    client::Context,
     http::Http,
     model::prelude::*,
+    prelude::TypeMap,
 };
 
 use serenity_utils::menu::*;
-use wandbox::*;
 
+use crate::cache::{CommandContentCache, ErrorLogCache, ExpiryCache, MessageDeleteCache, OutputRecordCache, SettingsCache, SupporterLookupCache, VoterCache};
+use crate::stats::guildstats::{FailureRateAlert, GuildStats};
+use crate::stats::statsmanager;
+use crate::utls::blocklist::BlockEntry;
+use crate::utls::compileresult::{CompileResult, Outcome};
 use crate::utls::constants::*;
+use crate::utls::diagnostics;
 use crate::utls::discordhelpers;
+use crate::utls::errorlog::ErrorLog;
+use crate::utls::gates::Requester;
+use crate::utls::guildlog::GuildLog;
+use crate::utls::schedule::{Maintenance, Schedule};
+use crate::utls::hints;
+use crate::utls::logos;
+use crate::utls::membership;
+use crate::utls::parser::FlagSource;
+use crate::utls::monitor::ServiceAlert;
+use crate::utls::reports::OutputRecord;
+use crate::utls::trusted;
 use tokio::sync::MutexGuard;
 use serenity::client::bridge::gateway::{ShardManager};
+use serenity::gateway::ConnectionStage;
 
 pub fn build_menu_items(
     items: Vec<String>,
@@ -99,53 +122,314 @@ pub fn build_reaction(emoji_id: u64, emoji_name: &str) -> ReactionType {
     }
 }
 
-pub fn build_compilation_embed(author: &User, res: & mut CompilationResult) -> CreateEmbed {
+pub fn build_compilation_embed(author: &User, res: &CompileResult, execute: bool, flags: FlagSource) -> CreateEmbed {
+    if !execute {
+        return build_compile_only_embed(author, res, flags);
+    }
+
     let mut embed = CreateEmbed::default();
 
-    if !res.status.is_empty() {
-        if res.status != "0" {
-            embed.color(COLOR_FAIL);
-        } else {
-            embed.field(
-                "Status",
-                format!("Finished with exit code: {}", &res.status),
-                false,
-            );
+    let outcome = res.outcome();
+    match outcome {
+        Outcome::Success => {
             embed.color(COLOR_OKAY);
+            embed.title("Run Results");
+            if res.exit_code == Some(0) {
+                // an empty embed looks like we lost the output, so say there wasn't any
+                let silent = res.stdout.is_empty() && res.stderr.is_empty();
+                match no_output_message() {
+                    Some(message) if silent => embed.field("Status", message, false),
+                    _ => embed.field("Status", "Finished with exit code: 0", false),
+                };
+            }
+        }
+        Outcome::CompileError => {
+            embed.color(COLOR_FAIL);
+            embed.title("Compilation Failed");
+            embed.field("Status", "The program failed to compile and never ran", false);
+        }
+        Outcome::RuntimeError => {
+            embed.color(COLOR_RUNTIME_ERROR);
+            embed.title("Runtime Error");
+            embed.field("Status", format!("Compiled, but crashed with {}", res.status()), false);
+        }
+        Outcome::TimedOut => {
+            embed.color(COLOR_RUNTIME_ERROR);
+            embed.title("Timed Out");
+            embed.field("Status", "The program ran for too long and was stopped by the backend", false);
+        }
+    }
+    if let Some(signal) = &res.signal {
+        embed.field("Signal", signal, false);
+    }
+    if !res.compiler_output.is_empty() {
+        add_compiler_output(&mut embed, &res.compiler_output);
+    }
+
+    // a crashing program's errors are what matters most, so they go first
+    let mut streams = vec![("Program Output (stdout)", &res.stdout), ("Program Errors (stderr)", &res.stderr)];
+    if outcome == Outcome::RuntimeError {
+        streams.reverse();
+    }
+    for (name, output) in streams {
+        if output.is_empty() {
+            continue;
+        }
+        embed.field(name, program_output(output, res.spoiler), false);
+    }
+    if !res.succeeded() {
+        let output = format!("{}\n{}\n{}", &res.compiler_output, &res.stdout, &res.stderr);
+        if let Some(hint) = hints::find_hint(&output) {
+            embed.field("Hint", hint, false);
+        }
+    }
+    if let Some(url) = &res.url {
+        embed.field("URL", url, false);
+    }
+
+    embed.footer(|f| f.text(compilation_footer(author, res, flags)));
+    embed
+}
+
+// The program's output as a code block. Spoiler tags go around the whole block, discord
+// doesn't parse markdown inside of code blocks so tags in there wouldn't hide anything.
+// The output is already cut down to size, so the closing tag always makes it in
+fn program_output(output: &str, spoiler: bool) -> String {
+    let block = format!("```\n{}\n```", conform_external_str(output));
+    if spoiler {
+        format!("||{}||", block)
+    } else {
+        block
+    }
+}
+
+// Warnings get a field of their own so they don't drown out the errors. Their text
+// is only shown when there are no errors, otherwise they're boiled down to a count.
+// Output we can't make sense of is shown as it is
+fn add_compiler_output(embed: &mut CreateEmbed, output: &str) {
+    let diagnostics = match diagnostics::classify(output) {
+        Some(d) if !d.warnings.is_empty() => d,
+        _ => {
+            let str = conform_external_str(output);
+            embed.field("Compiler Output", format!("```{}\n```", str), false);
+            return;
         }
+    };
+
+    let count = diagnostics.warnings.len();
+    let title = format!("{} warning{}", count, if count == 1 { "" } else { "s" });
+    if diagnostics.errors.is_empty() {
+        let str = conform_external_str(&diagnostics.warnings.join("\n"));
+        embed.field(title, format!("```\n{}\n```", str), false);
+    } else {
+        let str = conform_external_str(&diagnostics.errors.join("\n"));
+        embed.field("Compiler Errors", format!("```\n{}\n```", str), false);
+        embed.field(title, "Fix the errors first to see them", false);
     }
-    if !res.signal.is_empty() {
-        embed.field("Signal", &res.signal, false);
+}
 
-        // If we received 'Signal', then the application successfully ran, but was timed out
-        // by wandbox. We should skin this as successful, so we set status to 0 (success).
-        // This is done to ensure that the checkmark is added at the end of the compile
-        // command hook.
+/// Each side's results next to each other, sides that failed to run show why instead
+pub fn build_compare_embed(author: &User, results: &[(String, Result<CompileResult, String>)]) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.title("Comparison Results");
+
+    let succeeded: Vec<&CompileResult> = results
+        .iter()
+        .filter_map(|(_, r)| r.as_ref().ok())
+        .filter(|r| r.succeeded())
+        .collect();
+    if succeeded.len() == results.len() {
         embed.color(COLOR_OKAY);
-        res.status = String::from('0');
+        if succeeded.windows(2).all(|w| w[0].stdout.trim_end() == w[1].stdout.trim_end()) {
+            embed.description("Every program printed the same output");
+        } else {
+            embed.description("The programs printed different output");
+        }
+    } else {
+        embed.color(COLOR_FAIL);
+    }
+
+    for (language, result) in results {
+        match result {
+            Ok(res) => {
+                let output = if !res.compiled() {
+                    &res.compiler_output
+                } else if res.stdout.is_empty() {
+                    &res.stderr
+                } else {
+                    &res.stdout
+                };
+                let output = if output.is_empty() { String::from("<no output>") } else { conform_external_str(output) };
+                let mark = if res.succeeded() { "✅" } else { "❌" };
+                embed.field(
+                    format!("{} {}", mark, language),
+                    format!("```\n{}\n```{}", output, res.status()),
+                    true,
+                );
+            }
+            Err(e) => {
+                embed.field(format!("❌ {}", language), conform_external_str(e), true);
+            }
+        }
+    }
+
+    embed.footer(|f| f.text(format!("Requested by: {}", author.tag())));
+    embed
+}
+
+/// Placeholder for a streamed run that's still going, showing the latest output
+pub fn build_stream_embed(author: &User, output: &str, spoiler: bool) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.color(COLOR_OKAY);
+    embed.title("Running...");
+
+    // the newest output is what's interesting while the program is still running
+    let skip = output.chars().count().saturating_sub(MAX_OUTPUT_LEN);
+    let latest: String = output.chars().skip(skip).collect();
+    if !latest.is_empty() {
+        embed.field("Program Output (so far)", program_output(&latest, spoiler), false);
     }
-    if !res.compiler_all.is_empty() {
-        let str = conform_external_str(&res.compiler_all);
-        embed.field("Compiler Output", format!("```{}\n```", str), false);
+    embed.footer(|f| f.text(format!("Requested by: {}", author.tag())));
+    embed
+}
+
+// Compile-only requests stop before linking where the toolchain can (see syntax::compile_only_flag),
+// languages that can't be built without running them still run, so only the compiler is reported on
+fn build_compile_only_embed(author: &User, res: &CompileResult, flags: FlagSource) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+
+    let failed = !res.compiled();
+    if failed {
+        embed.color(COLOR_FAIL);
+        embed.field("Status", "Compilation failed", false);
+    } else {
+        embed.color(COLOR_OKAY);
+        embed.field("Status", "Compiled successfully", false);
     }
-    if !res.program_all.is_empty() {
-        let str = conform_external_str(&res.program_all);
-        embed.field("Program Output", format!("```\n{}\n```", str), false);
+
+    if !res.compiler_output.is_empty() {
+        add_compiler_output(&mut embed, &res.compiler_output);
+        if failed {
+            if let Some(hint) = hints::find_hint(&res.compiler_output) {
+                embed.field("Hint", hint, false);
+            }
+        }
     }
-    if !res.url.is_empty() {
-        embed.field("URL", &res.url, false);
+    if let Some(url) = &res.url {
+        embed.field("URL", url, false);
     }
 
     embed.title("Compilation Results");
-    embed.footer(|f| {
-        f.text(format!(
-            "Requested by: {} | Powered by wandbox.org",
-            author.tag()
-        ))
-    });
+    embed.footer(|f| f.text(compilation_footer(author, res, flags)));
     embed
 }
 
+fn compilation_footer(author: &User, res: &CompileResult, flags: FlagSource) -> String {
+    let mut footer = format!("Requested by: {} | Powered by {}.org", author.tag(), res.backend);
+    if flags != FlagSource::None {
+        footer.push_str(&format!(" | Flags: {}", flags));
+    }
+    if !res.libraries.is_empty() {
+        footer.push_str(&format!(" | Libraries: {}", res.libraries.join(", ")));
+    }
+    if !res.auto_flags.is_empty() {
+        footer.push_str(&format!(" | Auto flags: {}", res.auto_flags.join(" ")));
+    }
+    if let Some(runtime) = &res.runtime {
+        footer.push_str(&format!(" | Runtime: {}", runtime));
+    }
+    if let Some(bytes) = res.stdin_bytes {
+        footer.push_str(&format!(" | Input: {} bytes", bytes));
+    }
+    if res.timing {
+        footer.push_str(&format!(" | {}", res.timing_summary()));
+    }
+    footer
+}
+
+pub fn add_truncation_note(embed: &mut CreateEmbed, res: &CompileResult) {
+    let note = if res.url.is_some() {
+        "The output was too large and has been cut off, check the URL above for the rest of it"
+    } else {
+        "The output was too large and has been cut off"
+    };
+    embed.field("Note", note, false);
+}
+
+pub fn add_dm_failed_note(embed: &mut CreateEmbed) {
+    embed.field("Note", "I was unable to DM you the results, are your DMs closed?", false);
+}
+
+pub fn add_backend_fallback_note(embed: &mut CreateEmbed, reason: &str) {
+    embed.field("Note", format!("{}, so this was compiled on wandbox instead", reason), false);
+}
+
+pub fn add_onboarding_tip(embed: &mut CreateEmbed) {
+    embed.field("Tip", ONBOARDING_TIP, false);
+}
+
+/// Shows the language's logo as the thumbnail, if we have one for it
+pub fn add_language_logo(embed: &mut CreateEmbed, language: &str) {
+    if let Some(url) = logos::logo(language) {
+        embed.thumbnail(url);
+    }
+}
+
+pub fn add_execution_disabled_note(embed: &mut CreateEmbed, language: &str) {
+    embed.field(
+        "Note",
+        format!("Running {} programs is disabled, your code was only compiled", language),
+        false,
+    );
+}
+
+pub fn add_ran_anyway_note(embed: &mut CreateEmbed, language: &str) {
+    embed.field(
+        "Note",
+        format!("{} can't be compiled without running it, your program did run but its output is hidden", language),
+        false,
+    );
+}
+
+pub fn add_ignored_blocks_note(embed: &mut CreateEmbed, ignored: usize) {
+    embed.field(
+        "Note",
+        format!("{} extra code block(s) in your message were ignored", ignored),
+        false,
+    );
+}
+
+/// Lets the user know how the header blocks in their message were handled
+pub fn add_headers_note(embed: &mut CreateEmbed, headers: &[(String, bool)]) {
+    let lines: Vec<String> = headers
+        .iter()
+        .map(|(name, included)| {
+            if *included {
+                format!("`{}` was inlined where it was #included", name)
+            } else {
+                format!("`{}` was never #included, it was placed before your code", name)
+            }
+        })
+        .collect();
+    embed.field("Headers", lines.join("\n"), false);
+}
+
+// Debug view of the request we built for the user. Only the pieces listed by
+// the caller are shown, never the request itself, so nothing sensitive can
+// slip through. Spoilered to keep it out of the way of the actual results.
+pub fn add_request_details(embed: &mut CreateEmbed, details: &[(&str, String)]) {
+    let lines = details
+        .iter()
+        .map(|(key, value)| format!("{}: {}", key, value))
+        .collect::<Vec<String>>()
+        .join("\n");
+    embed.field(
+        "Request Details",
+        format!("||```\n{}\n```||", conform_external_str(&lines)),
+        false,
+    );
+}
+
 // Certain compiler outputs use unicode control characters that
 // make the user experience look nice (colors, etc). This ruins
 // the look of the compiler messages in discord, so we strip them out
@@ -196,6 +480,9 @@ pub fn build_asm_embed(author: &User, res: &godbolt::CompilationResult) -> Creat
                 format!("```\n{}```", compliant_str),
                 false,
             );
+            if let Some(hint) = hints::find_hint(&errs) {
+                embed.field("Hint", hint, false);
+            }
             return embed;
         }
     };
@@ -240,6 +527,117 @@ pub fn build_asm_embed(author: &User, res: &godbolt::CompilationResult) -> Creat
     embed
 }
 
+/// Finishers the backend timed fastest first as (language, milliseconds), then the untimed
+/// ones with how long their whole request took, then everyone who did not finish and why
+pub fn build_race_embed(author: &User, ranking: &[(String, u64)], untimed: &[(String, u64)], dnf: &[(String, String)]) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.title("Race Results");
+
+    let mut standings = String::new();
+    for (place, (language, ms)) in ranking.iter().enumerate() {
+        let position = match RACE_MEDALS.get(place) {
+            Some(medal) => String::from(*medal),
+            None => format!("{}.", place + 1),
+        };
+        standings.push_str(&format!("{} **{}** in {}ms\n", position, language, ms));
+    }
+    for (language, ms) in untimed {
+        standings.push_str(&format!("Untimed **{}**, took {}ms in total\n", language, ms));
+    }
+    for (language, _) in dnf {
+        standings.push_str(&format!("DNF **{}**\n", language));
+    }
+
+    embed.color(if ranking.is_empty() && untimed.is_empty() { COLOR_FAIL } else { COLOR_OKAY });
+    embed.description(standings);
+    for (language, reason) in dnf {
+        embed.field(format!("❌ {}", language), conform_external_str(reason), false);
+    }
+
+    embed.footer(|f| {
+        f.text(format!(
+            "Requested by: {} | Only runs the backend timed are ranked",
+            author.tag()
+        ))
+    });
+    embed
+}
+
+/// One row per optimization level, (asm lines, run time) or why the level failed
+pub fn build_optcompare_embed(author: &User, target: &str, results: &[(String, Result<(usize, Option<u64>), String>)], execute: bool) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.title(format!("Optimization Comparison: {}", target));
+
+    let mut table = if execute {
+        format!("{:<8}{:>10}{:>10}\n", "Level", "Asm lines", "Run time")
+    } else {
+        format!("{:<8}{:>10}\n", "Level", "Asm lines")
+    };
+    let mut failures = Vec::new();
+    for (level, result) in results {
+        match result {
+            Ok((lines, run_time)) if execute => {
+                let run_time = run_time.map(|ms| format!("{}ms", ms)).unwrap_or_else(|| String::from("-"));
+                table.push_str(&format!("{:<8}{:>10}{:>10}\n", level, lines, run_time));
+            }
+            Ok((lines, _)) => table.push_str(&format!("{:<8}{:>10}\n", level, lines)),
+            Err(e) => {
+                table.push_str(&format!("{:<8}{:>10}\n", level, "failed"));
+                failures.push((level, e));
+            }
+        }
+    }
+
+    embed.color(if failures.len() == results.len() { COLOR_FAIL } else { COLOR_OKAY });
+    embed.description(format!("```\n{}```", table));
+    for (level, error) in failures {
+        embed.field(format!("❌ {}", level), conform_external_str(error), false);
+    }
+
+    embed.footer(|f| {
+        f.text(format!(
+            "Requested by: {} | Powered by godbolt.org",
+            author.tag()
+        ))
+    });
+    embed
+}
+
+pub fn add_execution_fields(embed: &mut CreateEmbed, exec: &CompileResult) {
+    let code = match exec.exit_code {
+        Some(c) => c,
+        None => {
+            embed.field(
+                "Execution",
+                "The program was not executed, this compiler may not support execution",
+                false,
+            );
+            return;
+        }
+    };
+
+    if !exec.stdout.trim().is_empty() {
+        embed.field(
+            "Program Output (stdout)",
+            program_output(&exec.stdout, exec.spoiler),
+            false,
+        );
+    }
+
+    if !exec.stderr.trim().is_empty() {
+        embed.field(
+            "Program Errors (stderr)",
+            program_output(&exec.stderr, exec.spoiler),
+            false,
+        );
+    }
+
+    embed.field("Exit Code", code, true);
+    if code != 0 {
+        embed.color(COLOR_FAIL);
+    }
+}
+
 pub async fn manual_dispatch(http: Arc<Http>, id: u64, emb: CreateEmbed) {
     match serenity::model::id::ChannelId(id)
         .send_message(&http, |m| {
@@ -255,13 +653,248 @@ pub async fn manual_dispatch(http: Arc<Http>, id: u64, emb: CreateEmbed) {
     };
 }
 
-pub fn embed_message(emb: CreateEmbed) -> CreateMessage<'static> {
-    let mut msg = CreateMessage::default();
-    msg.embed(|e| {
-        e.0 = emb.0;
-        e
-    });
-    msg
+/// Reacts to the message in place of a reply we were unable to send, reactions only
+/// need the add reactions permission. If even that fails all we can do is log it
+pub async fn react_fallback(ctx: &Context, msg: &Message, emoji: &str) {
+    let reaction = ReactionType::Unicode(String::from(emoji));
+    if let Err(e) = msg.react(&ctx.http, reaction).await {
+        warn!("Unable to let {} know in {} that their request was refused: {}", msg.author.tag(), msg.channel_id.0, e);
+    }
+}
+
+/// Sends the embed to the user's DMs, None if they can't be reached there
+pub async fn send_dm(ctx: &Context, user: &User, emb: CreateEmbed) -> Option<Message> {
+    let channel = match user.create_dm_channel(&ctx.http).await {
+        Ok(c) => c,
+        Err(e) => {
+            warn!("Unable to open a DM channel with {}: {}", user.tag(), e);
+            return None;
+        }
+    };
+
+    // closed DMs only show up once we try to send something
+    send_embed(channel.id, &ctx.http, emb).await.ok()
+}
+
+/// Deletes the command message if the guild has opted into it, returning whether it was deleted.
+/// Missing permissions aren't the user's problem, so failures are silently ignored
+pub async fn auto_delete_command(ctx: &Context, msg: &Message) -> bool {
+    let guild_id = match msg.guild_id {
+        Some(g) => g.0,
+        None => return false,
+    };
+
+    let enabled = {
+        let data = ctx.data.read().await;
+        let settings = data.get::<SettingsCache>().unwrap().read().await;
+        settings.auto_delete_command(guild_id)
+    };
+
+    enabled && msg.delete(&ctx.http).await.is_ok()
+}
+
+/// Whether this is the first compilation the user has ever ran with us, guilds
+/// that opted out of tips don't count so the user still gets it elsewhere
+pub async fn first_compile(ctx: &Context, msg: &Message) -> bool {
+    let data = ctx.data.read().await;
+    let mut settings = data.get::<SettingsCache>().unwrap().write().await;
+    if let Some(guild) = msg.guild_id {
+        if !settings.tips_enabled(guild.0) {
+            return false;
+        }
+    }
+
+    let first = settings.onboard(msg.author.id.0);
+    if first {
+        settings.write();
+    }
+    first
+}
+
+/// Whether successful compilations in this guild are boiled down to a reaction
+pub async fn minimal_mode(ctx: &Context, guild_id: Option<GuildId>) -> bool {
+    let guild_id = match guild_id {
+        Some(g) => g.0,
+        None => return false,
+    };
+
+    let data = ctx.data.read().await;
+    let settings = data.get::<SettingsCache>().unwrap().read().await;
+    settings.minimal_mode(guild_id)
+}
+
+/// Whether compile commands in this guild are acknowledged with ACK_EMOJI, DMs never are
+pub async fn ack_reaction(ctx: &Context, guild_id: Option<GuildId>) -> bool {
+    let guild_id = match guild_id {
+        Some(g) => g.0,
+        None => return false,
+    };
+
+    let data = ctx.data.read().await;
+    let settings = data.get::<SettingsCache>().unwrap().read().await;
+    settings.ack_reaction(guild_id)
+}
+
+/// Lets the user know their command was seen while it waits on the backend, see ack_reaction.
+/// It's only a courtesy, so missing permissions are ignored
+pub async fn acknowledge(ctx: &Context, msg: &Message) {
+    if !ack_reaction(ctx, msg.guild_id).await {
+        return;
+    }
+
+    let reaction = ReactionType::Unicode(String::from(ACK_EMOJI));
+    if let Err(e) = msg.react(&ctx.http, reaction).await {
+        debug!("Unable to acknowledge {} in {}: {}", msg.id.0, msg.channel_id.0, e);
+    }
+}
+
+/// Removes our reaction left by acknowledge
+pub async fn clear_acknowledgement(ctx: &Context, msg: &Message) {
+    if !ack_reaction(ctx, msg.guild_id).await {
+        return;
+    }
+
+    let reaction = ReactionType::Unicode(String::from(ACK_EMOJI));
+    if let Err(e) = msg.delete_reaction(&ctx.http, None, reaction).await {
+        debug!("Unable to clear acknowledgement of {} in {}: {}", msg.id.0, msg.channel_id.0, e);
+    }
+}
+
+/// Whether flags may be added for headers that need them, DMs always get them
+pub async fn auto_flags_enabled(ctx: &Context, guild_id: Option<GuildId>) -> bool {
+    let guild_id = match guild_id {
+        Some(g) => g.0,
+        None => return true,
+    };
+
+    let data = ctx.data.read().await;
+    let settings = data.get::<SettingsCache>().unwrap().read().await;
+    settings.auto_flags_enabled(guild_id)
+}
+
+/// Whether results in this guild should carry the language's logo, DMs always do
+pub async fn logos_enabled(ctx: &Context, guild_id: Option<GuildId>) -> bool {
+    let guild_id = match guild_id {
+        Some(g) => g.0,
+        None => return true,
+    };
+
+    let data = ctx.data.read().await;
+    let settings = data.get::<SettingsCache>().unwrap().read().await;
+    settings.logos_enabled(guild_id)
+}
+
+/// The prefix to show users in a guild, its own if it has one & otherwise our default
+pub async fn command_prefix(ctx: &Context, guild_id: Option<GuildId>) -> String {
+    let default = env::var("BOT_PREFIX").unwrap_or_default();
+    let guild_id = match guild_id {
+        Some(g) => g.0,
+        None => return default,
+    };
+
+    let data = ctx.data.read().await;
+    let settings = data.get::<SettingsCache>().unwrap().read().await;
+    settings.prefix(guild_id).unwrap_or(default)
+}
+
+/// Resolves the accent color to use in a guild, falling back to our own
+pub async fn theme_color(ctx: &Context, guild_id: Option<GuildId>) -> i32 {
+    let guild_id = match guild_id {
+        Some(g) => g.0,
+        None => return COLOR_OKAY,
+    };
+
+    let data = ctx.data.read().await;
+    let settings = data.get::<SettingsCache>().unwrap().read().await;
+    settings.theme(guild_id).unwrap_or(COLOR_OKAY)
+}
+
+/// Swaps our default color for the guild's theme, errors are left red for clarity
+pub fn apply_theme(embed: &mut CreateEmbed, theme: i32) {
+    let is_default = embed
+        .0
+        .get("color")
+        .and_then(|c| c.as_u64())
+        .map(|c| c == COLOR_OKAY as u64)
+        .unwrap_or(false);
+    if is_default {
+        embed.color(theme);
+    }
+}
+
+/// Parses a '#RRGGBB' (or 'RRGGBB') color
+pub fn parse_hex_color(input: &str) -> Option<i32> {
+    let hex = input.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    i32::from_str_radix(hex, 16).ok()
+}
+
+/// Remembers the request behind an output so it can be reported by users later
+pub async fn record_output(ctx: &Context, output: &Message, record: OutputRecord) {
+    let data = ctx.data.read().await;
+    {
+        let mut records = data.get::<OutputRecordCache>().unwrap().lock().await;
+        records.insert(output.id.0, record);
+    }
+
+    // every output passes through here, so this is where they're queued up to expire
+    if let Some(guild_id) = output.guild_id {
+        let expiry = data.get::<SettingsCache>().unwrap().read().await.output_expiry(guild_id.0);
+        if let Some(minutes) = expiry {
+            let due = chrono::Utc::now() + chrono::Duration::minutes(minutes as i64);
+            data.get::<ExpiryCache>().unwrap().lock().await.add(output.channel_id.0, output.id.0, due);
+        }
+    }
+}
+
+/// Sends the embed to the channel, handing back the message so it can be edited or deleted later
+pub async fn send_embed(channel: ChannelId, http: &Http, emb: CreateEmbed) -> serenity::Result<Message> {
+    channel
+        .send_message(http, |m| {
+            m.embed(|e| {
+                e.0 = emb.0;
+                e
+            })
+        })
+        .await
+}
+
+/// Puts new results in place of the ones an edited command got the first time, see
+/// events::rerun_edited. None if the command was never answered, or its results have
+/// since been deleted, either way fresh results should be sent instead
+pub async fn replace_output(ctx: &Context, command: &Message, emb: &CreateEmbed) -> Option<Message> {
+    let previous = {
+        let data = ctx.data.read().await;
+        let mut delete_cache = data.get::<MessageDeleteCache>().unwrap().lock().await;
+        delete_cache.get_mut(&command.id.0).cloned()
+    };
+    let mut previous = previous?;
+
+    // a failed edit falls back to a new message, the old one must keep its reactions then
+    if previous.edit(&ctx.http, |m| m.content("").embed(|mut e| { e.0 = emb.0.clone(); e })).await.is_err() {
+        return None;
+    }
+
+    // the old success & rerun reactions may no longer apply, they're added again afterwards
+    if previous.delete_reactions(&ctx.http).await.is_err() {
+        // without manage messages they stay, the results themselves are still current
+    }
+    Some(previous)
+}
+
+/// Remembers what an answered command said, so events::rerun_edited can tell whether an edit
+/// changed it. Only results replace_output can update in place are ran again on an edit,
+/// streamed, DMed or threaded ones would leave the old results behind, so those are forgotten
+pub async fn record_command(ctx: &Context, command: &Message, rerunnable: bool) {
+    let data = ctx.data.read().await;
+    let mut contents = data.get::<CommandContentCache>().unwrap().lock().await;
+    if rerunnable {
+        contents.insert(command.id.0, command.content.clone());
+    } else {
+        contents.remove(&command.id.0);
+    }
 }
 
 pub fn build_dblvote_embed(tag: String) -> CreateEmbed {
@@ -272,6 +905,19 @@ pub fn build_dblvote_embed(tag: String) -> CreateEmbed {
     embed
 }
 
+pub fn build_vote_thanks_embed() -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.color(COLOR_OKAY);
+    embed.title("Thanks for voting!");
+    embed.description(format!(
+        "Your compile cooldown is reduced to {}% for the next {} hours, see you next vote!",
+        vote_cooldown(),
+        VOTE_REWARD_DURATION / 3600
+    ));
+    embed.thumbnail(ICON_VOTE);
+    embed
+}
+
 pub fn build_invite_embed(invite_link : &str) -> CreateEmbed {
     let mut embed = CreateEmbed::default();
     embed.title("Invite Link");
@@ -287,8 +933,21 @@ pub fn build_join_embed(guild: &Guild) -> CreateEmbed {
     embed.title("Guild joined");
     embed.color(COLOR_OKAY);
     embed.field("Name", guild.name.clone(), true);
-    embed.field("Members", guild.member_count, true);
+    // partial guild data leaves the member count at zero
+    if guild.member_count > 0 {
+        embed.field("Members", guild.member_count, true);
+    } else {
+        embed.field("Members", "Unknown", true);
+    }
     embed.field("Channels", guild.channels.len(), true);
+    if let Some(ratio) = membership::bot_ratio(guild) {
+        let percentage = (ratio.ratio() * 100.0).round();
+        if ratio.is_complete() {
+            embed.field("Bots", format!("{}%", percentage), true);
+        } else {
+            embed.field("Bots", format!("~{}% of {} sampled", percentage, ratio.sampled), true);
+        }
+    }
     if let Some(icon) = guild.icon_url() {
         embed.thumbnail(icon);
     }
@@ -305,9 +964,118 @@ pub fn build_leave_embed(guild: &GuildId) -> CreateEmbed {
     embed
 }
 
-pub fn build_complog_embed(
-    success: bool,
-    input_code: &str,
+pub fn build_maintenance_embed(maintenance: &Maintenance, started: bool) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    if started {
+        embed.title("Maintenance started");
+        embed.color(COLOR_FAIL);
+        embed.description(format!(
+            "Compiling is unavailable until {} UTC\n\n{}",
+            maintenance.ends().format("%Y-%m-%d %H:%M"),
+            maintenance.reason
+        ));
+    } else {
+        embed.title("Maintenance finished");
+        embed.color(COLOR_OKAY);
+        embed.description(format!("We're back! Thanks for your patience\n\n{}", maintenance.reason));
+    }
+    embed
+}
+
+pub fn build_schedule_embed(schedule: &Schedule) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.title("Scheduled maintenance");
+    embed.color(COLOR_OKAY);
+
+    if let Some(active) = schedule.active() {
+        embed.field(
+            "In progress",
+            format!("Until {} UTC: {}", active.ends().format("%Y-%m-%d %H:%M"), active.reason),
+            false,
+        );
+    }
+
+    if schedule.pending().is_empty() {
+        embed.description("Nothing is scheduled");
+        return embed;
+    }
+
+    let mut description = String::new();
+    for (i, maintenance) in schedule.pending().iter().enumerate() {
+        let line = format!(
+            "**{}**) `{}` UTC for {} minutes: {}\n",
+            i + 1,
+            maintenance.starts().format("%Y-%m-%d %H:%M"),
+            maintenance.minutes,
+            conform_external_str(&maintenance.reason)
+        );
+        if description.len() + line.len() > 2000 {
+            break;
+        }
+        description.push_str(&line);
+    }
+    embed.description(description);
+    embed
+}
+
+pub fn build_recent_guilds_embed(log: &GuildLog, amount: usize) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.title("Recent guild activity");
+    embed.color(COLOR_OKAY);
+
+    if log.is_empty() {
+        embed.description("No guilds have been joined or left since startup");
+        return embed;
+    }
+
+    let mut description = String::new();
+    for event in log.recent().take(amount) {
+        let name = match &event.name {
+            Some(n) => conform_external_str(n),
+            None => String::from("Unknown"),
+        };
+        let members = match event.members {
+            Some(m) => format!("{} members", m),
+            None => String::from("unknown members"),
+        };
+        let line = format!(
+            "`{}` {} **{}** (`{}`), {}\n",
+            event.time.format("%Y-%m-%d %H:%M:%S"),
+            if event.joined { "Joined" } else { "Left" },
+            name,
+            event.id,
+            members
+        );
+        if description.len() + line.len() > 2000 {
+            break;
+        }
+        description.push_str(&line);
+    }
+
+    embed.description(description);
+    embed.footer(|f| f.text(format!("{} event(s) recorded", log.len())));
+    embed
+}
+
+/// A guild's own audit log entry, the code is left out so members' code stays private
+pub fn build_guild_complog_embed(success: bool, lang: &str, author: &User, channel: ChannelId) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    if success {
+        embed.color(COLOR_OKAY);
+    } else {
+        embed.color(COLOR_FAIL);
+    }
+    embed.title("Compilation requested");
+    embed.field("Author", format!("<@{}> ({})", author.id.0, author.tag()), true);
+    embed.field("Language", lang, true);
+    embed.field("Channel", format!("<#{}>", channel.0), true);
+    embed.field("Result", if success { "Succeeded" } else { "Failed" }, true);
+    embed
+}
+
+pub fn build_complog_embed(
+    success: bool,
+    input_code: &str,
     lang: &str,
     tag: &str,
     guild: &str,
@@ -333,11 +1101,470 @@ pub fn build_complog_embed(
 
 pub async fn send_global_presence(shard_manager : &MutexGuard<'_, ShardManager>, sum : u64) {
     // update shard guild count & presence
-    let presence_str = format!("in {} servers | ;invite", sum);
+    let kind = presence_type();
+    let presence_str = match kind {
+        ActivityType::Playing => format!("in {} servers | ;invite", sum),
+        _ => format!("{} servers | ;invite", sum),
+    };
+
+    // stagger each shard's update by a random amount so we don't
+    // hit the gateway with every shard at the exact same moment
+    let jitter = presence_jitter();
+    let runners = shard_manager.runners.lock().await;
+    for (_, v) in runners.iter() {
+        let mut activity = Activity::playing(&presence_str);
+        activity.kind = kind;
+        if jitter == 0 {
+            v.runner_tx.set_presence(Some(activity), OnlineStatus::Online);
+            continue;
+        }
+
+        let messenger = v.runner_tx.clone();
+        let delay = rand::thread_rng().gen_range(0, jitter);
+        tokio::spawn(async move {
+            tokio::time::delay_for(Duration::from_millis(delay)).await;
+            messenger.set_presence(Some(activity), OnlineStatus::Online);
+        });
+    }
+}
 
+/// Shows the owners' custom presence on every shard, see ;setpresence
+pub async fn send_custom_presence(shard_manager : &MutexGuard<'_, ShardManager>, text : &str) {
+    let kind = presence_type();
     let runners = shard_manager.runners.lock().await;
     for (_, v) in runners.iter() {
-        v.runner_tx.set_presence(Some(Activity::playing(&presence_str)), OnlineStatus::Online);
+        let mut activity = Activity::playing(text);
+        activity.kind = kind;
+        v.runner_tx.set_presence(Some(activity), OnlineStatus::Online);
+    }
+}
+
+/// Updates our presence, a custom presence set by the owners takes precedence over the server count
+pub async fn refresh_presence(data : &TypeMap, shard_manager : &MutexGuard<'_, ShardManager>, sum : u64) {
+    let custom = data.get::<SettingsCache>().unwrap().read().await.presence().map(String::from);
+    match custom {
+        Some(text) => send_custom_presence(shard_manager, &text).await,
+        None => send_global_presence(shard_manager, sum).await,
+    }
+}
+
+/// Lets everyone know why we're slow while discord has us globally rate limited
+pub async fn send_ratelimit_presence(shard_manager : &MutexGuard<'_, ShardManager>) {
+    let runners = shard_manager.runners.lock().await;
+    for (_, v) in runners.iter() {
+        let activity = Activity::playing("rate limited, replies may be slow");
+        v.runner_tx.set_presence(Some(activity), OnlineStatus::Idle);
+    }
+}
+
+/// How long a request to discord may take before we consider ourselves
+/// globally rate limited, in milliseconds. Zero disables the check
+pub fn ratelimit_threshold() -> u64 {
+    env::var("RATELIMIT_THRESHOLD")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_RATELIMIT_THRESHOLD)
+}
+
+pub fn parse_presence_type(input: &str) -> Option<ActivityType> {
+    match input.trim().to_lowercase().as_str() {
+        "playing" => Some(ActivityType::Playing),
+        "watching" => Some(ActivityType::Watching),
+        "listening" => Some(ActivityType::Listening),
+        "competing" => Some(ActivityType::Competing),
+        _ => None,
+    }
+}
+
+/// The activity type shown in our presence, defaults to playing
+pub fn presence_type() -> ActivityType {
+    env::var("PRESENCE_TYPE")
+        .ok()
+        .and_then(|v| parse_presence_type(&v))
+        .unwrap_or(ActivityType::Playing)
+}
+
+/// Base interval between periodic presence refreshes, in seconds
+pub fn presence_interval() -> u64 {
+    env::var("PRESENCE_INTERVAL")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(PRESENCE_INTERVAL)
+}
+
+/// Maximum random delay applied to presence updates, in milliseconds
+pub fn presence_jitter() -> u64 {
+    env::var("PRESENCE_JITTER")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(PRESENCE_JITTER)
+}
+
+/// Status shown for successful runs that printed nothing, NO_OUTPUT_MESSAGE=off keeps the plain exit code
+pub fn no_output_message() -> Option<String> {
+    match env::var("NO_OUTPUT_MESSAGE") {
+        Ok(v) if v.eq_ignore_ascii_case("off") => None,
+        Ok(v) if !v.trim().is_empty() => Some(v),
+        _ => Some(String::from(DEFAULT_NO_OUTPUT_MESSAGE)),
+    }
+}
+
+/// Whether guild joins & leaves get logged, busy bots can set LOG_GUILD_EVENTS=off to keep quiet.
+/// Stats, the join log channel and top.gg are kept up to date either way
+pub fn log_guild_events() -> bool {
+    match env::var("LOG_GUILD_EVENTS") {
+        Ok(v) => !(v == "0" || v.eq_ignore_ascii_case("off") || v.eq_ignore_ascii_case("false")),
+        Err(_) => true,
+    }
+}
+
+/// Interval between cache size reports in the log, in minutes. Zero disables them
+pub fn cache_stats_interval() -> u64 {
+    env::var("CACHE_STATS_INTERVAL")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(CACHE_STATS_INTERVAL)
+}
+
+pub fn build_recent_errors_embed(errors: &ErrorLog) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.title("Recent errors");
+    embed.color(COLOR_FAIL);
+    embed.thumbnail(ICON_FAIL);
+
+    if errors.is_empty() {
+        embed.color(COLOR_OKAY);
+        embed.description("No errors have been recorded, nice!");
+        return embed;
+    }
+
+    // newest first, stopping before we hit discord's description limit
+    let mut description = String::new();
+    for entry in errors.recent() {
+        let line = format!(
+            "`{}` **{}**\n{}\n",
+            entry.time.format("%Y-%m-%d %H:%M:%S"),
+            entry.context,
+            conform_external_str(&entry.message)
+        );
+        if description.len() + line.len() > 2000 {
+            break;
+        }
+        description.push_str(&line);
+    }
+
+    embed.description(description);
+    embed.footer(|f| f.text(format!("{} error(s) recorded", errors.len())));
+    embed
+}
+
+// counters only outlive a restart while they're being flushed to disk
+fn stats_footer() -> &'static str {
+    if statsmanager::guild_stats_flush_interval() == 0 {
+        "Statistics are kept in memory and reset when the bot restarts"
+    } else {
+        "Statistics are saved every few minutes and kept across restarts"
+    }
+}
+
+pub fn build_guild_stats_embed(guild_name: &str, stats: Option<&GuildStats>) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.title(format!("{} statistics", guild_name));
+    embed.color(COLOR_OKAY);
+
+    let stats = match stats {
+        Some(s) => s,
+        None => {
+            embed.description("Nothing has been recorded for this server yet");
+            return embed;
+        }
+    };
+
+    let format_top = |entries: Vec<(&str, u64)>| {
+        if entries.is_empty() {
+            return String::from("None");
+        }
+        entries
+            .iter()
+            .map(|(name, count)| format!("{} - {}", name, count))
+            .collect::<Vec<String>>()
+            .join("\n")
+    };
+
+    embed.field("Compilations", stats.compilations, false);
+    embed.field("Top commands", format!("```\n{}\n```", format_top(stats.top_commands(5))), true);
+    embed.field("Top languages", format!("```\n{}\n```", format_top(stats.top_languages(5))), true);
+    embed.footer(|f| f.text(stats_footer()));
+    embed
+}
+
+/// Entries are (guild name, compilations), guilds we're no longer in are named by their id
+pub fn build_top_guilds_embed(guilds: &[(String, u64)]) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.title("Most active servers");
+    embed.color(COLOR_OKAY);
+
+    if guilds.is_empty() {
+        embed.description("Nothing has been recorded yet");
+        return embed;
+    }
+
+    let lines = guilds
+        .iter()
+        .enumerate()
+        .map(|(i, (name, count))| format!("{}. {} - {}", i + 1, name, count))
+        .collect::<Vec<String>>()
+        .join("\n");
+    embed.description(format!("```\n{}\n```", conform_external_str(&lines)));
+    embed.footer(|f| f.text(stats_footer()));
+    embed
+}
+
+pub fn build_docs_embed(author: &User, language: &str, query: Option<&str>, link: &str) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.color(COLOR_OKAY);
+    match query {
+        Some(q) => embed.title(format!("{} documentation for '{}'", language, q)),
+        None => embed.title(format!("{} documentation", language)),
+    };
+    embed.url(link);
+    embed.description(link);
+    embed.footer(|f| f.text(format!("Requested by: {}", author.tag())));
+    embed
+}
+
+pub fn build_backends_embed(author: &User, current: &str) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.color(COLOR_OKAY);
+    embed.title("Compilation backends");
+    for (name, description) in BACKENDS.iter() {
+        let title = if *name == current { format!("{} (in use)", name) } else { String::from(*name) };
+        embed.field(title, *description, false);
+    }
+    embed.footer(|f| f.text(format!("Requested by: {}", author.tag())));
+    embed
+}
+
+pub fn build_programs_embed(author: &User, programs: &[(&str, Vec<&str>)], version: u32) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.color(COLOR_OKAY);
+    embed.title("Example programs");
+    embed.description("Run one with `;example <name> [language]`");
+    for (name, languages) in programs {
+        embed.field(name, languages.join(", "), true);
+    }
+    embed.footer(|f| f.text(format!("Requested by: {} | Example set v{}", author.tag(), version)));
+    embed
+}
+
+pub fn build_features_embed(author: &User, guild: &[(&str, String)], global: Option<&[(&str, String)]>) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.color(COLOR_OKAY);
+    embed.title("Server features");
+    for (name, value) in guild {
+        embed.field(name, value, true);
+    }
+    if let Some(global) = global {
+        let description = global
+            .iter()
+            .map(|(name, value)| format!("**{}**: {}", name, value))
+            .collect::<Vec<String>>()
+            .join("\n");
+        embed.field("Global (owners only)", description, false);
+    }
+    embed.footer(|f| f.text(format!("Requested by: {}", author.tag())));
+    embed
+}
+
+pub fn build_libraries_embed(author: &User, language: &str, available: &[(&str, Vec<&str>)]) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.color(COLOR_OKAY);
+    embed.title(format!("{} libraries", language));
+    for (backend, libraries) in available {
+        let value = if libraries.is_empty() {
+            String::from("Not supported on this backend")
+        } else {
+            libraries.join(", ")
+        };
+        embed.field(backend, value, false);
+    }
+    embed.footer(|f| f.text(format!("Requested by: {}", author.tag())));
+    embed
+}
+
+pub fn build_version_embed(author: &User, target: &str, compiler: &wandbox::Compiler) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.color(COLOR_OKAY);
+    embed.title(format!("{} runtime", target));
+    embed.field("Compiler", &compiler.name, true);
+    embed.field("Language", &compiler.language, true);
+    embed.field("Version", format!("{} {}", compiler.display_name, compiler.version), false);
+    embed.footer(|f| f.text(format!("Requested by: {}", author.tag())));
+    embed
+}
+
+pub fn build_fmtdiff_embed(author: &User, formatter: &str, diff: Option<&str>) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.color(COLOR_OKAY);
+    embed.title(format!("{} changes", formatter));
+    match diff {
+        Some(diff) => {
+            // keep our code block intact, same as we do for compiler output
+            let mut diff = diff.replace("`", "\u{200B}`");
+            if diff.len() > MAX_DIFF_LEN {
+                diff = diff.chars().take(MAX_DIFF_LEN).collect();
+                diff.push_str("\n... (diff truncated)");
+            }
+            embed.description(format!("```diff\n{}\n```", diff))
+        }
+        None => embed.description("No changes needed, your code is already formatted"),
+    };
+    embed.footer(|f| f.text(format!("Requested by: {}", author.tag())));
+    embed
+}
+
+pub fn build_service_alert_embed(alert: &ServiceAlert) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.title(format!("{} is unreachable", alert.service));
+    embed.color(COLOR_FAIL);
+    embed.thumbnail(ICON_FAIL);
+    embed.description(format!(
+        "The last {} posts to {} have failed over the past {} minutes. \
+        I won't mention it again until it recovers.",
+        alert.failures,
+        alert.service,
+        alert.failing_for.as_secs() / 60
+    ));
+    embed.field(
+        "Last error",
+        format!("```\n{}\n```", conform_external_str(&alert.last_error)),
+        false,
+    );
+    embed
+}
+
+pub fn build_failure_rate_embed(guild_id: u64, alert: &FailureRateAlert) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.title("Unusual compilation failure rate");
+    embed.color(COLOR_FAIL);
+    embed.thumbnail(ICON_FAIL);
+    embed.description(format!(
+        "{} of the last {} compilations ({}%) in this server have failed within {} minutes",
+        alert.failures,
+        alert.compilations,
+        alert.percentage(),
+        alert.window.as_secs() / 60
+    ));
+    embed.field("Guild", guild_id, true);
+    embed
+}
+
+/// Lets us (and optionally the moderation channel) know a guild's compilations are
+/// failing suspiciously often, this is often abuse or a misconfigured server
+pub async fn report_failure_rate(ctx: &Context, guild_id: u64, alert: &FailureRateAlert) {
+    let message = format!(
+        "{} of {} compilations ({}%) failed",
+        alert.failures,
+        alert.compilations,
+        alert.percentage()
+    );
+    warn!("High failure rate in guild {}: {}", guild_id, message);
+    {
+        let data = ctx.data.read().await;
+        let mut errors = data.get::<ErrorLogCache>().unwrap().lock().await;
+        errors.push(&format!("failure rate: {}", guild_id), &message);
+    }
+
+    if let Some(id) = env::var("MODERATION_CHANNEL").ok().and_then(|c| c.parse::<u64>().ok()) {
+        manual_dispatch(ctx.http.clone(), id, build_failure_rate_embed(guild_id, alert)).await;
+    }
+}
+
+/// The shard discord sends a guild's events to
+pub fn shard_for_guild(guild_id: u64, shard_count: u64) -> u64 {
+    (guild_id >> 22) % shard_count
+}
+
+pub fn build_shard_embed(shard_id: u64, shard_count: u64, latency: Option<Duration>, stage: &ConnectionStage) -> CreateEmbed {
+    let latency = match latency {
+        Some(l) => format!("{}ms", l.as_millis()),
+        None => String::from("Unknown"),
+    };
+
+    let mut embed = CreateEmbed::default();
+    embed.color(COLOR_OKAY);
+    embed.title(format!("Shard {} of {}", shard_id, shard_count));
+    embed.field("Latency", latency, true);
+    embed.field("Status", format!("{:?}", stage), true);
+    embed
+}
+
+pub fn build_sessions_embed(lines: &[String], page: usize, pages: usize, shard_count: usize) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.color(COLOR_OKAY);
+    embed.title(format!("Gateway sessions ({} shards)", shard_count));
+    embed.description(format!("```\n{}\n```", lines.join("\n")));
+    embed.footer(|f| f.text(format!("Page {} of {} | Sequence numbers aren't exposed by serenity", page, pages)));
+    embed
+}
+
+pub fn build_report_embed(record: &OutputRecord, output: &Message) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.color(COLOR_FAIL);
+    embed.title("Output reported");
+
+    let guild = match record.guild_id {
+        Some(g) => g.to_string(),
+        None => String::from("<DM>"),
+    };
+    let link = match record.guild_id {
+        Some(g) => format!("https://discord.com/channels/{}/{}/{}", g.0, record.channel_id.0, output.id.0),
+        None => String::from("Unavailable"),
+    };
+    let reporter = match &record.flagged_by {
+        Some(u) => format!("{} [{}]", u.tag(), u.id.0),
+        None => String::from("Unknown"),
+    };
+
+    embed.field("Requested by", format!("{} [{}]", record.requester.tag(), record.requester.id.0), true);
+    embed.field("Reported by", reporter, true);
+    embed.field("Guild", guild, true);
+    embed.field("Channel", format!("<#{}>", record.channel_id.0), true);
+    embed.field("Message", link, false);
+
+    let code = conform_external_str(&record.code);
+    embed.field("Code", format!("```{}\n{}\n```", record.language, code), false);
+    embed
+}
+
+/// Why the user's commands are refused, channel blocks have no entry of their own
+pub fn build_blocked_embed(author: &User, target: &str, entry: Option<&BlockEntry>) -> CreateEmbed {
+    let mut text = format!("{} blocked from executing commands.\n", target);
+    match entry.and_then(|e| e.reason.as_ref()) {
+        Some(reason) => text.push_str(&format!("Reason: {}\n", reason)),
+        None => text.push_str("This may have happened due to abuse, spam, or other reasons.\n"),
+    }
+    match entry.and_then(|e| e.expires_at()) {
+        Some(expires) => text.push_str(&format!(
+            "The block lifts at {} UTC, {} from now.",
+            expires.format("%Y-%m-%d %H:%M"),
+            remaining_text(expires - chrono::Utc::now())
+        )),
+        None => text.push_str("If you feel that this has been done in error, request an unban in the support server."),
+    }
+    build_fail_embed(author, &text)
+}
+
+// 1d 2h, 3h 15m or 5m, whichever two units are largest
+fn remaining_text(remaining: chrono::Duration) -> String {
+    let minutes = remaining.num_minutes().max(1);
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
     }
 }
 
@@ -350,3 +1577,141 @@ pub fn build_fail_embed(author: &User, err: &str) -> CreateEmbed {
     embed.footer(|f| f.text(format!("Requested by: {}", author.tag())));
     embed
 }
+
+/// If the author is one of the guild's supporters (patron role or booster), has
+/// recently voted for us or is compiling in a trusted guild, the percentage of the
+/// normal compile cooldown they get. When several apply the shortest wins.
+/// Worked out once per request, the hooks each ask about the same one
+pub async fn supporter_cooldown(ctx: &Context, req: &Requester<'_>) -> Option<u8> {
+    let guild_id = req.guild_id?;
+    {
+        let data = ctx.data.read().await;
+        let mut lookups = data.get::<SupporterLookupCache>().unwrap().lock().await;
+        if let Some(cooldown) = lookups.get_mut(&req.id) {
+            return *cooldown;
+        }
+    }
+
+    let trusted = if trusted::is_trusted(guild_id.0) { Some(trusted::trusted_cooldown()) } else { None };
+    let voter = voter_cooldown(ctx, req.author.id.0).await;
+    let supporter = guild_supporter_cooldown(ctx, req, guild_id).await;
+    let cooldown = [trusted, voter, supporter].iter().flatten().min().copied();
+
+    let data = ctx.data.read().await;
+    data.get::<SupporterLookupCache>().unwrap().lock().await.insert(req.id, cooldown);
+    cooldown
+}
+
+/// Percentage of the normal compile cooldown recent voters get
+pub fn vote_cooldown() -> u8 {
+    env::var("VOTER_COOLDOWN")
+        .ok()
+        .and_then(|v| v.parse::<u8>().ok())
+        .map(|v| v.min(100))
+        .unwrap_or(DEFAULT_VOTER_COOLDOWN)
+}
+
+async fn voter_cooldown(ctx: &Context, user_id: u64) -> Option<u8> {
+    if !is_recent_voter(ctx, user_id).await {
+        return None;
+    }
+    Some(vote_cooldown())
+}
+
+/// Whether the user voted for us on top.gg within the last VOTE_REWARD_DURATION
+pub async fn is_recent_voter(ctx: &Context, user_id: u64) -> bool {
+    let data = ctx.data.read().await;
+    let mut voters = data.get::<VoterCache>().unwrap().lock().await;
+    match voters.get_mut(&user_id) {
+        Some(voted) => voted.elapsed() < Duration::from_secs(VOTE_REWARD_DURATION),
+        None => false,
+    }
+}
+
+// Boosts can only be seen on the full member, which we ask discord for only if it isn't cached.
+// If we're unable to get it they get the normal cooldown
+async fn guild_supporter_cooldown(ctx: &Context, req: &Requester<'_>, guild_id: GuildId) -> Option<u8> {
+    let (role, boosters, cooldown) = {
+        let data = ctx.data.read().await;
+        let settings = data.get::<SettingsCache>().unwrap().read().await;
+        let guild = settings.guild(guild_id.0)?;
+        (guild.supporter_role, guild.booster_exemption, guild.supporter_cooldown)
+    };
+    let cooldown = cooldown.unwrap_or(DEFAULT_SUPPORTER_COOLDOWN);
+
+    if let (Some(role), Some(roles)) = (role, req.roles) {
+        if roles.contains(&RoleId(role)) {
+            return Some(cooldown);
+        }
+    }
+
+    if boosters {
+        let member = match ctx.cache.member(guild_id, req.author.id).await {
+            Some(m) => Some(m),
+            None => guild_id.member(&ctx.http, req.author.id).await.ok(),
+        };
+        if let Some(member) = member {
+            if member.premium_since.is_some() {
+                return Some(cooldown);
+            }
+        }
+    }
+
+    None
+}
+
+/// A member's permissions in a guild channel, overwrites included. Worked out by serenity
+/// from its cache, so guilds (& members) we haven't received over the gateway come back None
+pub async fn channel_permissions(ctx: &Context, guild_id: GuildId, channel_id: ChannelId, user_id: UserId) -> Option<Permissions> {
+    let guild = ctx.cache.guild(guild_id).await?;
+    if !guild.members.contains_key(&user_id) {
+        return None;
+    }
+    Some(guild.user_permissions_in(channel_id, user_id))
+}
+
+/// Whether the channel is one of the guild's, channels are taken from serenity's cache
+/// where it has them. A mentioned channel may well belong to some other server
+pub async fn is_guild_channel(ctx: &Context, guild_id: GuildId, channel_id: ChannelId) -> bool {
+    if let Some(channel) = ctx.cache.guild_channel(channel_id).await {
+        return channel.guild_id == guild_id;
+    }
+    match channel_id.to_channel(&ctx.http).await {
+        Ok(Channel::Guild(channel)) => channel.guild_id == guild_id,
+        _ => false,
+    }
+}
+
+// Guild owners, administrators, and anyone with 'Manage Server' are considered admins.
+// The author's roles are resolved against the guild manually, so this works for members
+// serenity's cache hasn't seen yet
+pub async fn is_admin(ctx: &Context, msg: &Message) -> bool {
+    match msg.guild_id {
+        Some(guild_id) => is_guild_admin(ctx, guild_id, msg.author.id).await,
+        None => false,
+    }
+}
+
+/// is_admin for requests that don't come from a message, e.g. interactions
+pub async fn is_guild_admin(ctx: &Context, guild_id: GuildId, user_id: UserId) -> bool {
+    let guild = match guild_id.to_partial_guild(&ctx.http).await {
+        Ok(g) => g,
+        Err(_) => return false,
+    };
+
+    if guild.owner_id == user_id {
+        return true;
+    }
+
+    let member = match guild_id.member(&ctx.http, user_id).await {
+        Ok(m) => m,
+        Err(_) => return false,
+    };
+
+    let mut roles = member.roles.clone();
+    roles.push(RoleId(guild_id.0)); // @everyone
+    roles.iter().any(|id| match guild.roles.get(id) {
+        Some(role) => role.permissions.administrator() || role.permissions.manage_guild(),
+        None => false,
+    })
+}
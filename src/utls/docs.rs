@@ -0,0 +1,77 @@
+use reqwest::Url;
+
+use crate::utls::parser;
+
+// language -> (main documentation page, search page, search query parameter)
+static DOCS: [(&str, &str, &str, &str); 7] = [
+    (
+        "c++",
+        "https://en.cppreference.com/w/cpp",
+        "https://en.cppreference.com/mwiki/index.php",
+        "search",
+    ),
+    (
+        "c",
+        "https://en.cppreference.com/w/c",
+        "https://en.cppreference.com/mwiki/index.php",
+        "search",
+    ),
+    (
+        "python",
+        "https://docs.python.org/3/",
+        "https://docs.python.org/3/search.html",
+        "q",
+    ),
+    (
+        "javascript",
+        "https://developer.mozilla.org/en-US/docs/Web/JavaScript",
+        "https://developer.mozilla.org/en-US/search",
+        "q",
+    ),
+    (
+        "rust",
+        "https://doc.rust-lang.org/std/",
+        "https://doc.rust-lang.org/std/",
+        "search",
+    ),
+    (
+        "go",
+        "https://pkg.go.dev/std",
+        "https://pkg.go.dev/search",
+        "q",
+    ),
+    (
+        "csharp",
+        "https://learn.microsoft.com/en-us/dotnet/csharp/",
+        "https://learn.microsoft.com/en-us/search/",
+        "terms",
+    ),
+];
+
+/// Maps common shorthands onto the names we know languages by
+pub fn normalize(language: &str) -> &str {
+    match language {
+        "py" => "python",
+        "js" => "javascript",
+        "rs" => "rust",
+        "golang" => "go",
+        "c#" | "cs" => "csharp",
+        other => parser::normalize_language(other),
+    }
+}
+
+/// Builds a documentation link for the language, searching for the query if there is one
+pub fn lookup(language: &str, query: Option<&str>) -> Option<String> {
+    let (_, main, search, param) = DOCS.iter().find(|(lang, _, _, _)| *lang == language)?;
+    match query {
+        Some(q) => Url::parse_with_params(search, &[(*param, q)])
+            .ok()
+            .map(String::from),
+        None => Some(String::from(*main)),
+    }
+}
+
+/// All languages we have documentation for
+pub fn languages() -> Vec<&'static str> {
+    DOCS.iter().map(|(lang, _, _, _)| *lang).collect()
+}
@@ -0,0 +1,50 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Utc};
+
+use crate::utls::constants::ERROR_LOG_SIZE;
+
+pub struct ErrorEntry {
+    pub time: DateTime<Utc>,
+    pub context: String,
+    pub message: String,
+}
+
+/// Bounded, in-memory record of the most recent errors we've seen
+#[derive(Default)]
+pub struct ErrorLog {
+    entries: VecDeque<ErrorEntry>,
+}
+
+impl ErrorLog {
+    pub fn new() -> ErrorLog {
+        ErrorLog {
+            entries: VecDeque::with_capacity(ERROR_LOG_SIZE),
+        }
+    }
+
+    pub fn push(&mut self, context: &str, message: &str) {
+        if self.entries.len() >= ERROR_LOG_SIZE {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(ErrorEntry {
+            time: Utc::now(),
+            context: String::from(context),
+            message: String::from(message),
+        });
+    }
+
+    /// Iterates from the newest entry to the oldest
+    pub fn recent(&self) -> impl Iterator<Item = &ErrorEntry> {
+        self.entries.iter().rev()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
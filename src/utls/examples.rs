@@ -0,0 +1,54 @@
+use rand::seq::SliceRandom;
+
+// Curated, known-good snippets for ;random. Each language maps to a handful
+// of small programs that show off a language feature beyond hello world.
+static EXAMPLES: [(&str, &[&str]); 5] = [
+    (
+        "c++",
+        &[
+            "#include <iostream>\n#include <vector>\n#include <algorithm>\n\nint main() {\n    std::vector<int> v{5, 3, 1, 4, 2};\n    std::sort(v.begin(), v.end());\n    for (int i : v)\n        std::cout << i << ' ';\n    std::cout << \"\\nHello, sorted world!\\n\";\n}\n",
+            "#include <iostream>\n#include <map>\n#include <string>\n\nint main() {\n    std::map<std::string, int> ages{{\"alice\", 30}, {\"bob\", 25}};\n    for (const auto& [name, age] : ages)\n        std::cout << name << \" is \" << age << '\\n';\n}\n",
+            "#include <iostream>\n\ntemplate <unsigned N>\nstruct Factorial {\n    static constexpr unsigned long long value = N * Factorial<N - 1>::value;\n};\n\ntemplate <>\nstruct Factorial<0> {\n    static constexpr unsigned long long value = 1;\n};\n\nint main() {\n    std::cout << \"10! computed at compile time: \" << Factorial<10>::value << '\\n';\n}\n",
+        ],
+    ),
+    (
+        "c",
+        &[
+            "#include <stdio.h>\n\nint main(void) {\n    int squares[5];\n    for (int i = 0; i < 5; i++)\n        squares[i] = i * i;\n    for (int i = 0; i < 5; i++)\n        printf(\"%d squared is %d\\n\", i, squares[i]);\n    return 0;\n}\n",
+            "#include <stdio.h>\n\nstruct point {\n    int x, y;\n};\n\nint main(void) {\n    struct point p = { .x = 3, .y = 4 };\n    printf(\"Hello from (%d, %d)!\\n\", p.x, p.y);\n    return 0;\n}\n",
+        ],
+    ),
+    (
+        "python",
+        &[
+            "squares = [x * x for x in range(10)]\nprint(\"Hello, squares!\", squares)\n",
+            "from collections import Counter\n\nwords = \"the quick brown fox jumps over the lazy dog the end\".split()\nfor word, count in Counter(words).most_common(3):\n    print(f\"{word}: {count}\")\n",
+            "def fib():\n    a, b = 0, 1\n    while True:\n        yield a\n        a, b = b, a + b\n\ngen = fib()\nprint([next(gen) for _ in range(10)])\n",
+        ],
+    ),
+    (
+        "rust",
+        &[
+            "fn main() {\n    let evens: Vec<u32> = (1..=10).filter(|n| n % 2 == 0).collect();\n    println!(\"Hello, evens! {:?}\", evens);\n}\n",
+            "#[derive(Debug)]\nenum Shape {\n    Circle(f64),\n    Square(f64),\n}\n\nfn area(shape: &Shape) -> f64 {\n    match shape {\n        Shape::Circle(r) => std::f64::consts::PI * r * r,\n        Shape::Square(s) => s * s,\n    }\n}\n\nfn main() {\n    for shape in &[Shape::Circle(1.0), Shape::Square(2.0)] {\n        println!(\"{:?} has area {:.2}\", shape, area(shape));\n    }\n}\n",
+        ],
+    ),
+    (
+        "javascript",
+        &[
+            "const nums = [1, 2, 3, 4, 5];\nconst doubled = nums.map(n => n * 2);\nconsole.log(`Hello, doubled! ${doubled.join(', ')}`);\n",
+            "class Greeter {\n  constructor(name) {\n    this.name = name;\n  }\n\n  greet() {\n    return `Hello, ${this.name}!`;\n  }\n}\n\nconsole.log(new Greeter('world').greet());\n",
+        ],
+    ),
+];
+
+/// Picks a random example for the given language, if we have any
+pub fn random_example(language: &str) -> Option<&'static str> {
+    let (_, examples) = EXAMPLES.iter().find(|(lang, _)| *lang == language)?;
+    examples.choose(&mut rand::thread_rng()).copied()
+}
+
+/// All languages we have examples for
+pub fn languages() -> Vec<&'static str> {
+    EXAMPLES.iter().map(|(lang, _)| *lang).collect()
+}
@@ -0,0 +1,67 @@
+use chrono::{DateTime, Utc};
+use serde::*;
+
+use crate::utls::persistence::{self, Migration};
+
+// Upgrades older expiry.json formats, see persistence::Migration
+static MIGRATIONS: [Migration; 0] = [];
+
+/// An output message waiting to be deleted, see GuildSettings::output_expiry
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PendingDelete {
+    pub channel: u64,
+    pub message: u64,
+    /// Unix timestamp the message should be gone by
+    pub due: i64,
+}
+
+// Pending deletes are kept on disk so outputs posted before a restart still get
+// cleaned up afterwards, the ones that came due while we were down go right away
+#[derive(Serialize, Deserialize, Default)]
+pub struct Expirations {
+    #[serde(default)]
+    pending: Vec<PendingDelete>,
+}
+
+impl Expirations {
+    pub fn new() -> Expirations {
+        let loaded = persistence::load_versioned("expiry.json", &MIGRATIONS)
+            .expect("Unable to load expiry.json");
+        match loaded {
+            Some(store) => store,
+            None => Expirations::create_expirations(),
+        }
+    }
+
+    pub fn add(&mut self, channel: u64, message: u64, due: DateTime<Utc>) {
+        self.pending.push(PendingDelete {
+            channel,
+            message,
+            due: due.timestamp(),
+        });
+        self.write();
+    }
+
+    /// Removes & returns every delete whose time has come
+    pub fn take_due(&mut self, now: DateTime<Utc>) -> Vec<PendingDelete> {
+        let now = now.timestamp();
+        let (due, pending): (Vec<PendingDelete>, Vec<PendingDelete>) = self.pending.drain(..).partition(|d| d.due <= now);
+        self.pending = pending;
+
+        if !due.is_empty() {
+            self.write();
+        }
+        due
+    }
+
+    pub fn write(&self) {
+        persistence::write_versioned("expiry.json", self, &MIGRATIONS)
+            .expect("Unable to create expiry.json!");
+    }
+
+    fn create_expirations() -> Expirations {
+        let expirations = Expirations::default();
+        expirations.write();
+        expirations
+    }
+}
@@ -0,0 +1,31 @@
+// Headers whose use needs an extra flag to link on the backends' toolchains, as
+// (language, header, flag). Older libstdc++ keeps std::filesystem in its own library
+static FLAG_PRESETS: [(&str, &str, &str); 7] = [
+    ("c++", "thread", "-pthread"),
+    ("c++", "future", "-pthread"),
+    ("c++", "mutex", "-pthread"),
+    ("c++", "filesystem", "-lstdc++fs"),
+    ("c++", "pthread.h", "-pthread"),
+    ("c", "pthread.h", "-pthread"),
+    ("c", "math.h", "-lm"),
+];
+
+lazy_static! {
+    static ref SYSTEM_INCLUDE: regex::Regex = regex::Regex::new(r"(?m)^\s*#\s*include\s*<(?P<header>[^>]+)>").unwrap();
+}
+
+/// The flags the code's #includes call for, without duplicates
+pub fn flags_for(language: &str, code: &str) -> Vec<String> {
+    let language = language.to_lowercase();
+
+    let mut flags: Vec<String> = Vec::new();
+    for cap in SYSTEM_INCLUDE.captures_iter(code) {
+        let header = cap["header"].trim();
+        for (lang, preset_header, flag) in FLAG_PRESETS.iter() {
+            if *lang == language && *preset_header == header && !flags.iter().any(|f| f == flag) {
+                flags.push(String::from(*flag));
+            }
+        }
+    }
+    flags
+}
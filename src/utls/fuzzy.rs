@@ -0,0 +1,42 @@
+use crate::utls::constants::{SUGGESTION_MAX_DISTANCE, SUGGESTION_MIN_SIMILARITY};
+
+/// Number of single character edits needed to turn one string into the other
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            current[j + 1] = (previous[j + 1] + 1)
+                .min(current[j] + 1)
+                .min(previous[j] + cost);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// The candidate closest to the input, as long as it's close enough to be what
+/// the user actually meant. Short or symbol heavy input (;) etc) never matches
+pub fn closest<'a>(input: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let input = input.to_lowercase();
+    if input.chars().count() < 3 || !input.chars().all(char::is_alphanumeric) {
+        return None;
+    }
+
+    let (best, distance) = candidates
+        .map(|c| (c, levenshtein(&input, c)))
+        .min_by_key(|(_, d)| *d)?;
+
+    let longest = input.chars().count().max(best.chars().count());
+    let similarity = 1.0 - distance as f64 / longest as f64;
+    if distance == 0 || distance > SUGGESTION_MAX_DISTANCE || similarity < SUGGESTION_MIN_SIMILARITY {
+        return None;
+    }
+
+    Some(best)
+}
@@ -0,0 +1,210 @@
+use serenity::model::{
+    channel::Message,
+    id::{ChannelId, GuildId, RoleId},
+    user::User,
+};
+
+use crate::utls::blocklist::BlockEntry;
+use crate::utls::schedule::Maintenance;
+
+/// Who is asking for a command & where, commands reach us as messages or interactions
+/// and both go past the same gates
+pub struct Requester<'a> {
+    /// The message or interaction, supporter lookups are worked out once per request
+    pub id: u64,
+    pub author: &'a User,
+    pub guild_id: Option<GuildId>,
+    pub channel_id: ChannelId,
+    /// The author's roles in the guild, if discord sent them along
+    pub roles: Option<&'a [RoleId]>,
+}
+
+impl<'a> From<&'a Message> for Requester<'a> {
+    fn from(msg: &'a Message) -> Requester<'a> {
+        Requester {
+            id: msg.id.0,
+            author: &msg.author,
+            guild_id: msg.guild_id,
+            channel_id: msg.channel_id,
+            roles: msg.member.as_ref().map(|m| m.roles.as_slice()),
+        }
+    }
+}
+
+/// Everything events::before found out about a command before it's allowed to run.
+/// Gathering this means going through our caches (or asking discord), deciding on it is left to check
+#[derive(Default)]
+pub struct Gates {
+    pub owner: bool,
+    /// The command is one of COMPILE_COMMANDS, most gates only apply to those
+    pub compile: bool,
+    pub blocked_user: Option<BlockEntry>,
+    pub blocked_guild: Option<BlockEntry>,
+    pub blocked_channel: bool,
+    pub maintenance: Option<Maintenance>,
+    pub kill_switch: Option<String>,
+    /// The user gets a supporter's cooldown, which restarts with every command that goes ahead
+    pub supporter: bool,
+    /// A supporter sending commands faster than their reduced cooldown allows
+    pub too_fast: bool,
+    /// Where compiling is allowed, if it's restricted to channels other than this one
+    pub restricted_to: Option<u64>,
+    /// A role the guild requires that the user doesn't have, admins are never missing one
+    pub missing_role: Option<u64>,
+    /// The guild's minimum account age in days, if the user's account is any younger
+    pub too_young: Option<u64>,
+    /// The guild has used up its requests for the minute
+    pub rate_limited: bool,
+}
+
+/// Why a command was refused, the first gate it failed
+pub enum Refusal {
+    BlockedUser(Option<BlockEntry>),
+    BlockedGuild(Option<BlockEntry>),
+    BlockedChannel,
+    Maintenance(Maintenance),
+    KillSwitch(String),
+    TooFast,
+    RestrictedChannel(u64),
+    MissingRole(u64),
+    AccountAge(u64),
+    RateLimited,
+}
+
+/// Decides whether the command may go ahead, checking the gates in order. Owners skip
+/// every one of them, a bad blocklist entry, setting or schedule should never lock us out
+pub fn check(gates: &Gates) -> Result<(), Refusal> {
+    if gates.owner {
+        return Ok(());
+    }
+
+    if gates.blocked_user.is_some() {
+        return Err(Refusal::BlockedUser(gates.blocked_user.clone()));
+    }
+    if gates.blocked_guild.is_some() {
+        return Err(Refusal::BlockedGuild(gates.blocked_guild.clone()));
+    }
+    if gates.blocked_channel {
+        return Err(Refusal::BlockedChannel);
+    }
+
+    if gates.compile {
+        if let Some(maintenance) = &gates.maintenance {
+            return Err(Refusal::Maintenance(maintenance.clone()));
+        }
+        if let Some(reason) = &gates.kill_switch {
+            return Err(Refusal::KillSwitch(reason.clone()));
+        }
+        if gates.too_fast {
+            return Err(Refusal::TooFast);
+        }
+        if let Some(channel) = gates.restricted_to {
+            return Err(Refusal::RestrictedChannel(channel));
+        }
+    }
+
+    if let Some(role) = gates.missing_role {
+        return Err(Refusal::MissingRole(role));
+    }
+
+    if gates.compile {
+        if let Some(days) = gates.too_young {
+            return Err(Refusal::AccountAge(days));
+        }
+        if gates.rate_limited {
+            return Err(Refusal::RateLimited);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> Option<BlockEntry> {
+        Some(BlockEntry { reason: Some(String::from("spam")), expires: None })
+    }
+
+    fn maintenance() -> Option<Maintenance> {
+        Some(Maintenance { start: 0, minutes: 60, reason: String::from("upgrades") })
+    }
+
+    // one set of gates per gate, each tripping only that gate
+    fn tripped() -> Vec<Gates> {
+        let compile = || Gates { compile: true, ..Default::default() };
+        vec![
+            Gates { blocked_user: entry(), ..compile() },
+            Gates { blocked_guild: entry(), ..compile() },
+            Gates { blocked_channel: true, ..compile() },
+            Gates { maintenance: maintenance(), ..compile() },
+            Gates { kill_switch: Some(String::from("incident")), ..compile() },
+            Gates { too_fast: true, ..compile() },
+            Gates { restricted_to: Some(1), ..compile() },
+            Gates { missing_role: Some(2), ..compile() },
+            Gates { too_young: Some(7), ..compile() },
+            Gates { rate_limited: true, ..compile() },
+        ]
+    }
+
+    #[test]
+    fn every_gate_refuses() {
+        for gates in tripped() {
+            assert!(check(&gates).is_err());
+        }
+    }
+
+    #[test]
+    fn owners_bypass_every_gate() {
+        for mut gates in tripped() {
+            gates.owner = true;
+            assert!(check(&gates).is_ok());
+        }
+    }
+
+    #[test]
+    fn owners_bypass_all_gates_at_once() {
+        let gates = Gates {
+            owner: true,
+            compile: true,
+            blocked_user: entry(),
+            blocked_guild: entry(),
+            blocked_channel: true,
+            maintenance: maintenance(),
+            kill_switch: Some(String::from("incident")),
+            supporter: true,
+            too_fast: true,
+            restricted_to: Some(1),
+            missing_role: Some(2),
+            too_young: Some(7),
+            rate_limited: true,
+        };
+        assert!(check(&gates).is_ok());
+    }
+
+    #[test]
+    fn open_gates_pass() {
+        assert!(check(&Gates { compile: true, ..Default::default() }).is_ok());
+    }
+
+    #[test]
+    fn compile_gates_skip_other_commands() {
+        let gates = Gates {
+            maintenance: maintenance(),
+            kill_switch: Some(String::from("incident")),
+            too_fast: true,
+            restricted_to: Some(1),
+            too_young: Some(7),
+            rate_limited: true,
+            ..Default::default()
+        };
+        assert!(check(&gates).is_ok());
+    }
+
+    #[test]
+    fn blocks_apply_to_every_command() {
+        let gates = Gates { blocked_user: entry(), ..Default::default() };
+        assert!(matches!(check(&gates), Err(Refusal::BlockedUser(_))));
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Utc};
+
+use crate::utls::constants::GUILD_LOG_SIZE;
+
+pub struct GuildEvent {
+    pub time: DateTime<Utc>,
+    pub joined: bool,
+    pub id: u64,
+    pub name: Option<String>,
+    pub members: Option<u64>,
+}
+
+/// Bounded, in-memory record of the most recent guilds we've joined & left
+#[derive(Default)]
+pub struct GuildLog {
+    entries: VecDeque<GuildEvent>,
+}
+
+impl GuildLog {
+    pub fn new() -> GuildLog {
+        GuildLog {
+            entries: VecDeque::with_capacity(GUILD_LOG_SIZE),
+        }
+    }
+
+    pub fn joined(&mut self, id: u64, name: &str, members: u64) {
+        // partial guild data leaves the member count at zero
+        let members = if members > 0 { Some(members) } else { None };
+        self.push(GuildEvent {
+            time: Utc::now(),
+            joined: true,
+            id,
+            name: Some(String::from(name)),
+            members,
+        });
+    }
+
+    /// Leaves only carry the guild's id, if we saw it join we can still fill in the rest
+    pub fn left(&mut self, id: u64) {
+        let (name, members) = match self.entries.iter().rev().find(|e| e.id == id && e.joined) {
+            Some(join) => (join.name.clone(), join.members),
+            None => (None, None),
+        };
+        self.push(GuildEvent {
+            time: Utc::now(),
+            joined: false,
+            id,
+            name,
+            members,
+        });
+    }
+
+    fn push(&mut self, event: GuildEvent) {
+        if self.entries.len() >= GUILD_LOG_SIZE {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(event);
+    }
+
+    /// Iterates from the newest entry to the oldest
+    pub fn recent(&self) -> impl Iterator<Item = &GuildEvent> {
+        self.entries.iter().rev()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
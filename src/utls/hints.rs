@@ -0,0 +1,58 @@
+use regex::Regex;
+
+// Well-known compiler/interpreter errors paired with a beginner friendly
+// explanation. These are checked in order, so more specific patterns must
+// come before the general ones. Only add patterns that are unambiguous,
+// a wrong hint is worse than no hint at all.
+static HINTS: [(&str, &str); 9] = [
+    (
+        r"(?:'(?:std::)?(?:cout|cin|cerr|endl)' was not declared|undeclared identifier '(?:cout|cin|cerr|endl)'|no member named '(?:cout|cin|cerr|endl)' in namespace 'std')",
+        "`std::cout` and friends live in `<iostream>`, make sure you `#include <iostream>` and prefix them with `std::`",
+    ),
+    (
+        r"(?:'printf' was not declared|implicit declaration of function '?printf'?)",
+        "`printf` is declared in `<stdio.h>` (or `<cstdio>` for C++), make sure you include it",
+    ),
+    (
+        r"expected ';' (?:before|after|at end of)",
+        "You're likely missing a semicolon (`;`) at the end of the line before the error",
+    ),
+    (
+        r"(?:was not declared in this scope|use of undeclared identifier)",
+        "A name is being used before it was declared, check for typos or a missing declaration/include",
+    ),
+    (
+        r"expected '\}' at end of input",
+        "You're missing a closing brace (`}`) somewhere, check that every `{` has a matching `}`",
+    ),
+    (
+        r"undefined reference to `main'",
+        "Your program has no `main` function, which is where execution starts",
+    ),
+    (
+        r"class \w+ is public, should be declared in a file named",
+        "Try declaring your main class without the `public` modifier",
+    ),
+    (
+        r"IndentationError",
+        "Python is sensitive to indentation, make sure your blocks are consistently indented",
+    ),
+    (
+        r"NameError: name '\w+' is not defined",
+        "A name is being used before it was assigned, check for typos or a missing import",
+    ),
+];
+
+lazy_static! {
+    // HINTS' patterns, in the same order
+    static ref PATTERNS: Vec<Regex> = HINTS.iter().map(|(pattern, _)| Regex::new(pattern).unwrap()).collect();
+}
+
+/// Finds a hint for the first known error pattern found in the output, if any
+pub fn find_hint(output: &str) -> Option<&'static str> {
+    PATTERNS
+        .iter()
+        .zip(HINTS.iter())
+        .find(|(re, _)| re.is_match(output))
+        .map(|(_, (_, hint))| *hint)
+}
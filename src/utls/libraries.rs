@@ -0,0 +1,51 @@
+// Friendly library names users pass with --lib, mapped to what each backend
+// calls them. Both tables are keyed by (name, language)
+
+// wandbox selects libraries through compiler switches, most of which are built
+// per compiler. {} is filled in with the name of the compiler being used
+static WANDBOX_LIBRARIES: [(&str, &str, &str); 3] = [
+    ("boost", "c++", "boost-1.73.0-{}"),
+    ("msgpack", "c++", "msgpack"),
+    ("sprout", "c++", "sprout"),
+];
+
+// godbolt wants a library id along with the version to use
+static GODBOLT_LIBRARIES: [(&str, &str, &str, &str); 4] = [
+    ("boost", "c++", "boost", "175"),
+    ("eigen", "c++", "eigen", "trunk"),
+    ("fmt", "c++", "fmt", "trunk"),
+    ("range-v3", "c++", "rangesv3", "trunk"),
+];
+
+/// Library names available for a language on the given backend
+pub fn available(backend: &str, language: &str) -> Vec<&'static str> {
+    match backend {
+        "wandbox" => WANDBOX_LIBRARIES
+            .iter()
+            .filter(|(_, lang, _)| *lang == language)
+            .map(|(name, _, _)| *name)
+            .collect(),
+        "godbolt" => GODBOLT_LIBRARIES
+            .iter()
+            .filter(|(_, lang, _, _)| *lang == language)
+            .map(|(name, _, _, _)| *name)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The wandbox switch enabling a library for the given compiler
+pub fn wandbox_switch(name: &str, language: &str, compiler: &str) -> Option<String> {
+    WANDBOX_LIBRARIES
+        .iter()
+        .find(|(n, lang, _)| n.eq_ignore_ascii_case(name) && *lang == language)
+        .map(|(_, _, switch)| switch.replace("{}", compiler))
+}
+
+/// The godbolt library id & version of a library
+pub fn godbolt_library(name: &str, language: &str) -> Option<(&'static str, &'static str)> {
+    GODBOLT_LIBRARIES
+        .iter()
+        .find(|(n, lang, _, _)| n.eq_ignore_ascii_case(name) && *lang == language)
+        .map(|(_, _, id, version)| (*id, *version))
+}
@@ -0,0 +1,129 @@
+use std::collections::VecDeque;
+use std::env;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use lru_cache::LruCache;
+use serenity::framework::standard::CommandError;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::utls::constants::{COMPILE_QUEUE_TIMEOUT, DEFAULT_GUILD_RATE_LIMIT, DEFAULT_MAX_CONCURRENT_COMPILES, GUILD_RATE_LIMIT_SIZE};
+
+/// Caps how many backend requests we have in flight at once
+pub struct CompileLimiter {
+    semaphore: Semaphore,
+    max: usize,
+    /// Requests currently waiting on a slot
+    waiting: AtomicUsize,
+}
+
+// Counts a request as waiting for as long as it's alive, even if the wait is cancelled
+struct Waiting<'a>(&'a AtomicUsize);
+
+impl<'a> Waiting<'a> {
+    fn new(counter: &'a AtomicUsize) -> Waiting<'a> {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Waiting(counter)
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl CompileLimiter {
+    pub fn new() -> CompileLimiter {
+        let max = env::var("MAX_CONCURRENT_COMPILES")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .filter(|max| *max > 0)
+            .unwrap_or(DEFAULT_MAX_CONCURRENT_COMPILES);
+
+        CompileLimiter {
+            semaphore: Semaphore::new(max),
+            max,
+            waiting: AtomicUsize::new(0),
+        }
+    }
+
+    /// Waits for a free slot, giving up if we've been queued for too long.
+    /// The slot is released once the returned permit is dropped
+    pub async fn acquire(&self) -> Result<SemaphorePermit<'_>, CommandError> {
+        let timeout = Duration::from_secs(COMPILE_QUEUE_TIMEOUT);
+        let _waiting = Waiting::new(&self.waiting);
+        match tokio::time::timeout(timeout, self.semaphore.acquire()).await {
+            Ok(permit) => Ok(permit),
+            Err(_) => Err(CommandError::from(
+                "The bot is busy right now, please try again in a moment",
+            )),
+        }
+    }
+
+    /// Roughly where a request made right now would be in the queue, None if it'd run straight away
+    pub fn queue_position(&self) -> Option<usize> {
+        if self.semaphore.available_permits() > 0 {
+            return None;
+        }
+        Some(self.waiting.load(Ordering::SeqCst) + 1)
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.max - self.semaphore.available_permits()
+    }
+
+    pub fn max(&self) -> usize {
+        self.max
+    }
+}
+
+/// Compile requests a guild may send per minute across all of its members. Zero disables the limit
+pub fn guild_rate_limit() -> usize {
+    env::var("GUILD_RATE_LIMIT")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_GUILD_RATE_LIMIT)
+}
+
+/// Sliding one minute window of each guild's compile requests, independent of per-user cooldowns
+pub struct GuildRateLimiter {
+    windows: LruCache<u64, VecDeque<Instant>>,
+}
+
+impl GuildRateLimiter {
+    pub fn new() -> GuildRateLimiter {
+        GuildRateLimiter {
+            windows: LruCache::new(GUILD_RATE_LIMIT_SIZE),
+        }
+    }
+
+    /// Whether the guild has sent fewer than `limit` requests in the last minute
+    pub fn would_allow(&mut self, guild_id: u64, limit: usize) -> bool {
+        self.window(guild_id).len() < limit
+    }
+
+    /// Records the request if the guild has sent fewer than `limit` in the last minute
+    pub fn try_acquire(&mut self, guild_id: u64, limit: usize) -> bool {
+        let window = self.window(guild_id);
+        if window.len() >= limit {
+            return false;
+        }
+        window.push_back(Instant::now());
+        true
+    }
+
+    // the guild's requests from the last minute, older ones are dropped first
+    fn window(&mut self, guild_id: u64) -> &mut VecDeque<Instant> {
+        if !self.windows.contains_key(&guild_id) {
+            self.windows.insert(guild_id, VecDeque::new());
+        }
+        let window = self.windows.get_mut(&guild_id).unwrap();
+
+        let minute = Duration::from_secs(60);
+        while window.front().map(|t| t.elapsed() >= minute).unwrap_or(false) {
+            window.pop_front();
+        }
+        window
+    }
+}
@@ -0,0 +1,23 @@
+// Logos shown as the thumbnail of compilation results, keyed by the language
+// name we resolve targets to. Languages without an entry simply go without.
+// Discord can't render svg thumbnails, so these go through wikimedia's png renderer
+static LOGOS: [(&str, &str); 10] = [
+    ("c++", "https://commons.wikimedia.org/wiki/Special:FilePath/ISO_C%2B%2B_Logo.svg?width=128"),
+    ("c", "https://commons.wikimedia.org/wiki/Special:FilePath/The_C_Programming_Language_logo.svg?width=128"),
+    ("python", "https://commons.wikimedia.org/wiki/Special:FilePath/Python-logo-notext.svg?width=128"),
+    ("rust", "https://commons.wikimedia.org/wiki/Special:FilePath/Rust_programming_language_black_logo.svg?width=128"),
+    ("javascript", "https://commons.wikimedia.org/wiki/Special:FilePath/Unofficial_JavaScript_logo_2.svg?width=128"),
+    ("typescript", "https://commons.wikimedia.org/wiki/Special:FilePath/Typescript_logo_2020.svg?width=128"),
+    ("go", "https://commons.wikimedia.org/wiki/Special:FilePath/Go_Logo_Blue.svg?width=128"),
+    ("haskell", "https://commons.wikimedia.org/wiki/Special:FilePath/Haskell-Logo.svg?width=128"),
+    ("ruby", "https://commons.wikimedia.org/wiki/Special:FilePath/Ruby_logo.svg?width=128"),
+    ("lua", "https://commons.wikimedia.org/wiki/Special:FilePath/Lua-Logo.svg?width=128"),
+];
+
+/// The logo for a language, if we have one
+pub fn logo(language: &str) -> Option<&'static str> {
+    LOGOS
+        .iter()
+        .find(|(lang, _)| lang.eq_ignore_ascii_case(language))
+        .map(|(_, url)| *url)
+}
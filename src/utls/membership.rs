@@ -0,0 +1,38 @@
+use serenity::model::guild::Guild;
+
+use crate::utls::constants::MIN_RATIO_SAMPLE;
+
+/// How many of a guild's members are bots, judged from the members discord sent us
+pub struct BotRatio {
+    pub bots: usize,
+    pub sampled: usize,
+    /// The authoritative member count, which large guilds' member lists fall well short of
+    pub total: u64,
+}
+
+impl BotRatio {
+    pub fn ratio(&self) -> f64 {
+        self.bots as f64 / self.sampled as f64
+    }
+
+    /// Whether every member was in the sample, rather than just the part discord sent
+    pub fn is_complete(&self) -> bool {
+        self.sampled as u64 >= self.total
+    }
+}
+
+/// The guild's bot ratio, or None when too few of its members were sent along to
+/// tell. Anything acting on it should mind is_complete, a partial sample is only a hint
+pub fn bot_ratio(guild: &Guild) -> Option<BotRatio> {
+    let sampled = guild.members.len();
+    let ratio = BotRatio {
+        bots: guild.members.values().filter(|m| m.user.bot).count(),
+        sampled,
+        total: guild.member_count,
+    };
+
+    if sampled == 0 || (!ratio.is_complete() && sampled < MIN_RATIO_SAMPLE) {
+        return None;
+    }
+    Some(ratio)
+}
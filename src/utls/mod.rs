@@ -2,3 +2,35 @@ pub mod constants;
 pub mod discordhelpers;
 pub mod parser;
 pub mod blocklist;
+pub mod compileresult;
+pub mod settings;
+pub mod hints;
+pub mod errorlog;
+pub mod examples;
+pub mod limiter;
+pub mod monitor;
+pub mod profiles;
+pub mod programs;
+pub mod reports;
+pub mod docs;
+pub mod fuzzy;
+pub mod diagnostics;
+pub mod diff;
+pub mod persistence;
+pub mod postprocess;
+pub mod abuse;
+pub mod trusted;
+pub mod guildlog;
+pub mod logos;
+pub mod libraries;
+pub mod schedule;
+pub mod selftest;
+pub mod sessions;
+pub mod expiry;
+pub mod throttle;
+pub mod syntax;
+pub mod membership;
+pub mod chart;
+pub mod flagpresets;
+pub mod runtimes;
+pub mod gates;
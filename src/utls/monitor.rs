@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+use std::env;
+use std::time::{Duration, Instant};
+
+use crate::utls::constants::{DEFAULT_ALERT_THRESHOLD, DEFAULT_ALERT_WINDOW};
+
+#[derive(Default)]
+struct ServiceHealth {
+    consecutive_failures: u32,
+    failing_since: Option<Instant>,
+    last_error: String,
+    alerted: bool,
+}
+
+/// A service which has been failing long enough that an owner should know about it
+pub struct ServiceAlert {
+    pub service: &'static str,
+    pub failures: u32,
+    pub failing_for: Duration,
+    pub last_error: String,
+}
+
+/// Tracks consecutive failures of the external services we post to, so owners
+/// can be told when one has been unreachable for a while instead of us
+/// quietly logging warnings forever
+#[derive(Default)]
+pub struct HealthMonitor {
+    services: HashMap<&'static str, ServiceHealth>,
+}
+
+impl HealthMonitor {
+    pub fn new() -> HealthMonitor {
+        HealthMonitor::default()
+    }
+
+    pub fn success(&mut self, service: &'static str) {
+        if let Some(health) = self.services.remove(service) {
+            if health.alerted {
+                info!("{} has recovered after {} failed posts", service, health.consecutive_failures);
+            }
+        }
+    }
+
+    pub fn failure(&mut self, service: &'static str, error: &str) {
+        let health = self.services.entry(service).or_default();
+        health.consecutive_failures += 1;
+        health.failing_since.get_or_insert_with(Instant::now);
+        health.last_error = String::from(error);
+    }
+
+    /// Services that crossed the alert threshold since we last checked. Each
+    /// failure streak is only ever reported once, a success starts things over
+    pub fn take_alerts(&mut self) -> Vec<ServiceAlert> {
+        let threshold = alert_threshold();
+        let window = alert_window();
+
+        let mut alerts = Vec::new();
+        for (service, health) in self.services.iter_mut() {
+            let failing_for = match health.failing_since {
+                Some(since) => since.elapsed(),
+                None => continue,
+            };
+
+            if !health.alerted && health.consecutive_failures >= threshold && failing_for >= window {
+                health.alerted = true;
+                alerts.push(ServiceAlert {
+                    service,
+                    failures: health.consecutive_failures,
+                    failing_for,
+                    last_error: health.last_error.clone(),
+                });
+            }
+        }
+        alerts
+    }
+}
+
+fn alert_threshold() -> u32 {
+    env::var("ALERT_FAILURE_THRESHOLD")
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_ALERT_THRESHOLD)
+}
+
+fn alert_window() -> Duration {
+    let minutes = env::var("ALERT_FAILURE_WINDOW")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_ALERT_WINDOW);
+    Duration::from_secs(minutes * 60)
+}
@@ -1,9 +1,33 @@
+use std::env;
 use std::error::Error;
 use std::fmt;
 
-use crate::utls::constants::URL_ALLOW_LIST;
+use crate::utls::constants::{DEFAULT_HEADER_NAME, DEFAULT_MAX_CODE_BLOCKS, DEFAULT_MAX_OUTPUT_BYTES, SOURCE_EXTENSIONS, URL_ALLOW_LIST};
+use regex::Regex;
 use serenity::model::user::User;
 
+lazy_static! {
+    // a whole code block, with its language tag if it has one
+    static ref CODE_BLOCK: Regex = Regex::new(r"```(?:(?P<language>[^\s`]*)\r?\n)?(?P<code>[\s\S]*?)```").unwrap();
+    // a mention of us leading the message, see strip_mention
+    static ref LEADING_MENTION: Regex = Regex::new(r"^\s*<@!?\d+>\s*").unwrap();
+    // the filename comment a header block starts with (// foo.h), see header_name
+    static ref HEADER_COMMENT: Regex = Regex::new(r"^\s*//\s*(?:file:\s*)?(?P<name>[\w.-]+\.(?:h|hh|hpp|hxx))\s*$").unwrap();
+    // a local #include "foo.h", see inline_headers
+    static ref LOCAL_INCLUDE: Regex = Regex::new(r#"^\s*#\s*include\s*"(?P<name>[^"]+)"\s*$"#).unwrap();
+    // the language tag opening a code block (```cpp)
+    static ref LANGUAGE_TAG: Regex = Regex::new(r"```(?P<language>[^\s`]+)\r?\n").unwrap();
+}
+
+/// The name we know a language by, anything taking a language from the user goes through here.
+/// cpp is c++ since we removed the c pre-processor support for wandbox, godbolt is fine with it too
+pub fn normalize_language(language: &str) -> &str {
+    match language {
+        "cpp" => "c++",
+        other => other,
+    }
+}
+
 #[derive(Debug)]
 pub struct ParserError {
     details: String,
@@ -26,23 +50,79 @@ impl Error for ParserError {
     }
 }
 
+/// Where the options of a request came from, so it's clear why a flag was applied
+#[derive(Clone, Copy, PartialEq)]
+pub enum FlagSource {
+    None,
+    Inline,
+    User,
+    Auto,
+}
+
+impl Default for FlagSource {
+    fn default() -> FlagSource {
+        FlagSource::None
+    }
+}
+
+impl fmt::Display for FlagSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FlagSource::None => write!(f, "none"),
+            FlagSource::Inline => write!(f, "inline"),
+            FlagSource::User => write!(f, "myflags"),
+            FlagSource::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
 pub struct ParserResult {
     pub url: String,
     pub stdin: String,
     pub target: String,
     pub code: String,
     pub options: Vec<String>,
+    pub flags_source: FlagSource,
+    pub verbose: bool,
+    pub stderr_only: bool,
+    pub dm: bool,
+    pub stream: bool,
+    /// The footer should break down how long building & running took, see --timing
+    pub timing: bool,
+    /// Only the toolchain's syntax check should run, see ;check
+    pub syntax_only: bool,
+    /// Friendly library names from --lib, see utls::libraries
+    pub libraries: Vec<String>,
+    /// The runtime picked with --runtime, see utls::runtimes
+    pub runtime: Option<String>,
+    /// The stdin comes from an attached text file, see --stdin-file
+    pub stdin_file: bool,
+    /// The program's output should be hidden behind a spoiler, see --spoiler
+    pub spoiler: bool,
+    pub ignored_blocks: usize,
+    /// Header blocks inlined into the code, along with whether the code actually #included them
+    pub headers: Vec<(String, bool)>,
+}
+
+/// Source code given as a file rather than a code block, see compile::attachment_source
+pub struct SourceFile {
+    pub name: String,
+    pub code: String,
+}
+
+pub async fn get_components(input: &str, author : &User, default_target : Option<String>) -> Result<ParserResult, ParserError> {
+    get_components_with_file(input, author, default_target, None).await
 }
 
+/// Like get_components, but with the code coming from `file`. Code blocks in the message are
+/// then taken as input, and the file's extension stands in for a missing language
 #[allow(clippy::while_let_on_iterator)]
-pub async fn get_components(input: &str, author : &User) -> Result<ParserResult, ParserError> {
-    let mut result = ParserResult {
-        url: Default::default(),
-        stdin: Default::default(),
-        target: Default::default(),
-        code: Default::default(),
-        options: Default::default(),
-    };
+pub async fn get_components_with_file(input: &str, author : &User, default_target : Option<String>, file : Option<SourceFile>) -> Result<ParserResult, ParserError> {
+    let mut result = ParserResult::default();
+
+    let stripped = strip_formatting(strip_mention(input));
+    let input = stripped.as_str();
 
     // we grab the index for the first code block - this will help us
     // know when to stop parsing arguments
@@ -58,16 +138,11 @@ pub async fn get_components(input: &str, author : &User) -> Result<ParserResult,
     // ditch command str (;compile, ;asm)
     args.remove(0);
 
-    if args.is_empty() {
-        return Err(ParserError::new("You must provide a valid language or compiler!\n\n;compile c++ \n\\`\\`\\`\nint main() {}\n\\`\\`\\`"));
-    }
-    result.target = args.remove(0).trim().to_owned().to_lowercase();
-
-    // Replace cpp with c++ since we removed the c pre-processor
-    // support for wandbox. This is okay for godbolt requests, too.
-    if result.target == "cpp" {
-        result.target = String::from("c++");
-    }
+    // operators & flags up front mean the target was left out, see below
+    let target = match args.first() {
+        Some(first) if !is_operator(first) => args.remove(0).trim(),
+        _ => "",
+    };
 
     // looping every argument
     let mut iter = args.iter();
@@ -98,12 +173,70 @@ pub async fn get_components(input: &str, author : &User) -> Result<ParserResult,
             }
 
             result.stdin = input.trim().to_owned();
+        } else if c.trim() == "--verbose" {
+            result.verbose = true;
+        } else if c.trim() == "--stderr-only" {
+            result.stderr_only = true;
+        } else if c.trim() == "--dm" {
+            result.dm = true;
+        } else if c.trim() == "--stream" {
+            result.stream = true;
+        } else if c.trim() == "--timing" {
+            result.timing = true;
+        } else if c.trim() == "--stdin-file" {
+            result.stdin_file = true;
+        } else if c.trim() == "--spoiler" {
+            result.spoiler = true;
+        } else if c.trim() == "--lib" {
+            match iter.next() {
+                Some(lib) if !lib.contains("```") && !lib.trim().is_empty() => result.libraries.push(lib.trim().to_lowercase()),
+                _ => return Err(ParserError::new("'--lib' requires a library name\n\nUsage: `;run c++ --lib boost`")),
+            }
+        } else if c.trim() == "--runtime" {
+            match iter.next() {
+                Some(runtime) if !runtime.contains("```") && !runtime.trim().is_empty() => result.runtime = Some(runtime.trim().to_lowercase()),
+                _ => return Err(ParserError::new("'--runtime' requires a runtime name\n\nUsage: `;run python --runtime pypy`")),
+            }
         } else {
             result.options.push(c.trim().to_string());
         }
     }
 
-    if !result.url.is_empty() {
+    if !result.options.is_empty() {
+        result.flags_source = FlagSource::Inline;
+    }
+
+    // an explicit target always wins, then the language the code's file name implies
+    // & otherwise the channel's default
+    let file_name = match &file {
+        Some(f) => Some(f.name.clone()),
+        None => url_file_name(&result.url),
+    };
+    if !target.is_empty() {
+        result.target = target.to_lowercase();
+    } else if let Some(language) = file_name.as_deref().and_then(extension_language) {
+        result.target = String::from(language);
+    } else if let Some(default) = default_target {
+        result.target = default;
+    } else {
+        return Err(ParserError::new("You must provide a valid language or compiler!\n\n;compile c++ \n\\`\\`\\`\nint main() {}\n\\`\\`\\`"));
+    }
+
+    result.target = String::from(normalize_language(&result.target));
+
+    if let Some(file) = file {
+        if !result.url.is_empty() {
+            return Err(ParserError::new("You've given code both as a file and a url, please pick one"));
+        }
+        result.code = file.code;
+
+        // with the code in a file, a code block can only be meant as input
+        if result.stdin.is_empty() {
+            if let Some(stdin) = first_code_block(input) {
+                result.stdin = stdin;
+            }
+        }
+    } else if !result.url.is_empty() {
         let url = match reqwest::Url::parse(&result.url) {
             Err(e) => {
                 return Err(ParserError::new(&format!("Error parsing url: {}", e)))
@@ -122,7 +255,7 @@ pub async fn get_components(input: &str, author : &User) -> Result<ParserResult,
             return Err(ParserError::new("Unknown paste service. Please use pastebin.com, hastebin.com, or GitHub gists.\n\nAlso please be sure to use a 'raw text' link"))
         }
 
-        let response = match reqwest::get(&result.url).await {
+        let mut response = match reqwest::get(&result.url).await {
             Ok(b) => b,
             Err(_e) => {
                 return Err(ParserError::new(
@@ -131,12 +264,23 @@ pub async fn get_components(input: &str, author : &User) -> Result<ParserResult,
             }
         };
 
-        let body = match response.text().await {
-            Ok(t) => t,
-            Err(_e) => return Err(ParserError::new("Unable to grab resource")),
-        };
+        // read the body in chunks so a huge paste can't be buffered unbounded
+        let limit = max_output_bytes();
+        let mut body: Vec<u8> = Vec::new();
+        loop {
+            match response.chunk().await {
+                Ok(Some(chunk)) => {
+                    if body.len() + chunk.len() > limit {
+                        return Err(ParserError::new(&format!("Resource is too large, the limit is {} bytes", limit)));
+                    }
+                    body.extend_from_slice(&chunk);
+                }
+                Ok(None) => break,
+                Err(_e) => return Err(ParserError::new("Unable to grab resource")),
+            }
+        }
 
-        result.code = body;
+        result.code = String::from_utf8_lossy(&body).to_string();
     } else {
         find_code_block(&mut result, input)?;
     }
@@ -144,31 +288,355 @@ pub async fn get_components(input: &str, author : &User) -> Result<ParserResult,
     Ok(result)
 }
 
-fn find_code_block(result: &mut ParserResult, haystack: &str) -> Result<(), ParserError> {
-    let re = regex::Regex::new(r"```(?:(?P<language>[^\s`]*)\r?\n)?(?P<code>[\s\S]*?)```").unwrap();
-    let matches = re.captures_iter(haystack);
+fn is_operator(arg: &str) -> bool {
+    let arg = arg.trim();
+    arg == "<" || arg == "|" || arg.starts_with("--")
+}
+
+/// The language a source file's extension implies, if it's one we know of
+pub fn extension_language(file_name: &str) -> Option<&'static str> {
+    let dot = file_name.rfind('.')?;
+    let extension = file_name[dot + 1..].to_lowercase();
+    SOURCE_EXTENSIONS
+        .iter()
+        .find(|(ext, _)| *ext == extension)
+        .map(|(_, language)| *language)
+}
+
+// raw gist links end in the file's name, pastes generally don't
+fn url_file_name(url: &str) -> Option<String> {
+    let url = reqwest::Url::parse(url).ok()?;
+    let name = url.path_segments()?.last()?;
+    Some(String::from(name))
+}
 
-    let mut captures: Vec<&str> = Vec::new();
-    let list = matches.enumerate();
-    for (_, cap) in list {
-        captures.push(cap.name("code").unwrap().as_str());
+/// The most output (or fetched input) we're willing to hold on to for a single request
+pub fn max_output_bytes() -> usize {
+    env::var("MAX_OUTPUT_BYTES")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_MAX_OUTPUT_BYTES)
+}
+
+/// The contents of the first code block, if there is one
+pub fn first_code_block(input: &str) -> Option<String> {
+    let code = CODE_BLOCK.captures(input)?.name("code")?.as_str();
+    Some(String::from(code))
+}
+
+/// Every code block in the input along with its language tag, if it has one
+pub fn code_blocks(input: &str) -> Vec<(Option<String>, String)> {
+    CODE_BLOCK.captures_iter(input)
+        .map(|cap| {
+            let language = cap
+                .name("language")
+                .map(|l| l.as_str().to_lowercase())
+                .filter(|l| !l.is_empty());
+            (language, String::from(cap.name("code").unwrap().as_str()))
+        })
+        .collect()
+}
+
+/// The language tag of the first code block (```cpp), if it has one
+pub fn code_block_language(input: &str) -> Option<String> {
+    let language = LANGUAGE_TAG.captures(input)?.name("language")?.as_str().to_lowercase();
+    Some(language)
+}
+
+/// The most code blocks we'll take from a single message, not counting a stdin block
+pub fn max_code_blocks() -> usize {
+    env::var("MAX_CODE_BLOCKS")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_MAX_CODE_BLOCKS)
+}
+
+/// Commands can be invoked by mentioning us instead of using the prefix, the
+/// framework only routes mentions of our own id here so any leading mention is us
+fn strip_mention(input: &str) -> &str {
+    match LEADING_MENTION.find(input) {
+        Some(m) => &input[m.end()..],
+        None => input,
     }
+}
 
-    // support for stdin codeblocks
-    // support for stdin codeblocks
-    match captures.len() {
-        len if len > 1 => {
-            result.code = String::from(captures[1]);
-            result.stdin = String::from(captures[0]);
+// Discord formatting can wrap the code we're after, so spoilers (||code||) and
+// quotes (> code, >>> code) are stripped before we go looking for code blocks.
+// Spoiler markers inside a code block are left alone since they're valid code,
+// as are quote markers unless the block itself was opened inside a quote.
+fn strip_formatting(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut in_block = false;
+    let mut quoted_block = false;
+    let mut multiline_quote = false;
+
+    for (i, line) in input.split('\n').enumerate() {
+        if i > 0 {
+            output.push('\n');
         }
-        1 => {
-            result.code = String::from(captures[0]);
+
+        let mut line = line;
+        let mut quoted = false;
+        if !multiline_quote && (!in_block || quoted_block) {
+            if let Some(rest) = line.strip_prefix(">>> ") {
+                line = rest;
+                multiline_quote = true;
+            } else if let Some(rest) = line.strip_prefix("> ") {
+                line = rest;
+                quoted = true;
+            } else if line == ">" {
+                line = "";
+                quoted = true;
+            }
         }
-        _ => {
-            return Err(ParserError::new(
-                "You must attach a code-block containing code to your message",
-            ))
+
+        let mut rest = line;
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix("```") {
+                in_block = !in_block;
+                if in_block {
+                    quoted_block = quoted;
+                }
+                output.push_str("```");
+                rest = after;
+            } else if let (false, Some(after)) = (in_block, rest.strip_prefix("||")) {
+                rest = after;
+            } else {
+                let c = rest.chars().next().unwrap();
+                output.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
         }
     }
+
+    output
+}
+
+fn find_code_block(result: &mut ParserResult, haystack: &str) -> Result<(), ParserError> {
+    let matches = CODE_BLOCK.captures_iter(haystack);
+
+    let mut blocks: Vec<(Option<&str>, &str)> = Vec::new();
+    for cap in matches {
+        let language = cap.name("language").map(|l| l.as_str()).filter(|l| !l.is_empty());
+        blocks.push((language, cap.name("code").unwrap().as_str()));
+    }
+
+    if blocks.is_empty() {
+        return Err(ParserError::new(
+            "You must attach a code-block containing code to your message",
+        ));
+    }
+
+    // header blocks are pulled out first so they're never mistaken for stdin,
+    // a lone block is always the code itself though
+    let mut headers: Vec<(String, &str)> = Vec::new();
+    if blocks.len() > 1 {
+        blocks.retain(|(language, code)| match header_name(*language, code) {
+            Some(name) => {
+                headers.push((name, *code));
+                false
+            }
+            None => true,
+        });
+    }
+
+    let max = max_code_blocks();
+    if let Some(stdin) = take_stdin(&mut blocks, max)? {
+        result.stdin = String::from(stdin);
+    }
+    let mut captures: Vec<&str> = blocks.into_iter().map(|(_, code)| code).collect();
+    if captures.is_empty() {
+        return Err(ParserError::new(
+            "You must attach a code-block containing code to your message, not just headers",
+        ));
+    }
+
+    // everything past the cap is dropped so one message can't fan out into a
+    // pile of work, whatever is left is compiled together in order
+    if captures.len() > max {
+        result.ignored_blocks = captures.len() - max;
+        captures.truncate(max);
+    }
+    result.code = captures.join("\n");
+
+    if !headers.is_empty() {
+        let (code, inlined) = inline_headers(&result.code, &headers);
+        result.code = code;
+        result.headers = inlined;
+    }
     Ok(())
 }
+
+// Pulls the program's input out of the blocks. A block tagged ```stdin always is the input,
+// otherwise only two blocks with a single code block allowed are read as input & code.
+// Any more and there's no telling which one was meant as input, so we'd rather ask
+fn take_stdin<'a>(blocks: &mut Vec<(Option<&str>, &'a str)>, max: usize) -> Result<Option<&'a str>, ParserError> {
+    let tagged: Vec<usize> = blocks
+        .iter()
+        .enumerate()
+        .filter(|(_, (language, _))| *language == Some("stdin"))
+        .map(|(i, _)| i)
+        .collect();
+    match tagged.as_slice() {
+        [] => (),
+        [i] => return Ok(Some(blocks.remove(*i).1)),
+        _ => return Err(ParserError::new("Only one code block can be tagged as stdin")),
+    }
+
+    if blocks.len() < 2 || max > 1 {
+        return Ok(None);
+    }
+    if blocks.len() > 2 {
+        return Err(ParserError::new(&format!(
+            "Your message has {} code blocks, which one is your program's input? Tag it with ```stdin",
+            blocks.len()
+        )));
+    }
+    Ok(Some(blocks.remove(0).1))
+}
+
+/// The name a header block is #included by. Headers are recognized by a filename
+/// comment on their first line (// foo.h) or by their language tag (```h)
+fn header_name(language: Option<&str>, code: &str) -> Option<String> {
+    let first_line = code.lines().next().unwrap_or_default();
+    if let Some(cap) = HEADER_COMMENT.captures(first_line) {
+        return Some(String::from(&cap["name"]));
+    }
+
+    match language.map(|l| l.to_lowercase()).as_deref() {
+        Some("h") | Some("hh") | Some("hpp") | Some("hxx") => Some(String::from(DEFAULT_HEADER_NAME)),
+        _ => None,
+    }
+}
+
+// Backends only take a single file, so headers are pasted in place of the
+// #include that references them. Later includes of the same header are dropped
+// as if it had #pragma once, and headers that are never included go on top
+fn inline_headers(code: &str, headers: &[(String, &str)]) -> (String, Vec<(String, bool)>) {
+    let mut included = vec![false; headers.len()];
+    let mut lines: Vec<&str> = Vec::new();
+    for line in code.lines() {
+        let include = LOCAL_INCLUDE.captures(line).map(|cap| cap["name"].to_string());
+        match include.and_then(|name| headers.iter().position(|(header, _)| *header == name)) {
+            Some(i) if !included[i] => {
+                included[i] = true;
+                lines.push(headers[i].1.trim_end());
+            }
+            Some(_) => (),
+            None => lines.push(line),
+        }
+    }
+
+    let mut output = String::new();
+    for (i, (_, header)) in headers.iter().enumerate() {
+        if !included[i] {
+            output.push_str(header.trim_end());
+            output.push('\n');
+        }
+    }
+    output.push_str(&lines.join("\n"));
+
+    let report = headers
+        .iter()
+        .zip(included)
+        .map(|((name, _), included)| (name.clone(), included))
+        .collect();
+    (output, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_input_is_untouched() {
+        let input = ";run c++\n```cpp\nint main() { return a > b || c; }\n```";
+        assert_eq!(strip_formatting(input), input);
+    }
+
+    #[test]
+    fn spoilers_are_stripped() {
+        let input = ";run c++ ||```cpp\nint main() {}\n```||";
+        assert_eq!(strip_formatting(input), ";run c++ ```cpp\nint main() {}\n```");
+    }
+
+    #[test]
+    fn spoilers_inside_blocks_are_code() {
+        let input = "||```c\nreturn a || b;\n```||";
+        assert_eq!(strip_formatting(input), "```c\nreturn a || b;\n```");
+    }
+
+    #[test]
+    fn quotes_are_stripped() {
+        let input = "> ;run python\n> ```py\n> print(1 > 0)\n>\n> ```";
+        assert_eq!(strip_formatting(input), ";run python\n```py\nprint(1 > 0)\n\n```");
+    }
+
+    #[test]
+    fn multiline_quotes_are_stripped() {
+        let input = ">>> ;run python\n```py\n> not a quote\n```";
+        assert_eq!(strip_formatting(input), ";run python\n```py\n> not a quote\n```");
+    }
+
+    #[test]
+    fn unquoted_blocks_keep_quote_markers() {
+        let input = "```\n> input\n```";
+        assert_eq!(strip_formatting(input), input);
+    }
+
+    #[test]
+    fn quoted_spoilers_are_stripped() {
+        let input = "> ||```rs\n> fn main() {}\n> ```||";
+        assert_eq!(strip_formatting(input), "```rs\nfn main() {}\n```");
+    }
+
+    #[test]
+    fn zero_width_characters_are_kept() {
+        let input = "||\u{200b}```cpp\nauto s = \"\u{200b}\";\n```||";
+        let stripped = strip_formatting(input);
+        assert_eq!(stripped, "\u{200b}```cpp\nauto s = \"\u{200b}\";\n```");
+        assert_eq!(first_code_block(&stripped).unwrap(), "auto s = \"\u{200b}\";\n");
+    }
+
+    #[test]
+    fn lone_block_is_code() {
+        let mut blocks = vec![(Some("cpp"), "int main() {}")];
+        assert!(take_stdin(&mut blocks, 1).unwrap().is_none());
+        assert_eq!(blocks.len(), 1);
+    }
+
+    #[test]
+    fn first_of_two_blocks_is_stdin() {
+        let mut blocks = vec![(None, "42"), (Some("cpp"), "int main() {}")];
+        assert_eq!(take_stdin(&mut blocks, 1).unwrap(), Some("42"));
+        assert_eq!(blocks, vec![(Some("cpp"), "int main() {}")]);
+    }
+
+    #[test]
+    fn tagged_block_is_stdin() {
+        let mut blocks = vec![(Some("cpp"), "int a;"), (Some("stdin"), "42"), (Some("cpp"), "int main() {}")];
+        assert_eq!(take_stdin(&mut blocks, 3).unwrap(), Some("42"));
+        assert_eq!(blocks, vec![(Some("cpp"), "int a;"), (Some("cpp"), "int main() {}")]);
+    }
+
+    #[test]
+    fn multiple_files_take_no_stdin() {
+        let mut blocks = vec![(Some("cpp"), "int a;"), (Some("cpp"), "int main() {}")];
+        assert!(take_stdin(&mut blocks, 2).unwrap().is_none());
+        assert_eq!(blocks.len(), 2);
+    }
+
+    #[test]
+    fn untagged_blocks_are_ambiguous() {
+        let mut blocks = vec![(None, "1"), (None, "2"), (Some("cpp"), "int main() {}")];
+        assert!(take_stdin(&mut blocks, 1).is_err());
+    }
+
+    #[test]
+    fn two_stdin_blocks_are_refused() {
+        let mut blocks = vec![(Some("stdin"), "1"), (Some("stdin"), "2"), (Some("cpp"), "int main() {}")];
+        assert!(take_stdin(&mut blocks, 1).is_err());
+    }
+}
@@ -0,0 +1,236 @@
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::utls::constants::{DEFAULT_BACKUP_INTERVAL, DEFAULT_BACKUP_RETENTION};
+
+// Everything we keep on disk, profiles.json is only ever written by hand
+// but it's just as painful to lose
+static PERSISTED_FILES: [&str; 6] = ["blocklist.json", "settings.json", "profiles.json", "schedule.json", "expiry.json", "guildstats.json"];
+
+// Every store we write carries the version of its format under this key. Files
+// from before stores were versioned don't have it, those are version 1
+static VERSION_KEY: &str = "version";
+
+/// Upgrades a store's json by a single version. A store's migrations are listed
+/// oldest first, the first one takes version 1 to version 2 and so on, which makes
+/// the current version one more than the number of migrations
+pub type Migration = fn(Value) -> Value;
+
+pub fn current_version(migrations: &[Migration]) -> u64 {
+    migrations.len() as u64 + 1
+}
+
+/// Brings a store's json up to the current version, running only the migrations it
+/// hasn't been through yet. Files from a newer version than ours are refused, we'd
+/// silently drop whatever they added the next time we wrote them
+pub fn migrate(mut value: Value, migrations: &[Migration]) -> io::Result<Value> {
+    let version = match value.as_object_mut().and_then(|map| map.remove(VERSION_KEY)) {
+        Some(v) => v.as_u64().ok_or_else(|| invalid_data(format!("Invalid {} '{}'", VERSION_KEY, v)))?,
+        None => 1,
+    };
+
+    let current = current_version(migrations);
+    if version == 0 || version > current {
+        return Err(invalid_data(format!(
+            "Written with format version {}, we only understand up to version {}",
+            version, current
+        )));
+    }
+
+    for migration in &migrations[(version - 1) as usize..] {
+        value = migration(value);
+    }
+    Ok(value)
+}
+
+/// The store as json, tagged with the current version
+pub fn versioned_json<T: Serialize>(store: &T, migrations: &[Migration]) -> io::Result<String> {
+    let mut value = serde_json::to_value(store)?;
+    match value.as_object_mut() {
+        Some(map) => {
+            map.insert(String::from(VERSION_KEY), json!(current_version(migrations)));
+        }
+        None => return Err(invalid_data(String::from("Only json objects can carry a version"))),
+    }
+    Ok(serde_json::to_string(&value)?)
+}
+
+/// Reads a store written by write_versioned, upgrading older formats along the way.
+/// None if the file doesn't exist yet
+pub fn load_versioned<T: DeserializeOwned>(path: &str, migrations: &[Migration]) -> io::Result<Option<T>> {
+    if !Path::new(path).exists() {
+        return Ok(None);
+    }
+
+    let json = fs::read_to_string(path)?;
+    let value = migrate(serde_json::from_str(&json)?, migrations)?;
+    Ok(Some(serde_json::from_value(value)?))
+}
+
+/// Writes the store atomically, tagged with the current version
+pub fn write_versioned<T: Serialize>(path: &str, store: &T, migrations: &[Migration]) -> io::Result<()> {
+    let json = versioned_json(store, migrations)?;
+    write_atomic(path, &json)
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Writes the file through a temporary one that's renamed into place,
+/// so a crash mid-write can never leave us with half a file
+pub fn write_atomic(path: &str, contents: &str) -> io::Result<()> {
+    let temp = format!("{}.tmp", path);
+    {
+        let mut file = fs::File::create(&temp)?;
+        file.write_all(contents.as_bytes())?;
+        // the contents have to be on disk before the rename is, or a crash can still leave an empty file
+        file.sync_all()?;
+    }
+    fs::rename(&temp, path)?;
+
+    // the rename itself lives in the directory, not every platform lets us sync one though
+    let dir = match Path::new(path).parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    if let Ok(dir) = fs::File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// Interval between backups of our persisted state, in minutes. Zero disables them
+pub fn backup_interval() -> u64 {
+    env::var("BACKUP_INTERVAL")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_BACKUP_INTERVAL)
+}
+
+/// How many backups are kept around before the oldest are removed
+pub fn backup_retention() -> usize {
+    env::var("BACKUP_RETENTION")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_BACKUP_RETENTION)
+}
+
+pub fn backup_dir() -> String {
+    env::var("BACKUP_DIR")
+        .ok()
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| String::from("backups"))
+}
+
+/// Copies all persisted state into a new timestamped directory, then prunes
+/// the oldest backups so only the most recent `keep` remain
+pub fn backup(dir: &str, keep: usize) -> io::Result<PathBuf> {
+    let target = Path::new(dir).join(Utc::now().format("%Y%m%d-%H%M%S").to_string());
+    fs::create_dir_all(&target)?;
+    for file in PERSISTED_FILES.iter() {
+        let source = Path::new(file);
+        if source.exists() {
+            fs::copy(source, target.join(file))?;
+        }
+    }
+
+    // the timestamped names sort oldest first
+    let mut backups: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    backups.sort();
+    if backups.len() > keep {
+        let expired = backups.len() - keep;
+        for old in backups.drain(..expired) {
+            fs::remove_dir_all(&old)?;
+        }
+    }
+
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_field(mut value: Value) -> Value {
+        if let Some(map) = value.as_object_mut() {
+            map.insert(String::from("added"), json!(true));
+        }
+        value
+    }
+
+    static MIGRATIONS: [Migration; 1] = [add_field];
+
+    // unique per test so the tests can run in parallel
+    fn temp_path(name: &str) -> String {
+        let path = env::temp_dir().join(format!("compiler-bot-{}-{}.json", name, std::process::id()));
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn unversioned_is_version_one() {
+        let value = migrate(json!({ "a": 1 }), &MIGRATIONS).unwrap();
+        assert_eq!(value, json!({ "a": 1, "added": true }));
+    }
+
+    #[test]
+    fn current_version_runs_nothing() {
+        let value = migrate(json!({ "a": 1, "version": 2 }), &MIGRATIONS).unwrap();
+        assert_eq!(value, json!({ "a": 1 }));
+    }
+
+    #[test]
+    fn newer_version_is_refused() {
+        let err = migrate(json!({ "version": 3 }), &MIGRATIONS).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn version_zero_is_refused() {
+        assert!(migrate(json!({ "version": 0 }), &MIGRATIONS).is_err());
+    }
+
+    #[test]
+    fn non_numeric_version_is_refused() {
+        assert!(migrate(json!({ "version": "two" }), &MIGRATIONS).is_err());
+    }
+
+    #[test]
+    fn load_missing_file() {
+        let loaded: Option<Value> = load_versioned(&temp_path("missing"), &MIGRATIONS).unwrap();
+        assert!(loaded.is_none());
+    }
+
+    #[test]
+    fn write_then_load() {
+        let path = temp_path("roundtrip");
+        write_versioned(&path, &json!({ "a": 1 }), &MIGRATIONS).unwrap();
+        let written: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["version"], json!(2));
+
+        let loaded: Option<Value> = load_versioned(&path, &MIGRATIONS).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, Some(json!({ "a": 1 })));
+    }
+
+    #[test]
+    fn load_upgrades_old_files() {
+        let path = temp_path("upgrade");
+        fs::write(&path, r#"{"a":1}"#).unwrap();
+        let loaded: Option<Value> = load_versioned(&path, &MIGRATIONS).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, Some(json!({ "a": 1, "added": true })));
+    }
+}
@@ -0,0 +1,135 @@
+use crate::utls::compileresult::CompileResult;
+
+type Processor = fn(&str) -> String;
+
+// Language -> cleanup applied to every output of its programs before we render them.
+// Both wandbox's & godbolt's names are listed where they differ
+static POST_PROCESSORS: [(&str, Processor); 5] = [
+    ("java", strip_java_banner),
+    ("javascript", strip_node_warnings),
+    ("c#", strip_csc_banner),
+    ("csharp", strip_csc_banner),
+    ("python", strip_python_warnings),
+];
+
+/// Cleans up the result's output for the language, line endings are normalized for every language
+pub fn apply(language: &str, result: &mut CompileResult) {
+    let language = language.to_lowercase();
+    let processor = POST_PROCESSORS
+        .iter()
+        .find(|(lang, _)| *lang == language)
+        .map(|(_, processor)| *processor);
+
+    for output in [&mut result.compiler_output, &mut result.stdout, &mut result.stderr].iter_mut() {
+        let mut text = normalize_line_endings(output);
+        if let Some(processor) = processor {
+            text = processor(&text);
+        }
+        **output = text;
+    }
+}
+
+fn normalize_line_endings(text: &str) -> String {
+    text.replace("\r\n", "\n")
+}
+
+// Drops the lines at the very start of the output that match, the rest is left untouched
+fn strip_leading(text: &str, banner: fn(&str) -> bool) -> String {
+    let mut lines = text.lines().peekable();
+    let mut stripped = false;
+    while let Some(line) = lines.peek() {
+        if !banner(line) {
+            break;
+        }
+        lines.next();
+        stripped = true;
+    }
+
+    if !stripped {
+        return String::from(text);
+    }
+    lines.collect::<Vec<&str>>().join("\n")
+}
+
+// The JVM announces any options it picked up from the environment
+fn strip_java_banner(text: &str) -> String {
+    strip_leading(text, |line| {
+        line.starts_with("Picked up JAVA_TOOL_OPTIONS:") || line.starts_with("Picked up _JAVA_OPTIONS:")
+    })
+}
+
+// Experimental & deprecation warnings, along with node's hint on how to trace them
+fn strip_node_warnings(text: &str) -> String {
+    strip_leading(text, |line| {
+        (line.starts_with("(node:") && line.contains("Warning:")) || line.starts_with("(Use `node --trace-")
+    })
+}
+
+fn strip_csc_banner(text: &str) -> String {
+    strip_leading(text, |line| {
+        line.starts_with("Microsoft (R) Visual C# Compiler")
+            || line.starts_with("Copyright (C) Microsoft Corporation")
+            || line.trim().is_empty()
+    })
+}
+
+// Warnings some interpreter builds print about themselves before the program runs
+fn strip_python_warnings(text: &str) -> String {
+    strip_leading(text, |line| line.starts_with("Could not find platform independent libraries"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn java_banner() {
+        let output = "Picked up JAVA_TOOL_OPTIONS: -Xmx256m\nPicked up _JAVA_OPTIONS: -Xss8m\nHello";
+        assert_eq!(strip_java_banner(output), "Hello");
+    }
+
+    #[test]
+    fn node_warnings() {
+        let output = "(node:123) ExperimentalWarning: The fs.promises API is experimental\n\
+            (Use `node --trace-warnings ...` to show where the warning was created)\nHello";
+        assert_eq!(strip_node_warnings(output), "Hello");
+    }
+
+    #[test]
+    fn csc_banner() {
+        let output = "Microsoft (R) Visual C# Compiler version 3.6.0\nCopyright (C) Microsoft Corporation. All rights reserved.\n\nprog.cs(3,5): error CS1002";
+        assert_eq!(strip_csc_banner(output), "prog.cs(3,5): error CS1002");
+    }
+
+    #[test]
+    fn python_warnings() {
+        let output = "Could not find platform independent libraries <prefix>\nTraceback (most recent call last):";
+        assert_eq!(strip_python_warnings(output), "Traceback (most recent call last):");
+    }
+
+    #[test]
+    fn only_leading_lines_are_stripped() {
+        let output = "Hello\nPicked up JAVA_TOOL_OPTIONS: -Xmx256m";
+        assert_eq!(strip_java_banner(output), output);
+    }
+
+    #[test]
+    fn untouched_without_banner() {
+        assert_eq!(strip_node_warnings("Hello\n"), "Hello\n");
+    }
+
+    #[test]
+    fn apply_normalizes_every_language() {
+        let mut result = CompileResult::from_godbolt(Default::default(), "test", std::time::Duration::from_millis(0));
+        result.stdout = String::from("Picked up JAVA_TOOL_OPTIONS: -Xmx256m\r\nHello\r\n");
+        result.stderr = String::from("oops\r\n");
+
+        apply("Java", &mut result);
+        assert_eq!(result.stdout, "Hello");
+        assert_eq!(result.stderr, "oops\n");
+
+        result.stdout = String::from("a\r\nb");
+        apply("c++", &mut result);
+        assert_eq!(result.stdout, "a\nb");
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::fs;
+use std::time::Duration;
+
+use serde::*;
+
+use crate::utls::parser;
+
+/// Runtime limits applied to a compilation
+#[derive(Deserialize, Clone)]
+pub struct Profile {
+    /// Seconds we'll wait on the backend before giving up
+    #[serde(default = "default_time_limit")]
+    pub time_limit: u64,
+
+    /// Bytes of output we'll hold on to, defaults to MAX_OUTPUT_BYTES
+    #[serde(default)]
+    pub output_limit: Option<usize>,
+
+    /// Prefixes of the compiler flags users may pass, anything goes if unset
+    #[serde(default)]
+    pub allowed_flags: Option<Vec<String>>,
+
+    /// Whether programs may be ran at all. Otherwise builds stop before there's a program
+    /// to run (see syntax::compile_only_flag), languages that can't do that are refused
+    #[serde(default = "default_execution_allowed")]
+    pub execution_allowed: bool,
+}
+
+fn default_time_limit() -> u64 {
+    30
+}
+
+fn default_execution_allowed() -> bool {
+    true
+}
+
+impl Default for Profile {
+    fn default() -> Profile {
+        Profile {
+            time_limit: default_time_limit(),
+            output_limit: None,
+            allowed_flags: None,
+            execution_allowed: default_execution_allowed(),
+        }
+    }
+}
+
+impl Profile {
+    pub fn time_limit(&self) -> Duration {
+        Duration::from_secs(self.time_limit)
+    }
+
+    /// What the user is told once the time limit runs out
+    pub fn timed_out(&self, language: &str) -> String {
+        format!("Compilation timed out after {} seconds, the time limit for {}", self.time_limit, language)
+    }
+
+    pub fn output_limit(&self) -> usize {
+        self.output_limit.unwrap_or_else(parser::max_output_bytes)
+    }
+
+    /// Returns the first flag this profile doesn't allow, if any
+    pub fn disallowed_flag<'a>(&self, flags: &'a [String]) -> Option<&'a str> {
+        let allowed = self.allowed_flags.as_ref()?;
+        flags
+            .iter()
+            .find(|flag| !allowed.iter().any(|prefix| flag.starts_with(prefix.as_str())))
+            .map(|flag| flag.as_str())
+    }
+}
+
+/// Maps languages onto named profiles. Anything not mapped to a profile
+/// uses the 'default' profile. A language can also override just the time
+/// limit of its profile, its startup cost shouldn't need a profile of its own
+///
+/// Built-in profiles can be overridden, and new ones added, in profiles.json:
+/// `{ "profiles": { "heavy": { "time_limit": 60 } }, "languages": { "rust": "heavy" }, "time_limits": { "java": 45 } }`
+#[derive(Deserialize, Default)]
+pub struct Profiles {
+    #[serde(default)]
+    profiles: HashMap<String, Profile>,
+    #[serde(default)]
+    languages: HashMap<String, String>,
+    /// Seconds, taking precedence over the time limit of the language's profile
+    #[serde(default)]
+    time_limits: HashMap<String, u64>,
+}
+
+impl Profiles {
+    pub fn new() -> Profiles {
+        let mut profiles = Profiles::builtin();
+
+        let path = std::path::Path::new("profiles.json");
+        if path.exists() {
+            let json = fs::read_to_string(path)
+                .expect("Unable to read profiles.json");
+            let overrides: Profiles = serde_json::from_str(&json)
+                .expect("Unable to deserialize profiles.json");
+
+            profiles.profiles.extend(overrides.profiles);
+            profiles.languages.extend(overrides.languages);
+            profiles.time_limits.extend(overrides.time_limits);
+        }
+
+        profiles
+    }
+
+    // heavier toolchains get more time, everything else shares the default
+    fn builtin() -> Profiles {
+        let mut profiles = HashMap::new();
+        profiles.insert(String::from("default"), Profile::default());
+        profiles.insert(String::from("heavy"), Profile {
+            time_limit: 60,
+            ..Profile::default()
+        });
+
+        let mut languages = HashMap::new();
+        for lang in &["rust", "haskell", "scala", "swift", "c++"] {
+            languages.insert(String::from(*lang), String::from("heavy"));
+        }
+
+        // the jvm spends a good while just starting up
+        let mut time_limits = HashMap::new();
+        time_limits.insert(String::from("java"), 45);
+
+        Profiles { profiles, languages, time_limits }
+    }
+
+    pub fn for_language(&self, language: &str) -> Profile {
+        let name = self.languages.get(language).map(|s| s.as_str()).unwrap_or("default");
+        let mut profile = match self.profiles.get(name) {
+            Some(profile) => profile.clone(),
+            None => {
+                warn!("Language '{}' maps to unknown profile '{}'", language, name);
+                self.profiles.get("default").cloned().unwrap_or_default()
+            }
+        };
+
+        if let Some(limit) = self.time_limits.get(language) {
+            profile.time_limit = *limit;
+        }
+        profile
+    }
+}
@@ -0,0 +1,38 @@
+// Curated example programs for ;example, as (name, language, source). They're
+// compiled into the binary from src/examples/ so they can't be changed at runtime,
+// unlike guild snippets. Bump PROGRAMS_VERSION whenever the set changes
+pub static PROGRAMS_VERSION: u32 = 1;
+static PROGRAMS: [(&str, &str, &str); 11] = [
+    ("fibonacci", "c++", include_str!("../examples/fibonacci.cpp")),
+    ("fibonacci", "python", include_str!("../examples/fibonacci.py")),
+    ("fibonacci", "rust", include_str!("../examples/fibonacci.rs")),
+    ("fizzbuzz", "c", include_str!("../examples/fizzbuzz.c")),
+    ("fizzbuzz", "javascript", include_str!("../examples/fizzbuzz.js")),
+    ("fizzbuzz", "python", include_str!("../examples/fizzbuzz.py")),
+    ("primes", "c++", include_str!("../examples/primes.cpp")),
+    ("primes", "python", include_str!("../examples/primes.py")),
+    ("quicksort", "c++", include_str!("../examples/quicksort.cpp")),
+    ("quicksort", "python", include_str!("../examples/quicksort.py")),
+    ("quicksort", "rust", include_str!("../examples/quicksort.rs")),
+];
+
+/// The program's source in the given language, or in the first language we have it in
+pub fn find(name: &str, language: Option<&str>) -> Option<(&'static str, &'static str)> {
+    PROGRAMS
+        .iter()
+        .filter(|(n, _, _)| *n == name)
+        .find(|(_, lang, _)| language.map(|l| l == *lang).unwrap_or(true))
+        .map(|(_, lang, source)| (*lang, *source))
+}
+
+/// Every program along with the languages it's available in, in the order they're listed
+pub fn list() -> Vec<(&'static str, Vec<&'static str>)> {
+    let mut programs: Vec<(&str, Vec<&str>)> = Vec::new();
+    for (name, lang, _) in PROGRAMS.iter() {
+        match programs.iter_mut().find(|(n, _)| n == name) {
+            Some((_, languages)) => languages.push(*lang),
+            None => programs.push((*name, vec![*lang])),
+        }
+    }
+    programs
+}
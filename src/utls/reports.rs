@@ -0,0 +1,24 @@
+use serenity::model::prelude::*;
+
+/// What we need to know about a bot output in order to report it to moderators
+pub struct OutputRecord {
+    pub requester: User,
+    pub channel_id: ChannelId,
+    pub guild_id: Option<GuildId>,
+    pub language: String,
+    pub code: String,
+    pub flagged_by: Option<User>,
+}
+
+impl OutputRecord {
+    pub fn new(msg: &Message, language: &str, code: &str) -> OutputRecord {
+        OutputRecord {
+            requester: msg.author.clone(),
+            channel_id: msg.channel_id,
+            guild_id: msg.guild_id,
+            language: String::from(language),
+            code: String::from(code),
+            flagged_by: None,
+        }
+    }
+}
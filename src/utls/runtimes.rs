@@ -0,0 +1,35 @@
+// Runtimes users pick with --runtime, for languages where the interpreter or VM
+// matters more than the flags. Each maps onto a prefix of the backend's compiler
+// names, so a runtime keeps working as the backends add new versions. An empty
+// prefix means the backend doesn't have that runtime
+static RUNTIMES: [(&str, &str, &str, &str); 11] = [
+    // (language, runtime, wandbox compiler prefix, godbolt compiler prefix)
+    ("python", "2", "cpython-2.", ""),
+    ("python", "3", "cpython-3.", "python3"),
+    ("python", "pypy", "pypy-", "pypy"),
+    ("java", "openjdk", "openjdk-", "java"),
+    ("javascript", "node", "nodejs-", ""),
+    ("javascript", "spidermonkey", "spidermonkey-", ""),
+    ("ruby", "mri", "ruby-", ""),
+    ("ruby", "mruby", "mruby-", ""),
+    ("lua", "lua", "lua-", ""),
+    ("lua", "luajit", "luajit-", ""),
+    ("c#", "mono", "mono-", ""),
+];
+
+/// Runtimes that can be picked for a language
+pub fn available(language: &str) -> Vec<&'static str> {
+    RUNTIMES
+        .iter()
+        .filter(|(lang, _, _, _)| *lang == language)
+        .map(|(_, runtime, _, _)| *runtime)
+        .collect()
+}
+
+/// The wandbox & godbolt compiler prefixes of a runtime
+pub fn prefixes(language: &str, runtime: &str) -> Option<(&'static str, &'static str)> {
+    RUNTIMES
+        .iter()
+        .find(|(lang, r, _, _)| *lang == language && r.eq_ignore_ascii_case(runtime))
+        .map(|(_, _, wandbox, godbolt)| (*wandbox, *godbolt))
+}
@@ -0,0 +1,151 @@
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use serde::*;
+
+use crate::utls::persistence::{self, Migration};
+
+// Upgrades older schedule.json formats, see persistence::Migration
+static MIGRATIONS: [Migration; 0] = [];
+
+/// A planned maintenance window, compile commands are refused while it's running
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Maintenance {
+    /// Unix timestamp of when the window opens
+    pub start: i64,
+    pub minutes: u64,
+    pub reason: String,
+}
+
+impl Maintenance {
+    pub fn starts(&self) -> DateTime<Utc> {
+        Utc.timestamp(self.start, 0)
+    }
+
+    pub fn ends(&self) -> DateTime<Utc> {
+        Utc.timestamp(self.start + self.minutes as i64 * 60, 0)
+    }
+}
+
+/// What changed the last time the schedule was checked
+#[derive(Default)]
+pub struct ScheduleUpdate {
+    pub started: Option<Maintenance>,
+    pub finished: Option<Maintenance>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Schedule {
+    /// Upcoming windows, soonest first
+    #[serde(default)]
+    pending: Vec<Maintenance>,
+    #[serde(default)]
+    active: Option<Maintenance>,
+}
+
+impl Schedule {
+    pub fn new() -> Schedule {
+        let loaded = persistence::load_versioned("schedule.json", &MIGRATIONS)
+            .expect("Unable to load schedule.json");
+        match loaded {
+            Some(store) => store,
+            None => Schedule::create_schedule(),
+        }
+    }
+
+    pub fn add(&mut self, maintenance: Maintenance) {
+        self.pending.push(maintenance);
+        self.pending.sort_by_key(|m| m.start);
+        self.write();
+    }
+
+    pub fn cancel(&mut self, index: usize) -> Option<Maintenance> {
+        if index >= self.pending.len() {
+            return None;
+        }
+
+        let removed = self.pending.remove(index);
+        self.write();
+        Some(removed)
+    }
+
+    pub fn pending(&self) -> &[Maintenance] {
+        &self.pending
+    }
+
+    pub fn active(&self) -> Option<&Maintenance> {
+        self.active.as_ref()
+    }
+
+    /// Opens & closes windows whose time has come. Windows that came and went
+    /// entirely while we were offline are dropped without ever starting
+    pub fn tick(&mut self, now: DateTime<Utc>) -> ScheduleUpdate {
+        let mut update = ScheduleUpdate::default();
+
+        if self.active.as_ref().map(|m| m.ends() <= now).unwrap_or(false) {
+            update.finished = self.active.take();
+        }
+
+        let before = self.pending.len();
+        self.pending.retain(|m| m.ends() > now);
+        if self.active.is_none() && self.pending.first().map(|m| m.starts() <= now).unwrap_or(false) {
+            let started = self.pending.remove(0);
+            self.active = Some(started.clone());
+            update.started = Some(started);
+        }
+
+        if before != self.pending.len() || update.finished.is_some() {
+            self.write();
+        }
+        update
+    }
+
+    pub fn write(&self) {
+        persistence::write_versioned("schedule.json", self, &MIGRATIONS)
+            .expect("Unable to create schedule.json!");
+    }
+
+    fn create_schedule() -> Schedule {
+        let schedule = Schedule::default();
+        schedule.write();
+        schedule
+    }
+}
+
+/// Accepts RFC 3339 times along with shorter forms like 2024-06-01T02:00Z, always in UTC
+pub fn parse_time(input: &str) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(input) {
+        return Some(time.with_timezone(&Utc));
+    }
+
+    let input = input.trim_end_matches(|c| c == 'Z' || c == 'z');
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(input, format).ok())
+        .map(|time| DateTime::from_utc(time, Utc))
+}
+
+/// Durations in minutes, written as 30m, 2h or 1h30m
+pub fn parse_duration(input: &str) -> Option<u64> {
+    let mut minutes = 0;
+    let mut number = String::new();
+    for c in input.trim().to_lowercase().chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'h' | 'm' => {
+                let value = number.parse::<u64>().ok()?;
+                minutes += if c == 'h' { value * 60 } else { value };
+                number.clear();
+            }
+            _ => return None,
+        }
+    }
+
+    // anything left over without a unit is taken as minutes
+    if !number.is_empty() {
+        minutes += number.parse::<u64>().ok()?;
+    }
+    if minutes == 0 {
+        None
+    } else {
+        Some(minutes)
+    }
+}
@@ -0,0 +1,40 @@
+use std::env;
+
+use crate::utls::compileresult::CompileResult;
+
+// Compiled on every backend once all shards are up, see events::spawn_self_test
+pub static SELF_TEST_LANGUAGE: &str = "c++";
+pub static SELF_TEST_SOURCE: &str = "#include <iostream>\n\nint main() {\n    std::cout << \"Hello, world!\\n\";\n}\n";
+static SELF_TEST_EXPECTED: &str = "Hello, world!";
+
+/// What happens when the primary backend fails the startup self-test
+#[derive(Clone, Copy, PartialEq)]
+pub enum SelfTestMode {
+    /// The self-test doesn't run at all
+    Off,
+    /// Compiling is switched off until an owner lifts the kill switch
+    KillSwitch,
+    /// The bot refuses to keep running
+    Strict,
+}
+
+/// Reads SELF_TEST, which is one of off, killswitch (the default) or strict
+pub fn mode() -> SelfTestMode {
+    match env::var("SELF_TEST").unwrap_or_default().to_lowercase().as_str() {
+        "off" => SelfTestMode::Off,
+        "strict" => SelfTestMode::Strict,
+        _ => SelfTestMode::KillSwitch,
+    }
+}
+
+/// Whether the self-test program ran and printed what it should have
+pub fn verify(result: &CompileResult) -> Result<(), String> {
+    if result.stdout.trim() == SELF_TEST_EXPECTED {
+        return Ok(());
+    }
+
+    if !result.compiler_output.trim().is_empty() {
+        return Err(format!("unexpected compiler output: {}", result.compiler_output.trim()));
+    }
+    Err(format!("expected '{}' but got '{}' ({})", SELF_TEST_EXPECTED, result.stdout.trim(), result.status()))
+}
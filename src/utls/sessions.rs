@@ -0,0 +1,52 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+
+/// What we've seen of a shard's gateway session through its ready & resume events
+pub struct ShardSession {
+    pub session_id: String,
+    /// Full identifies, the first connection included
+    pub identifies: u32,
+    pub resumes: u32,
+    pub last_connect: DateTime<Utc>,
+}
+
+// Serenity keeps a shard's session to itself, so we piece together what we
+// can from the events it hands us. Sequence numbers never make it out at all
+#[derive(Default)]
+pub struct SessionLog {
+    shards: BTreeMap<u64, ShardSession>,
+}
+
+impl SessionLog {
+    pub fn new() -> SessionLog {
+        SessionLog::default()
+    }
+
+    pub fn ready(&mut self, shard_id: u64, session_id: &str) {
+        let session = self.shards.entry(shard_id).or_insert_with(|| ShardSession {
+            session_id: String::new(),
+            identifies: 0,
+            resumes: 0,
+            last_connect: Utc::now(),
+        });
+        session.session_id = String::from(session_id);
+        session.identifies += 1;
+        session.last_connect = Utc::now();
+    }
+
+    pub fn resumed(&mut self, shard_id: u64) {
+        if let Some(session) = self.shards.get_mut(&shard_id) {
+            session.resumes += 1;
+            session.last_connect = Utc::now();
+        }
+    }
+
+    pub fn get(&self, shard_id: u64) -> Option<&ShardSession> {
+        self.shards.get(&shard_id)
+    }
+
+    pub fn shard_ids(&self) -> impl Iterator<Item = &u64> {
+        self.shards.keys()
+    }
+}
@@ -0,0 +1,287 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::*;
+
+use crate::utls::persistence::{self, Migration};
+
+// Upgrades older settings.json formats, see persistence::Migration
+static MIGRATIONS: [Migration; 0] = [];
+
+/// Settings applied to a single channel
+#[derive(Serialize, Deserialize, Default)]
+pub struct ChannelSettings {
+    #[serde(default)]
+    pub language: Option<String>,
+
+    /// Every message with a code block is compiled, no command needed
+    #[serde(default)]
+    pub auto_compile: bool,
+}
+
+/// Settings applied to an entire guild
+#[derive(Serialize, Deserialize, Default)]
+pub struct GuildSettings {
+    /// Prefix accepted on top of our default one, see events::dynamic_prefix
+    #[serde(default)]
+    pub prefix: Option<String>,
+
+    /// Language used when none is given, channel languages take priority
+    #[serde(default)]
+    pub language: Option<String>,
+
+    /// Channels compile commands are restricted to, empty means anywhere
+    #[serde(default)]
+    pub allowed_channels: HashSet<u64>,
+
+    /// Command category -> role required to use commands in that category
+    #[serde(default)]
+    pub required_roles: HashMap<String, u64>,
+
+    /// Removes the user's command message once their results have been posted
+    #[serde(default)]
+    pub auto_delete_command: bool,
+
+    /// Accent color used for info & success embeds
+    #[serde(default)]
+    pub theme: Option<i32>,
+
+    /// Members with this role get a reduced compile cooldown
+    #[serde(default)]
+    pub supporter_role: Option<u64>,
+
+    /// Whether server boosters get the reduced compile cooldown
+    #[serde(default)]
+    pub booster_exemption: bool,
+
+    /// Percentage of the normal cooldown supporters get, zero removes it entirely
+    #[serde(default)]
+    pub supporter_cooldown: Option<u8>,
+
+    /// Backend compilations are sent to when it supports the language, see BACKENDS
+    #[serde(default)]
+    pub backend: Option<String>,
+
+    /// Leaves out the tip shown with a user's very first compilation
+    #[serde(default)]
+    pub hide_tips: bool,
+
+    /// Leaves the language's logo off compilation results
+    #[serde(default)]
+    pub hide_logos: bool,
+
+    /// Leaves flags off that we'd otherwise add for headers needing them, see utls::flagpresets
+    #[serde(default)]
+    pub no_auto_flags: bool,
+
+    /// Successful compilations only get a reaction, the results are posted when asked for
+    #[serde(default)]
+    pub minimal_mode: bool,
+
+    /// Compile commands are reacted to as soon as they're accepted, until their results are in
+    #[serde(default)]
+    pub ack_reaction: bool,
+
+    /// Minutes after which our outputs are deleted, unrelated to auto_delete_command
+    #[serde(default)]
+    pub output_expiry: Option<u64>,
+
+    /// Accounts younger than this many days may not compile, recent voters & trusted guilds are exempt
+    #[serde(default)]
+    pub min_account_age: Option<u64>,
+
+    /// Channel every compilation in the guild is logged to, without the code
+    #[serde(default)]
+    pub compile_log: Option<u64>,
+
+    /// Channel our announcements (e.g. maintenance windows) are posted in
+    #[serde(default)]
+    pub announcement_channel: Option<u64>,
+
+    /// Snippet name -> the snippet, capped at MAX_SNIPPETS
+    #[serde(default)]
+    pub snippets: HashMap<String, Snippet>,
+}
+
+/// Source saved in a guild so it can be ran by name, see ;snippet
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Snippet {
+    pub language: String,
+    pub code: String,
+    pub author: u64,
+}
+
+/// Settings belonging to a single user, these follow them across guilds
+#[derive(Serialize, Deserialize, Default)]
+pub struct UserSettings {
+    /// Language -> flags used whenever the user doesn't pass any of their own
+    #[serde(default)]
+    pub flags: HashMap<String, Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Settings {
+    #[serde(default)]
+    guilds: HashMap<u64, GuildSettings>,
+    #[serde(default)]
+    channels: HashMap<u64, ChannelSettings>,
+    #[serde(default)]
+    users: HashMap<u64, UserSettings>,
+    /// Users who have already been shown the first compilation tip
+    #[serde(default)]
+    onboarded: HashSet<u64>,
+    /// Custom presence set by the owners, shown instead of our server count
+    #[serde(default)]
+    presence: Option<String>,
+}
+
+impl Settings {
+    pub fn new() -> Settings {
+        let loaded = persistence::load_versioned("settings.json", &MIGRATIONS)
+            .expect("Unable to load settings.json");
+        match loaded {
+            Some(store) => store,
+            None => Settings::create_settings(),
+        }
+    }
+
+    pub fn guild(&self, guild : u64) -> Option<&GuildSettings> {
+        self.guilds.get(&guild)
+    }
+
+    pub fn guild_mut(&mut self, guild : u64) -> &mut GuildSettings {
+        self.guilds.entry(guild).or_default()
+    }
+
+    /// Drops any role requirements referencing a role that no longer exists
+    pub fn remove_role(&mut self, guild : u64, role : u64) -> bool {
+        match self.guilds.get_mut(&guild) {
+            Some(settings) => {
+                let before = settings.required_roles.len();
+                settings.required_roles.retain(|_, r| *r != role);
+                before != settings.required_roles.len()
+            }
+            None => false,
+        }
+    }
+
+    pub fn backend(&self, guild : u64) -> Option<String> {
+        self.guild(guild).and_then(|g| g.backend.clone())
+    }
+
+    pub fn auto_delete_command(&self, guild : u64) -> bool {
+        self.guild(guild).map(|g| g.auto_delete_command).unwrap_or(false)
+    }
+
+    pub fn theme(&self, guild : u64) -> Option<i32> {
+        self.guild(guild).and_then(|g| g.theme)
+    }
+
+    pub fn channel(&self, channel : u64) -> Option<&ChannelSettings> {
+        self.channels.get(&channel)
+    }
+
+    pub fn channel_mut(&mut self, channel : u64) -> &mut ChannelSettings {
+        self.channels.entry(channel).or_default()
+    }
+
+    pub fn channel_language(&self, channel : u64) -> Option<String> {
+        self.channel(channel).and_then(|c| c.language.clone())
+    }
+
+    /// The language used when none is given, the channel's if it has one & otherwise the guild's
+    pub fn default_language(&self, guild : u64, channel : u64) -> Option<String> {
+        self.channel_language(channel)
+            .or_else(|| self.guild(guild).and_then(|g| g.language.clone()))
+    }
+
+    pub fn prefix(&self, guild : u64) -> Option<String> {
+        self.guild(guild).and_then(|g| g.prefix.clone())
+    }
+
+    pub fn auto_compile(&self, channel : u64) -> bool {
+        self.channel(channel).map(|c| c.auto_compile).unwrap_or(false)
+    }
+
+    pub fn snippet(&self, guild : u64, name : &str) -> Option<&Snippet> {
+        self.guild(guild).and_then(|g| g.snippets.get(name))
+    }
+
+    pub fn tips_enabled(&self, guild : u64) -> bool {
+        self.guild(guild).map(|g| !g.hide_tips).unwrap_or(true)
+    }
+
+    /// Every channel subscribed to our announcements
+    pub fn announcement_channels(&self) -> Vec<u64> {
+        self.guilds.values().filter_map(|g| g.announcement_channel).collect()
+    }
+
+    pub fn output_expiry(&self, guild : u64) -> Option<u64> {
+        self.guild(guild).and_then(|g| g.output_expiry)
+    }
+
+    pub fn compile_log(&self, guild : u64) -> Option<u64> {
+        self.guild(guild).and_then(|g| g.compile_log)
+    }
+
+    pub fn min_account_age(&self, guild : u64) -> Option<u64> {
+        self.guild(guild).and_then(|g| g.min_account_age)
+    }
+
+    pub fn auto_flags_enabled(&self, guild : u64) -> bool {
+        self.guild(guild).map(|g| !g.no_auto_flags).unwrap_or(true)
+    }
+
+    pub fn minimal_mode(&self, guild : u64) -> bool {
+        self.guild(guild).map(|g| g.minimal_mode).unwrap_or(false)
+    }
+
+    pub fn ack_reaction(&self, guild : u64) -> bool {
+        self.guild(guild).map(|g| g.ack_reaction).unwrap_or(false)
+    }
+
+    pub fn logos_enabled(&self, guild : u64) -> bool {
+        self.guild(guild).map(|g| !g.hide_logos).unwrap_or(true)
+    }
+
+    pub fn presence(&self) -> Option<&str> {
+        self.presence.as_deref()
+    }
+
+    pub fn set_presence(&mut self, presence : Option<String>) {
+        self.presence = presence;
+    }
+
+    /// Marks the user as having seen the first compilation tip, returns false if they already had
+    pub fn onboard(&mut self, user : u64) -> bool {
+        self.onboarded.insert(user)
+    }
+
+    pub fn user_mut(&mut self, user : u64) -> &mut UserSettings {
+        self.users.entry(user).or_default()
+    }
+
+    pub fn user_flags(&self, user : u64, language : &str) -> Option<&Vec<String>> {
+        self.users.get(&user).and_then(|u| u.flags.get(&language.to_lowercase()))
+    }
+
+    /// Removes the user's flags for a language, returns false if there were none
+    pub fn clear_user_flags(&mut self, user : u64, language : &str) -> bool {
+        let removed = match self.users.get_mut(&user) {
+            Some(u) => u.flags.remove(&language.to_lowercase()).is_some(),
+            None => false,
+        };
+        self.users.retain(|_, u| !u.flags.is_empty());
+        removed
+    }
+
+    pub fn write(&self) {
+        persistence::write_versioned("settings.json", self, &MIGRATIONS)
+            .expect("Unable to create settings.json!");
+    }
+
+    fn create_settings() -> Settings {
+        let settings = Settings::default();
+        settings.write();
+        settings
+    }
+}
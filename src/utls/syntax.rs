@@ -0,0 +1,66 @@
+// Flags that stop a toolchain after it has checked the code, skipping codegen
+// & linking. Languages missing from here get a regular compilation instead
+static SYNTAX_FLAGS: [(&str, &str); 4] = [
+    ("c++", "-fsyntax-only"),
+    ("c", "-fsyntax-only"),
+    ("rust", "--emit=metadata"),
+    ("haskell", "-fno-code"),
+];
+
+/// The compiler flag giving a syntax-only pass for the language, if it has one
+pub fn syntax_flag(language: &str) -> Option<&'static str> {
+    let language = language.to_lowercase();
+    SYNTAX_FLAGS
+        .iter()
+        .find(|(lang, _)| *lang == language)
+        .map(|(_, flag)| *flag)
+}
+
+// Interpreted languages have no flag for it, so their code is handed on stdin to a
+// program of ours that compiles it without running it. Syntax errors fail the program
+static CHECKERS: [(&str, &str); 4] = [
+    (
+        "python",
+        "import sys\ntry:\n    compile(sys.stdin.read(), \"prog.py\", \"exec\")\nexcept SyntaxError as e:\n    sys.exit(\"{}:{}: {}\".format(e.filename, e.lineno, e.msg))\n",
+    ),
+    (
+        "ruby",
+        "begin\n  RubyVM::InstructionSequence.compile(STDIN.read, \"prog.rb\")\nrescue SyntaxError => e\n  abort e.message\nend\n",
+    ),
+    (
+        "javascript",
+        "const vm = require(\"vm\");\ntry {\n  new vm.Script(require(\"fs\").readFileSync(0, \"utf8\"), { filename: \"prog.js\" });\n} catch (e) {\n  console.error(String(e.stack).split(\"\\n    at \")[0]);\n  process.exit(1);\n}\n",
+    ),
+    (
+        "lua",
+        "local _, e = load(io.read(\"*a\"), \"=prog.lua\")\nif e then\n  io.stderr:write(e, \"\\n\")\n  os.exit(1)\nend\n",
+    ),
+];
+
+/// The program checking the language's syntax, it expects the code on stdin
+pub fn checker(language: &str) -> Option<&'static str> {
+    let language = language.to_lowercase();
+    CHECKERS
+        .iter()
+        .find(|(lang, _)| *lang == language)
+        .map(|(_, program)| *program)
+}
+
+// Flags that have a toolchain stop once the code is built, before there's a
+// program the backend could run. Syntax-only flags work just as well
+static COMPILE_ONLY_FLAGS: [(&str, &str); 4] = [
+    ("c++", "-c"),
+    ("c", "-c"),
+    ("rust", "--emit=obj"),
+    ("haskell", "-c"),
+];
+
+/// The compiler flag that builds the language's code without producing a program, if it has one
+pub fn compile_only_flag(language: &str) -> Option<&'static str> {
+    let language = language.to_lowercase();
+    COMPILE_ONLY_FLAGS
+        .iter()
+        .find(|(lang, _)| *lang == language)
+        .map(|(_, flag)| *flag)
+        .or_else(|| syntax_flag(&language))
+}
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::utls::constants::LOG_THROTTLE_WINDOW;
+
+struct Window {
+    started: Instant,
+    suppressed: u32,
+}
+
+/// Collapses repeats of the same warning into one summary per window, so an
+/// outage of something we post to doesn't bury the rest of our logs
+#[derive(Default)]
+pub struct LogThrottle {
+    windows: HashMap<&'static str, Window>,
+}
+
+impl LogThrottle {
+    pub fn new() -> LogThrottle {
+        LogThrottle::default()
+    }
+
+    /// Whether a warning under this key should be logged, repeats within the window are only counted
+    pub fn allow(&mut self, key: &'static str) -> bool {
+        match self.windows.get_mut(key) {
+            Some(window) => {
+                window.suppressed += 1;
+                false
+            }
+            None => {
+                self.windows.insert(key, Window {
+                    started: Instant::now(),
+                    suppressed: 0,
+                });
+                true
+            }
+        }
+    }
+
+    /// Closes windows that have run their course, returning how often each key was suppressed
+    pub fn flush(&mut self) -> Vec<(&'static str, u32)> {
+        let expiry = Duration::from_secs(LOG_THROTTLE_WINDOW);
+        let mut summaries = Vec::new();
+        self.windows.retain(|key, window| {
+            if window.started.elapsed() < expiry {
+                return true;
+            }
+            if window.suppressed > 0 {
+                summaries.push((*key, window.suppressed));
+            }
+            false
+        });
+        summaries
+    }
+}
+
+/// Logs the warning unless one with the same key was already logged this window
+pub fn warn(throttle: &Mutex<LogThrottle>, key: &'static str, message: &str) {
+    if throttle.lock().unwrap().allow(key) {
+        warn!("{}", message);
+    }
+}
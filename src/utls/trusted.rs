@@ -0,0 +1,53 @@
+use std::env;
+
+use crate::utls::constants::{DEFAULT_TRUSTED_COOLDOWN, DEFAULT_TRUSTED_RATE_LIMIT_MULTIPLIER, DEFAULT_TRUSTED_SAMPLE_MULTIPLIER};
+
+// Partner guilds listed in TRUSTED_GUILDS (comma separated ids) sit between fully
+// open and locked down, they get a shorter compile cooldown, a higher guild wide
+// rate limit and need more failing compilations before they're reported as a
+// possible source of abuse
+
+pub fn is_trusted(guild_id: u64) -> bool {
+    match env::var("TRUSTED_GUILDS") {
+        Ok(list) => list
+            .split(',')
+            .filter_map(|id| id.trim().parse::<u64>().ok())
+            .any(|id| id == guild_id),
+        Err(_) => false,
+    }
+}
+
+/// Percentage of the normal compile cooldown members of trusted guilds get
+pub fn trusted_cooldown() -> u8 {
+    env::var("TRUSTED_COOLDOWN")
+        .ok()
+        .and_then(|v| v.parse::<u8>().ok())
+        .filter(|v| *v <= 100)
+        .unwrap_or(DEFAULT_TRUSTED_COOLDOWN)
+}
+
+/// How many times more compilations a guild needs before its failure rate is judged
+pub fn sample_multiplier(guild_id: u64) -> u32 {
+    if !is_trusted(guild_id) {
+        return 1;
+    }
+
+    env::var("TRUSTED_SAMPLE_MULTIPLIER")
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_TRUSTED_SAMPLE_MULTIPLIER)
+}
+
+/// How many times more requests per minute a guild may send, see limiter::guild_rate_limit
+pub fn rate_limit_multiplier(guild_id: u64) -> usize {
+    if !is_trusted(guild_id) {
+        return 1;
+    }
+
+    env::var("TRUSTED_RATE_LIMIT_MULTIPLIER")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_TRUSTED_RATE_LIMIT_MULTIPLIER)
+}